@@ -29,6 +29,9 @@ const MASK12: u32 = 4095;
 struct VertexInput {
     @builtin(instance_index) instance_index: u32,
     @location(0) voxel_data: vec2<u32>,
+#ifdef SHAPE_DATA
+    @location(1) shape_data: u32,
+#endif
 };
 
 struct CustomVertexOutput {
@@ -61,6 +64,10 @@ fn normal_from_id(id: u32) -> vec3<f32> {
         case 5u {
             n = vec3(0.0, 0.0, -1.0);
         }
+        // diagonal quads of plants are lit like the ground they stand on
+        case 6u {
+            n = vec3(0.0, 1.0, 0.0);
+        }
         default {
             n = vec3(0.0);
         }
@@ -87,6 +94,13 @@ fn vertex(vertex: VertexInput) -> CustomVertexOutput {
     var u = f32((vertex_info >> 18) & MASK6);
     var v = f32((vertex_info >> 24) & MASK6);
     var position = vec4(x, y, z, 1.0);
+#ifdef SHAPE_DATA
+    // non-cube shapes are placed and textured in sixteenths of a block
+    var shape_data = vertex.shape_data;
+    position += vec4(f32(shape_data & MASK5), f32((shape_data >> 5) & MASK5), f32((shape_data >> 10) & MASK5), 0.0) / 16.0;
+    u += f32((shape_data >> 15) & MASK5) / 16.0;
+    v += f32((shape_data >> 20) & MASK5) / 16.0;
+#endif
     
     // Quad specific information
     var quad_info = vertex.voxel_data.y;
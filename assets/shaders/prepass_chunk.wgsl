@@ -20,6 +20,7 @@
 
 // Bit masks matching the voxel vertex encoding in mesh_logic.rs / chunk.wgsl
 const MASK3: u32 = 7u;
+const MASK5: u32 = 31u;
 const MASK6: u32 = 63u;
 
 // Decode face normal from a 3-bit ID (same encoding as the main chunk shader)
@@ -31,6 +32,7 @@ fn normal_from_id(id: u32) -> vec3<f32> {
         case 3u { return vec3(-1.0, 0.0, 0.0); }  // -X (left)
         case 4u { return vec3(0.0, 0.0, 1.0); }   // +Z (forward)
         case 5u { return vec3(0.0, 0.0, -1.0); }  // -Z (back)
+        case 6u { return vec3(0.0, 1.0, 0.0); }   // plant diagonals, lit as +Y
         default { return vec3(0.0); }
     }
 }
@@ -39,6 +41,10 @@ fn normal_from_id(id: u32) -> vec3<f32> {
 struct VertexInput {
     @builtin(instance_index) instance_index: u32,
     @location(0) voxel_data: vec2<u32>,
+#ifdef SHAPE_DATA
+    // Offset of the vertex in its block for non-cube shapes, in sixteenths (5 bits per axis)
+    @location(1) shape_data: u32,
+#endif
 }
 
 struct VertexOutput {
@@ -67,7 +73,11 @@ fn vertex(vertex: VertexInput) -> VertexOutput {
     let x = f32(vertex_info & MASK6);
     let y = f32((vertex_info >> 6u) & MASK6);
     let z = f32((vertex_info >> 12u) & MASK6);
-    let local_position = vec4(x, y, z, 1.0);
+    var local_position = vec4(x, y, z, 1.0);
+#ifdef SHAPE_DATA
+    let shape_data = vertex.shape_data;
+    local_position += vec4(f32(shape_data & MASK5), f32((shape_data >> 5u) & MASK5), f32((shape_data >> 10u) & MASK5), 0.0) / 16.0;
+#endif
 
     let world_from_local = get_world_from_local(vertex.instance_index);
     out.world_position = mesh_position_local_to_world(world_from_local, local_position);
@@ -1,9 +1,12 @@
 use bevy::prelude::*;
 use bevy::tasks::AsyncComputeTaskPool;
 use crossbeam::channel::{Receiver, Sender, unbounded};
-use rb_generation::{Biome, TerrainGenerator};
+use rb_generation::{
+    Biome, GEN_MARGIN, GenStage, Pipeline, TerrainGenerator, WorldMeta, WorldPreset,
+};
 use rb_pos::{ChunkPos2d, Pos2d};
-use rb_world::{ColUnloadEvent, VoxelWorld, WorldRng, chunk_area};
+use rb_world::{ColUnloadEvent, VoxelWorld, chunk_area};
+use std::collections::HashSet;
 const LOAD_RADIUS: u32 = 6;
 
 pub struct BiomeTerrainLoaderPlugin;
@@ -29,7 +32,7 @@ fn setup_recievers(mut commands: Commands) {
 
 fn setup_load_thread(
     world: Res<VoxelWorld>,
-    world_rng: Res<WorldRng>,
+    world_meta: Res<WorldMeta>,
     target_biome: Res<TargetBiome>,
     unload_sender: Res<ColUnloadSender>,
 ) {
@@ -39,63 +42,43 @@ fn setup_load_thread(
     println!("Loading biome {:?}", target_biome.0);
     let thread_pool = AsyncComputeTaskPool::get();
     let load_world = world.clone();
-    let seed_value = world_rng.seed;
+    let seed_value = world_meta.seed;
+    let erosion = world_meta.erosion;
     let biome = target_biome.0;
     let unload_sender = unload_sender.0.clone();
     thread_pool
         .spawn(async move {
-            // first unload anything that might be loaded
-            while let Some(col) = load_world.loaded_columns.pop_back() {
-                load_world.unload_col(*col);
-                if unload_sender.send(*col).is_err() {
-                    // This means the game is shutting down, so we break the loop
-                    warn!("ColUnloadsReciever channel is closed, stopping terrain thread");
-                    break;
-                }
-            }
-            // Deal with unloaded world columns that have data
-            // (happens when a structure generate blocks in a chunk that was not supposed to be loaded)
-            while let Some(col) = load_world.unloaded_columns.pop_back() {
-                load_world.unload_col(*col);
-                if unload_sender.send(*col).is_err() {
+            // first unload anything that might be loaded, including partially generated columns
+            let loaded_cols = load_world
+                .chunks
+                .iter()
+                .map(|entry| ChunkPos2d::from(*entry.key()))
+                .collect::<HashSet<_>>();
+            for col in loaded_cols {
+                load_world.unload_col(col);
+                if unload_sender.send(col).is_err() {
                     // This means the game is shutting down, so we break the loop
                     warn!("ColUnloadsReciever channel is closed, stopping terrain thread");
                     break;
                 }
             }
             // load new terrain
-            let terrain_gen = TerrainGenerator::new(seed_value as u32);
-            let avg_params = terrain_gen
-                .biome_params_at(Pos2d::default())
-                .average(terrain_gen.biomes_points.parameters);
-            let ideal_biome_params = terrain_gen
-                .biomes_points
-                .points
-                .iter()
-                .find(|(_, b)| *b == biome)
-                .unwrap()
-                .0;
-            let mut bias_params = avg_params.clone();
-            bias_params
-                .iter_mut()
-                .zip(ideal_biome_params.iter())
-                .for_each(|(b, i)| *b = i - *b);
-            let mut col_to_load =
-                chunk_area(&Pos2d::default(), LOAD_RADIUS as i32).collect::<Vec<_>>();
-            col_to_load.sort_by_key(|pos| pos.dist(Pos2d::default()));
-            for col in col_to_load {
-                let mut col_params = terrain_gen.biome_params_at(col);
-                for (i, param) in terrain_gen.biomes_points.parameters.iter().enumerate() {
-                    col_params
-                        .0
-                        .get_mut(param)
-                        .unwrap()
-                        .iter_mut()
-                        .for_each(|v| *v += bias_params[i]);
-                }
-                load_world.loaded_columns.insert(col);
-                terrain_gen.generate_with_params(&load_world, col.into(), col_params);
-                load_world.mark_change_col(col);
+            let terrain_gen = TerrainGenerator::new(seed_value, WorldPreset::SingleBiome(biome))
+                .with_erosion(erosion);
+            let center = Pos2d::default();
+            let pipeline = Pipeline::default();
+            pipeline.update_area(
+                chunk_area(&center, LOAD_RADIUS as i32 + GEN_MARGIN)
+                    .filter_map(|col| {
+                        let dist = col.dist(center);
+                        GenStage::target_at(dist - LOAD_RADIUS as i32)
+                            .map(|target| (col, (target, dist)))
+                    })
+                    .collect(),
+            );
+            while let Some(mut job) = pipeline.try_next_job() {
+                terrain_gen.run_stage(&load_world, &mut job);
+                pipeline.finish(job);
             }
         })
         .detach();
@@ -4,11 +4,14 @@ use crate::{auto_camera::AutoCameraPlugin, biome_terrain_loader::BiomeTerrainLoa
 use bevy::{image::*, prelude::*, window::PresentMode};
 use crossbeam::channel::unbounded;
 use rand_chacha::{ChaCha8Rng, rand_core::SeedableRng};
+use rb_generation::WorldMeta;
 use rb_render::{MeshOrderReceiver, MeshOrderSender, RenderPlugin, TextureLoadPlugin};
 use rb_world::{VoxelWorld, WorldRng};
-const SEED: u64 = 42;
 
 fn main() {
+    // The preset is ignored, the editor generates the biome it's looking at
+    let world_meta =
+        WorldMeta::from_args(std::env::args().skip(1)).unwrap_or_else(|err| panic!("{err:#}"));
     let mut app = App::new();
     let (mesh_order_sender, mesh_order_receiver) = unbounded();
     app.insert_resource(VoxelWorld::new(mesh_order_sender.clone()))
@@ -40,9 +43,10 @@ fn main() {
                 }),
         )
         .insert_resource(WorldRng {
-            seed: SEED,
-            rng: ChaCha8Rng::seed_from_u64(SEED),
+            seed: world_meta.seed,
+            rng: ChaCha8Rng::seed_from_u64(world_meta.seed),
         })
+        .insert_resource(world_meta)
         .add_plugins(AutoCameraPlugin)
         .add_plugins(BiomeTerrainLoaderPlugin)
         .add_plugins(TextureLoadPlugin)
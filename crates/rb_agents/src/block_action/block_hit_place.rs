@@ -1,16 +1,12 @@
 use crate::game_state::{CursorGrabbed, GameUiState, SelectedHotbarSlot};
-use crate::{Action, TargetBlock};
+use crate::{Action, DROPPED_ITEM_SIZE, TargetBlock, spawn_dropped_item};
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
-use rand::RngExt;
-use rb_block::Block;
+use rb_block::{Block, BlockState, Stage};
 use rb_camera::{FpsCam, PlayerControlled};
-use rb_items::{
-    BlockLootTable, DropQuantity, FiringTable, InventoryTrait, Item, ItemGet, ItemHolder,
-    LootEntry, Stack,
-};
+use rb_items::{BlockLootTable, FiringTable, Item, ItemHolder, LootEntry, Stack};
 use rb_world::WorldRng;
-use rb_world::{BlockEntities, BlockPos, Realm, VoxelWorld};
+use rb_world::{BlockEntities, BlockPos, BlockPos2d, Realm, VoxelWorld};
 use std::fs;
 use std::iter::zip;
 use std::time::{Duration, Instant};
@@ -68,9 +64,13 @@ pub struct BlockLootAction {
     pub action_type: BlockActionType,
     pub time_left: f32,
     pub break_entry: LootEntry,
+    /// The hotbar slot and the tool the action started with
+    pub slot: usize,
+    pub tool: Option<Item>,
 }
 
 const TARGET_DIST: f32 = 10.;
+const DROP_POP_VELOCITY: Vec3 = Vec3::new(0., 5., 0.);
 const EDGES_ANCHORS: [Vec3; 4] = [
     Vec3::ZERO,
     Vec3::new(1., 1., 0.),
@@ -85,7 +85,7 @@ const EDGES_LINES: [Vec3; 4] = [
 ];
 
 #[derive(Component)]
-pub struct BlockAttached(BlockPos);
+pub struct BlockAttached(pub BlockPos);
 
 #[derive(Component)]
 pub struct Renewable {
@@ -156,6 +156,7 @@ fn break_action(
     mut col_entities: ResMut<BlockEntities>,
     mut world_rng: ResMut<WorldRng>,
     block_entt_query: Query<&BlockAttached>,
+    mut container_query: Query<&mut ItemHolder, Without<TargetBlock>>,
 ) {
     for (player, target_block_opt, mut hotbar, action, opt_looting) in block_action_query.iter_mut()
     {
@@ -171,14 +172,26 @@ fn break_action(
             let Some(target_block) = &target_block_opt.0 else {
                 continue;
             };
-            let block = world.get_block(target_block.pos);
-            if !block.is_targetable() {
+            let state = world.get_block_state(target_block.pos);
+            let block = state.block;
+            // crops can only be harvested once ripe
+            if !block.is_targetable()
+                || (matches!(action_type, BlockActionType::Harvesting)
+                    && state.next_stage().is_some())
+            {
                 continue;
             }
-            let tool_used = hotbar.get(selected_slot.0).item();
+            let tool_used = hotbar.get(selected_slot.0).item().copied();
+            let biome = world
+                .climate_at(BlockPos2d::from(target_block.pos))
+                .map(|climate| climate.biome);
             let break_entry = match action_type {
-                BlockActionType::Breaking => block_break_table.0.get(tool_used, &block),
-                BlockActionType::Harvesting => block_harvest_table.0.get(tool_used, &block),
+                BlockActionType::Breaking => {
+                    block_break_table.0.get(tool_used.as_ref(), &block, biome)
+                }
+                BlockActionType::Harvesting => {
+                    block_harvest_table.0.get(tool_used.as_ref(), &block, biome)
+                }
             };
             let Some(hardness) = break_entry.hardness else {
                 continue;
@@ -189,6 +202,8 @@ fn break_action(
                 action_type,
                 time_left: hardness,
                 break_entry,
+                slot: selected_slot.0,
+                tool: tool_used,
             });
             continue;
         };
@@ -207,6 +222,16 @@ fn break_action(
         let Some(target_block) = &target_block_opt.0 else {
             continue;
         };
+        // tools lose a point of durability per second of use, at least one per block,
+        // the tool wears even if another slot got selected in the meantime
+        let wear = looting
+            .break_entry
+            .hardness
+            .map_or(1, |hardness| hardness.ceil().max(1.) as u32);
+        let tool_slot = hotbar.get_mut(looting.slot);
+        if tool_slot.item() == looting.tool.as_ref() {
+            tool_slot.wear(wear);
+        }
         match looting.action_type {
             BlockActionType::Breaking => {
                 world.set_block(target_block.pos, Block::Air);
@@ -215,12 +240,32 @@ fn break_action(
                         if block_pos.0 == target_block.pos {
                             commands.entity(entity).despawn();
                         }
+                    } else if let Ok(mut container) = container_query.get_mut(entity) {
+                        // chests and furnaces spill what they hold
+                        let block_center = Vec3::from(target_block.pos) + Vec3::splat(0.5);
+                        for stack in container.take_all() {
+                            spawn_dropped_item(
+                                &mut commands,
+                                &time,
+                                stack,
+                                block_center,
+                                target_block.pos.realm,
+                                DROP_POP_VELOCITY,
+                                Duration::ZERO,
+                            );
+                        }
+                        commands.entity(entity).despawn();
+                        col_entities.remove(&target_block.pos);
                     }
                 }
             }
+            // harvesting a crop replants it
+            BlockActionType::Harvesting if world.get_block(target_block.pos).is_crop() => {
+                world.update_state(target_block.pos, |crop| crop.with_stage(Stage::Sown));
+            }
             BlockActionType::Harvesting => {
                 let depleted = world.get_block(target_block.pos).depleted();
-                world.set_block(target_block.pos, depleted);
+                world.turn_into(target_block.pos, depleted);
                 if let Some(renewal_minutes) = depleted.renewal_minutes() {
                     let renew_entt = commands
                         .spawn((
@@ -236,24 +281,24 @@ fn break_action(
                 }
             }
         }
-        if let Some(drop) = looting.break_entry.drops {
-            let drop_quantity = looting
-                .break_entry
-                .quantity
-                .as_ref()
-                .unwrap_or(&DropQuantity::Fixed(1));
-            let quantity = match drop_quantity {
-                DropQuantity::Fixed(q) => *q,
-                DropQuantity::Range { min, max } => {
-                    let rng = &mut world_rng.rng;
-                    rng.random_range(*min..=*max)
-                }
-            };
-            let ItemHolder::Inventory(ref mut hotbar) = *hotbar else {
-                continue;
-            };
-            if hotbar.try_add(Stack::Some(drop, quantity)).is_none() {
-                commands.trigger(ItemGet { entity: player });
+        // the drops pop out of the block, or on top of it if it's still there after a harvest
+        let drop_height = if world.get_block(target_block.pos).is_traversable() {
+            0.5
+        } else {
+            1. + DROPPED_ITEM_SIZE
+        };
+        let drop_center = Vec3::from(target_block.pos) + Vec3::new(0.5, drop_height, 0.5);
+        for (drop, quantity) in looting.break_entry.roll(&mut world_rng.rng) {
+            for stack in Stack::new_many(drop, quantity) {
+                spawn_dropped_item(
+                    &mut commands,
+                    &time,
+                    stack,
+                    drop_center,
+                    target_block.pos.realm,
+                    DROP_POP_VELOCITY,
+                    Duration::ZERO,
+                );
             }
         }
         commands.entity(player).remove::<BlockLootAction>();
@@ -267,6 +312,7 @@ fn place_block(
     mut commands: Commands,
     world: Res<VoxelWorld>,
     mut block_action_query: Query<(&TargetBlock, &mut ItemHolder, &ActionState<Action>)>,
+    player_cam: Query<&GlobalTransform, With<FpsCam>>,
     selected_slot: Res<SelectedHotbarSlot>,
 ) {
     for (target_block_opt, mut hotbar, action) in block_action_query.iter_mut() {
@@ -287,7 +333,13 @@ fn place_block(
                 continue;
             }
         };
-        if !world.set_block_safe(pos, block) {
+        let looking = player_cam
+            .single()
+            .map_or(Vec3::NEG_Z, |transform| *transform.forward());
+        let state = BlockState::from(block).placed(target_block.normal, looking);
+        if !block.can_be_placed_on(world.get_block(pos + (0, -1, 0)))
+            || !world.set_block_safe(pos, state)
+        {
             // If the block couldn't be added we add it back
             hotbar
                 .get_mut(selected_slot.0)
@@ -306,7 +358,7 @@ fn renew_block(
     let now = Instant::now();
     for (entity, renewable, pos) in renewables.iter() {
         if now >= renewable.renew_after {
-            world.set_block(pos.0, world.get_block(pos.0).renewed());
+            world.turn_into(pos.0, world.get_block(pos.0).renewed());
             commands.entity(entity).despawn();
         }
     }
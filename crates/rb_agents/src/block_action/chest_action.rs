@@ -0,0 +1,237 @@
+use super::BlockPlaced;
+use crate::chest_state::OpenChest;
+use crate::game_state::GameUiState;
+use crate::{Action, TargetBlock};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use rb_block::BlockState;
+use rb_camera::PlayerControlled;
+use rb_items::{ItemHolder, chest_slots};
+use rb_world::{
+    BlockEntities, BlockPos, ChunkPos2d, ColLoadEvent, ColUnloadEvent, VoxelWorld,
+    unload_block_entities,
+};
+use std::collections::HashMap;
+
+pub struct ChestActionPlugin;
+
+impl Plugin for ChestActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StoredChests>()
+            .add_observer(place_chest)
+            .add_systems(Update, open_chest_menu.run_if(in_state(GameUiState::None)))
+            .add_systems(
+                Update,
+                (
+                    store_unloaded_chests.before(unload_block_entities),
+                    restore_loaded_chests.after(unload_block_entities),
+                ),
+            );
+    }
+}
+
+/// A block that stores items, its slots are in the ItemHolder of the entity
+#[derive(Debug, Clone, Component)]
+pub struct Chest {
+    pub name: String,
+    pub block_pos: BlockPos,
+    /// The block of the chest, with its facing, to put it back after its column is generated again
+    pub block: BlockState,
+}
+
+/// The chests of the unloaded columns with what they hold.
+/// Columns are generated again when they load, so the chests are put back in them once they're loaded.
+#[derive(Default, Resource)]
+pub struct StoredChests(HashMap<ChunkPos2d, Vec<(Chest, ItemHolder)>>);
+
+fn spawn_chest(
+    commands: &mut Commands,
+    block_entities: &mut BlockEntities,
+    chest: Chest,
+    slots: ItemHolder,
+) -> Entity {
+    let pos = chest.block_pos;
+    let ent = commands.spawn((chest, slots)).id();
+    block_entities.add(&pos, ent);
+    ent
+}
+
+fn place_chest(
+    block_placed: On<BlockPlaced>,
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    mut block_entities: ResMut<BlockEntities>,
+) {
+    let pos = block_placed.event().0;
+    let block = world.get_block_state(pos);
+    let slots = block.block.storage() as usize;
+    if slots == 0 {
+        return;
+    }
+    let chest = Chest {
+        name: block.block.to_string(),
+        block_pos: pos,
+        block,
+    };
+    spawn_chest(
+        &mut commands,
+        &mut block_entities,
+        chest,
+        chest_slots(slots),
+    );
+}
+
+/// Takes the slots out of the chests of the unloaded columns before their entities are despawned
+fn store_unloaded_chests(
+    mut unload_events: MessageReader<ColUnloadEvent>,
+    mut chest_query: Query<(&Chest, &mut ItemHolder)>,
+    mut stored_chests: ResMut<StoredChests>,
+) {
+    for ColUnloadEvent(col) in unload_events.read() {
+        for (chest, mut slots) in chest_query.iter_mut() {
+            if ChunkPos2d::from(chest.block_pos) != *col {
+                continue;
+            }
+            let slots = std::mem::replace(&mut *slots, chest_slots(0));
+            stored_chests
+                .0
+                .entry(*col)
+                .or_default()
+                .push((chest.clone(), slots));
+        }
+    }
+}
+
+fn restore_loaded_chests(
+    mut commands: Commands,
+    mut load_events: MessageReader<ColLoadEvent>,
+    world: Res<VoxelWorld>,
+    mut stored_chests: ResMut<StoredChests>,
+    mut block_entities: ResMut<BlockEntities>,
+) {
+    for ColLoadEvent(col) in load_events.read() {
+        let Some(chests) = stored_chests.0.remove(col) else {
+            continue;
+        };
+        for (chest, slots) in chests {
+            world.set_block(chest.block_pos, chest.block);
+            spawn_chest(&mut commands, &mut block_entities, chest, slots);
+        }
+    }
+}
+
+fn open_chest_menu(
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    block_action_query: Query<(&TargetBlock, &ActionState<Action>), With<PlayerControlled>>,
+    chest_query: Query<&Chest>,
+    mut block_entities: ResMut<BlockEntities>,
+    mut next_ui_state: ResMut<NextState<GameUiState>>,
+    mut chest_menu: ResMut<OpenChest>,
+) {
+    for (target_block_opt, action) in block_action_query.iter() {
+        if !action.just_pressed(&Action::Modify) {
+            continue;
+        }
+        let Some(target_block) = &target_block_opt.0 else {
+            continue;
+        };
+        let block = world.get_block_state(target_block.pos);
+        let slots = block.block.storage() as usize;
+        if slots == 0 {
+            continue;
+        }
+        // the chest keeps its entity, and what's in it, until it's broken,
+        // the entity is stored away while its column is unloaded
+        let chest_ent = match block_entities.get(&target_block.pos) {
+            Some(ent) if chest_query.contains(ent) => ent,
+            ent_opt => {
+                let mut ent_commands = match ent_opt {
+                    Some(ent) => commands.entity(ent),
+                    None => commands.spawn_empty(),
+                };
+                let ent = ent_commands
+                    .insert(Chest {
+                        name: block.block.to_string(),
+                        block_pos: target_block.pos,
+                        block,
+                    })
+                    .insert(chest_slots(slots))
+                    .id();
+                block_entities.add(&target_block.pos, ent);
+                ent
+            }
+        };
+        chest_menu.0 = Some(chest_ent);
+        next_ui_state.set(GameUiState::ChestMenu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam::channel::unbounded;
+    use rb_block::Block;
+    use rb_items::{Item, Stack};
+    use rb_world::Realm;
+
+    #[test]
+    fn chests_survive_unload() {
+        let (chunk_changes, _chunk_changes_recv) = unbounded();
+        let world = VoxelWorld::new(chunk_changes);
+        let mut app = App::new();
+        app.add_message::<ColUnloadEvent>()
+            .add_message::<ColLoadEvent>()
+            .insert_resource(world.clone())
+            .insert_resource(BlockEntities::default())
+            .init_resource::<StoredChests>()
+            .add_systems(
+                Update,
+                (
+                    store_unloaded_chests,
+                    unload_block_entities,
+                    restore_loaded_chests,
+                )
+                    .chain(),
+            );
+        let pos = BlockPos {
+            x: 3,
+            y: 40,
+            z: 5,
+            realm: Realm::Overworld,
+        };
+        let col = ChunkPos2d::from(pos);
+        let block = BlockState {
+            block: Block::Chest,
+            state: 2,
+        };
+        world.loaded_columns.insert(col);
+        world.set_block(pos, block);
+        let mut slots = chest_slots(Block::Chest.storage() as usize);
+        slots.try_add(Stack::Some(Item::Stick, 3));
+        let chest = Chest {
+            name: Block::Chest.to_string(),
+            block_pos: pos,
+            block,
+        };
+        let chest_ent = app.world_mut().spawn((chest, slots)).id();
+        app.world_mut()
+            .resource_mut::<BlockEntities>()
+            .add(&pos, chest_ent);
+
+        world.unload_col(col);
+        app.world_mut().write_message(ColUnloadEvent(col));
+        app.update();
+        assert_eq!(app.world().resource::<BlockEntities>().get(&pos), None);
+        assert_eq!(world.get_block(pos), Block::Air);
+
+        // the column is generated again without the chest
+        world.loaded_columns.insert(col);
+        app.world_mut().write_message(ColLoadEvent(col));
+        app.update();
+        assert_eq!(world.get_block_state(pos), block);
+        let chest_ent = app.world().resource::<BlockEntities>().get(&pos).unwrap();
+        let slots = app.world().get::<ItemHolder>(chest_ent).unwrap();
+        assert_eq!(slots.get(0), &Stack::Some(Item::Stick, 3));
+    }
+}
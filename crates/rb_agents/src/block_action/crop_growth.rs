@@ -0,0 +1,178 @@
+use super::{BlockAttached, BlockPlaced};
+use crate::game_state::{GameUiState, SelectedHotbarSlot};
+use crate::{Action, TargetBlock};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use rb_block::Block;
+use rb_camera::PlayerControlled;
+use rb_items::{CropTable, ItemHolder, ToolFamily};
+use rb_world::{BlockEntities, BlockPos, BlockPos2d, ColLoadEvent, VoxelWorld};
+use std::fs;
+use std::time::Duration;
+
+/// Water this far from the tilled soil (in blocks) keeps it moist
+const IRRIGATION_RADIUS: i32 = 4;
+/// Crops that can't grow or are ripe check their conditions again after this long
+const RECHECK_SECS: f32 = 30.;
+
+pub struct CropGrowthPlugin;
+
+impl Plugin for CropGrowthPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(
+            json5::from_str::<CropTable>(&fs::read_to_string("assets/data/crops.json5").unwrap())
+                .unwrap(),
+        )
+        .add_observer(plant_crop)
+        .add_systems(Update, till_soil.run_if(in_state(GameUiState::None)))
+        .add_systems(Update, (attach_loaded_crops, grow_crops));
+    }
+}
+
+#[derive(Component)]
+pub struct Crop {
+    /// Elapsed game time at which the crop gets to its next stage
+    due: Duration,
+}
+
+/// 1 right next to water, fading out to 0 past the irrigation radius
+fn moisture(world: &VoxelWorld, soil: BlockPos) -> f32 {
+    let mut moisture: f32 = 0.;
+    for dx in -IRRIGATION_RADIUS..=IRRIGATION_RADIUS {
+        for dz in -IRRIGATION_RADIUS..=IRRIGATION_RADIUS {
+            if world.get_block_safe(soil + (dx, 0, dz)) == Block::SeaBlock {
+                let dist = dx.abs().max(dz.abs()) as f32;
+                moisture = moisture.max(1. - (dist - 1.) / IRRIGATION_RADIUS as f32);
+            }
+        }
+    }
+    moisture
+}
+
+fn stage_delay(world: &VoxelWorld, crop_table: &CropTable, pos: BlockPos) -> Duration {
+    let crop = world.get_block_state(pos);
+    let soil = pos + (0, -1, 0);
+    let secs = if crop.next_stage().is_none() || world.get_block(soil) != Block::TilledSoil {
+        None
+    } else {
+        world.climate_at(BlockPos2d::from(pos)).and_then(|climate| {
+            crop_table.stage_secs(
+                crop.block,
+                climate.temperature,
+                climate.humidity,
+                moisture(world, soil),
+            )
+        })
+    };
+    Duration::from_secs_f32(secs.unwrap_or(RECHECK_SECS))
+}
+
+fn plant_crop(
+    block_placed: On<BlockPlaced>,
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    crop_table: Res<CropTable>,
+    mut block_entities: ResMut<BlockEntities>,
+    time: Res<Time>,
+) {
+    let pos = block_placed.event().0;
+    if !world.get_block(pos).is_crop() {
+        return;
+    }
+    let crop_entt = commands
+        .spawn((
+            Crop {
+                due: time.elapsed() + stage_delay(&world, &crop_table, pos),
+            },
+            BlockAttached(pos),
+        ))
+        .id();
+    block_entities.add(&pos, crop_entt);
+}
+
+/// Block entities are dropped with their column, crops start their stage over once it's loaded again
+fn attach_loaded_crops(
+    mut commands: Commands,
+    mut load_events: MessageReader<ColLoadEvent>,
+    world: Res<VoxelWorld>,
+    crop_table: Res<CropTable>,
+    mut block_entities: ResMut<BlockEntities>,
+    time: Res<Time>,
+) {
+    for ColLoadEvent(col) in load_events.read() {
+        for pos in world.find_blocks(*col, |block| block.is_crop()) {
+            if block_entities.get(&pos).is_some() {
+                continue;
+            }
+            let crop_entt = commands
+                .spawn((
+                    Crop {
+                        due: time.elapsed() + stage_delay(&world, &crop_table, pos),
+                    },
+                    BlockAttached(pos),
+                ))
+                .id();
+            block_entities.add(&pos, crop_entt);
+        }
+    }
+}
+
+fn grow_crops(
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    crop_table: Res<CropTable>,
+    mut block_entities: ResMut<BlockEntities>,
+    mut crops: Query<(Entity, &mut Crop, &BlockAttached)>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    for (entity, mut crop, pos) in crops.iter_mut() {
+        if now < crop.due {
+            continue;
+        }
+        let state = world.get_block_state(pos.0);
+        if !state.block.is_crop() {
+            commands.entity(entity).despawn();
+            block_entities.remove(&pos.0);
+            continue;
+        }
+        // ripe crops stay attached, harvesting them replants the first stage
+        if let Some(next_stage) = state.next_stage()
+            && world.get_block(pos.0 + (0, -1, 0)) == Block::TilledSoil
+        {
+            world.set_block(pos.0, next_stage);
+        }
+        crop.due = now + stage_delay(&world, &crop_table, pos.0);
+    }
+}
+
+fn till_soil(
+    world: Res<VoxelWorld>,
+    block_action_query: Query<
+        (&TargetBlock, &ItemHolder, &ActionState<Action>),
+        With<PlayerControlled>,
+    >,
+    selected_slot: Res<SelectedHotbarSlot>,
+) {
+    for (target_block_opt, hotbar, action) in block_action_query.iter() {
+        if !action.just_pressed(&Action::Modify) {
+            continue;
+        }
+        let Some(target_block) = &target_block_opt.0 else {
+            continue;
+        };
+        let holds_hoe = hotbar
+            .get(selected_slot.0)
+            .item()
+            .and_then(|item| item.tool_family())
+            .is_some_and(|tool_family| tool_family == ToolFamily::Hoe);
+        if holds_hoe
+            && world.get_block(target_block.pos).is_tillable()
+            && !world
+                .get_block(target_block.pos + (0, 1, 0))
+                .is_targetable()
+        {
+            world.set_block(target_block.pos, Block::TilledSoil);
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::game_state::GameUiState;
 use crate::{Action, TargetBlock};
 use bevy::prelude::*;
 use leafwing_input_manager::prelude::*;
+use rb_block::Lit;
 use rb_camera::PlayerControlled;
 use rb_items::{FiringTable, ItemHolder, LitFurnace, Stack, furnace_slots};
 use rb_world::{BlockEntities, BlockPos, VoxelWorld};
@@ -98,10 +99,7 @@ fn on_furnace_edit(
         let Some(mut new_lit_furnace) = firing_table.get(item_holder, furnace.temp) else {
             // Turn furnace off
             commands.entity(furnace_entt).remove::<LitFurnace>();
-            voxel_world.set_block(
-                furnace.block_pos,
-                voxel_world.get_block(furnace.block_pos).off(),
-            );
+            voxel_world.update_state(furnace.block_pos, |state| state.with_lit(Lit::Off));
             continue;
         };
         // If firing continues we inherit the previous remaining fuel sec
@@ -112,10 +110,7 @@ fn on_furnace_edit(
         }
         // Replace the previous value
         commands.entity(furnace_entt).insert(new_lit_furnace);
-        voxel_world.set_block(
-            furnace.block_pos,
-            voxel_world.get_block(furnace.block_pos).on(),
-        );
+        voxel_world.update_state(furnace.block_pos, |state| state.with_lit(Lit::On));
     }
 }
 
@@ -1,13 +1,25 @@
 mod block_hit_place;
+mod chest_action;
+mod crop_growth;
 mod furnace_action;
+mod sapling_growth;
 use bevy::prelude::*;
 pub use block_hit_place::*;
+pub use chest_action::*;
+pub use crop_growth::*;
 pub use furnace_action::*;
+pub use sapling_growth::*;
 
 pub struct BlockActionPlugin;
 
 impl Plugin for BlockActionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((BlockHitPlacePlugin, FurnaceActionPlugin));
+        app.add_plugins((
+            BlockHitPlacePlugin,
+            ChestActionPlugin,
+            CropGrowthPlugin,
+            FurnaceActionPlugin,
+            SaplingGrowthPlugin,
+        ));
     }
 }
@@ -0,0 +1,116 @@
+use super::{BlockAttached, BlockPlaced};
+use bevy::prelude::*;
+use rand::RngExt;
+use rb_block::Block;
+use rb_generation::{SaplingFate, SaplingGrowth, Tree, WorldMeta};
+use rb_world::{BlockEntities, BlockPos, ColLoadEvent, VoxelWorld, WorldRng};
+use std::time::Duration;
+
+pub struct SaplingGrowthPlugin;
+
+impl Plugin for SaplingGrowthPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaplingGrowth::default())
+            .add_observer(plant_sapling)
+            .add_systems(Update, (attach_loaded_saplings, grow_saplings));
+    }
+}
+
+#[derive(Component)]
+pub struct Sapling {
+    tree: Tree,
+    fate: SaplingFate,
+    /// Elapsed game time at which the fate is sealed
+    due: Duration,
+}
+
+impl Sapling {
+    /// None if the block isn't a sapling or the climate of its column is unknown
+    fn planted(
+        world: &VoxelWorld,
+        sapling_growth: &SaplingGrowth,
+        pos: BlockPos,
+        roll: f32,
+        now: Duration,
+    ) -> Option<Self> {
+        let tree = Tree::from_sapling(world.get_block(pos))?;
+        let fate = sapling_growth.fate(world, tree, pos, roll)?;
+        Some(Sapling {
+            tree,
+            fate,
+            due: now + fate.after(),
+        })
+    }
+}
+
+fn plant_sapling(
+    block_placed: On<BlockPlaced>,
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    sapling_growth: Res<SaplingGrowth>,
+    mut world_rng: ResMut<WorldRng>,
+    mut block_entities: ResMut<BlockEntities>,
+    time: Res<Time>,
+) {
+    let pos = block_placed.event().0;
+    let roll = world_rng.rng.random::<f32>();
+    let Some(sapling) = Sapling::planted(&world, &sapling_growth, pos, roll, time.elapsed()) else {
+        return;
+    };
+    let sapling_entt = commands.spawn((sapling, BlockAttached(pos))).id();
+    block_entities.add(&pos, sapling_entt);
+}
+
+/// Block entities are dropped with their column, saplings get a new fate once it's loaded again
+fn attach_loaded_saplings(
+    mut commands: Commands,
+    mut load_events: MessageReader<ColLoadEvent>,
+    world: Res<VoxelWorld>,
+    sapling_growth: Res<SaplingGrowth>,
+    mut world_rng: ResMut<WorldRng>,
+    mut block_entities: ResMut<BlockEntities>,
+    time: Res<Time>,
+) {
+    for ColLoadEvent(col) in load_events.read() {
+        for pos in world.find_blocks(*col, |block| Tree::from_sapling(block).is_some()) {
+            if block_entities.get(&pos).is_some() {
+                continue;
+            }
+            let roll = world_rng.rng.random::<f32>();
+            if let Some(sapling) =
+                Sapling::planted(&world, &sapling_growth, pos, roll, time.elapsed())
+            {
+                let sapling_entt = commands.spawn((sapling, BlockAttached(pos))).id();
+                block_entities.add(&pos, sapling_entt);
+            }
+        }
+    }
+}
+
+fn grow_saplings(
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    world_meta: Res<WorldMeta>,
+    mut block_entities: ResMut<BlockEntities>,
+    saplings: Query<(Entity, &Sapling, &BlockAttached)>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    for (entity, sapling, pos) in saplings.iter() {
+        if now < sapling.due {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        block_entities.remove(&pos.0);
+        // the sapling may have been broken or replaced in the meantime
+        if Tree::from_sapling(world.get_block(pos.0)) != Some(sapling.tree) {
+            continue;
+        }
+        match sapling.fate {
+            SaplingFate::Grows { dist, .. } => {
+                SaplingGrowth::grow(&world, sapling.tree, pos.0, world_meta.seed, dist)
+            }
+            SaplingFate::Withers { .. } => world.set_block(pos.0, Block::DeadBush),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+use bevy::prelude::*;
+
+#[derive(Resource)]
+pub struct OpenChest(pub Option<Entity>);
@@ -0,0 +1,191 @@
+use crate::game_state::{GameUiState, SelectedHotbarSlot};
+use crate::{Action, PlayerName};
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use rb_block::Block;
+use rb_camera::{FpsCam, PlayerControlled};
+use rb_items::{ItemGet, ItemHolder, Stack, in_pickup_reach, merge_stacks, pick_up};
+use rb_physics::*;
+use rb_world::Realm;
+use std::time::Duration;
+
+/// Side of the collision box of dropped items
+pub const DROPPED_ITEM_SIZE: f32 = 0.25;
+/// Items thrown by a player can't be picked up for this long, so they don't come right back
+const THROWN_PICKUP_DELAY: Duration = Duration::from_secs(2);
+const DESPAWN_AFTER: Duration = Duration::from_secs(300);
+const THROW_SPEED: f32 = 8.;
+const ITEM_GRAVITY: f32 = 30.;
+
+pub struct DroppedItemPlugin;
+
+impl Plugin for DroppedItemPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (merge_dropped_items, pick_up_items, despawn_dropped_items).chain(),
+        )
+        .add_systems(
+            Update,
+            throw_selected_item.run_if(in_state(GameUiState::None)),
+        );
+    }
+}
+
+/// A stack lying in the world, moved around by rb_physics
+#[derive(Component)]
+pub struct DroppedItem {
+    pub stack: Stack,
+    /// Elapsed game time from which players can pick it up
+    pickable_from: Duration,
+    /// Elapsed game time at which it disappears
+    despawn_at: Duration,
+}
+
+/// Spawns `stack` as an item entity centered on `center`
+pub fn spawn_dropped_item(
+    commands: &mut Commands,
+    time: &Time,
+    stack: Stack,
+    center: Vec3,
+    realm: Realm,
+    velocity: Vec3,
+    pickup_delay: Duration,
+) {
+    if stack == Stack::None {
+        return;
+    }
+    let now = time.elapsed();
+    commands
+        .spawn((
+            DroppedItem {
+                stack,
+                pickable_from: now + pickup_delay,
+                despawn_at: now + DESPAWN_AFTER,
+            },
+            Transform::from_translation(center - Vec3::splat(DROPPED_ITEM_SIZE / 2.)),
+            Visibility::default(),
+            realm,
+        ))
+        .insert((
+            AABB(Vec3::splat(DROPPED_ITEM_SIZE)),
+            Velocity(velocity),
+            // only friction slows it down
+            Heading(Vec3::new(0., f32::NAN, 0.)),
+            Gravity(ITEM_GRAVITY),
+            SteppingOn(Block::Air),
+            Walking,
+        ));
+}
+
+fn center(transform: &Transform, aabb: &AABB) -> Vec3 {
+    transform.translation + aabb.0 / 2.
+}
+
+fn merge_dropped_items(
+    mut commands: Commands,
+    mut items: Query<(Entity, &Transform, &AABB, &Realm, &mut DroppedItem)>,
+) {
+    let mut pairs = items.iter_combinations_mut();
+    while let Some(
+        [
+            (_, transform, aabb, realm, mut kept),
+            (other, other_transform, other_aabb, other_realm, mut merged),
+        ],
+    ) = pairs.fetch_next()
+    {
+        if realm != other_realm
+            || !merge_stacks(
+                &mut kept.stack,
+                center(transform, aabb),
+                &mut merged.stack,
+                center(other_transform, other_aabb),
+            )
+        {
+            continue;
+        }
+        kept.despawn_at = kept.despawn_at.max(merged.despawn_at);
+        commands.entity(other).despawn();
+    }
+}
+
+fn pick_up_items(
+    mut commands: Commands,
+    mut items: Query<(Entity, &Transform, &AABB, &Realm, &mut DroppedItem)>,
+    mut players: Query<(Entity, &Transform, &AABB, &Realm, &mut ItemHolder), With<PlayerName>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    for (item_entt, transform, aabb, realm, mut dropped) in items.iter_mut() {
+        if now < dropped.pickable_from || dropped.stack == Stack::None {
+            continue;
+        }
+        let item_center = center(transform, aabb);
+        for (player, player_transform, player_aabb, player_realm, mut item_holder) in
+            players.iter_mut()
+        {
+            let player_min = player_transform.translation;
+            if realm != player_realm
+                || !in_pickup_reach(item_center, player_min, player_min + player_aabb.0)
+            {
+                continue;
+            }
+            let ItemHolder::Inventory(ref mut inventory) = *item_holder else {
+                continue;
+            };
+            if pick_up(inventory, &mut dropped.stack) {
+                commands.trigger(ItemGet { entity: player });
+            }
+            if dropped.stack == Stack::None {
+                commands.entity(item_entt).despawn();
+                break;
+            }
+        }
+    }
+}
+
+fn despawn_dropped_items(
+    mut commands: Commands,
+    items: Query<(Entity, &Transform, &DroppedItem)>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed();
+    for (entity, transform, dropped) in items.iter() {
+        // items that fell out of the world are gone too
+        if now >= dropped.despawn_at || transform.translation.y < 0. {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Throws one item of the selected slot in front of the player
+fn throw_selected_item(
+    mut commands: Commands,
+    mut player_query: Query<
+        (&Realm, &mut ItemHolder, &ActionState<Action>),
+        With<PlayerControlled>,
+    >,
+    player_cam: Query<&GlobalTransform, With<FpsCam>>,
+    selected_slot: Res<SelectedHotbarSlot>,
+    time: Res<Time>,
+) {
+    let Ok(cam_transform) = player_cam.single() else {
+        return;
+    };
+    for (realm, mut item_holder, action) in player_query.iter_mut() {
+        if !action.just_pressed(&Action::Drop) {
+            continue;
+        }
+        let stack = item_holder.get_mut(selected_slot.0).take(1);
+        let forward = *cam_transform.forward();
+        spawn_dropped_item(
+            &mut commands,
+            &time,
+            stack,
+            cam_transform.translation() + forward * 0.5,
+            *realm,
+            (forward + Vec3::Y * 0.3) * THROW_SPEED,
+            THROWN_PICKUP_DELAY,
+        );
+    }
+}
@@ -7,12 +7,13 @@ pub enum GameUiState {
     None,
     InGameMenu,
     CraftingMenu,
-    FurnaceMenu
+    FurnaceMenu,
+    ChestMenu
 }
 
 impl GameUiState {
     pub fn needs_free_cursor(&self) -> bool {
-        matches!(self, GameUiState::InGameMenu | GameUiState::FurnaceMenu)
+        matches!(self, GameUiState::InGameMenu | GameUiState::FurnaceMenu | GameUiState::ChestMenu)
     }
 
     pub fn needs_scrolling(&self) -> bool {
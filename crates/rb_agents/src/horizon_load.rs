@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
+use itertools::Itertools;
+use rb_camera::PlayerControlled;
+use rb_generation::TerrainGenerator;
+use rb_world::{ChunkPos2d, HORIZON_DISTANCE, HorizonTile, PlayerCol, RENDER_DISTANCE, chunk_area};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Column of the controlled player, the horizon is built around it
+#[derive(Resource)]
+pub struct HorizonCenterSender(pub Sender<ChunkPos2d>);
+
+pub fn send_horizon_center(
+    sender: Res<HorizonCenterSender>,
+    player_query: Query<&PlayerCol, (With<PlayerControlled>, Changed<PlayerCol>)>,
+) {
+    for player_col in player_query.iter() {
+        if sender.0.send(player_col.0).is_err() {
+            warn!("HorizonCenterSender channel is closed");
+        }
+    }
+}
+
+/// Makes the horizon tiles closest first, in the ring between the render distance and the horizon distance
+pub struct HorizonWorker {
+    pub terrain_gen: Arc<TerrainGenerator>,
+    pub center_recv: Receiver<ChunkPos2d>,
+    pub tile_sender: Sender<HorizonTile>,
+}
+
+impl HorizonWorker {
+    pub fn run(&self) {
+        // tiles that were sent and are still in range, the renderer keeps them
+        let mut made: HashSet<ChunkPos2d> = HashSet::new();
+        let Ok(mut center) = self.center_recv.recv() else {
+            return;
+        };
+        loop {
+            made.retain(|col| col.dist(center) <= HORIZON_DISTANCE);
+            let mut todo = chunk_area(&center, HORIZON_DISTANCE)
+                .filter(|col| col.dist(center) > RENDER_DISTANCE && !made.contains(col))
+                .sorted_by_key(|col| Reverse(col.dist(center)))
+                .collect_vec();
+            loop {
+                match self.center_recv.try_recv() {
+                    Ok(new_center) => {
+                        center = new_center;
+                        break;
+                    }
+                    Err(TryRecvError::Disconnected) => return,
+                    Err(TryRecvError::Empty) => {}
+                }
+                let Some(col) = todo.pop() else {
+                    // the horizon is complete, wait for the player to move
+                    match self.center_recv.recv() {
+                        Ok(new_center) => {
+                            center = new_center;
+                            break;
+                        }
+                        Err(_) => return,
+                    }
+                };
+                if self
+                    .tile_sender
+                    .send(self.terrain_gen.horizon_tile(col))
+                    .is_err()
+                {
+                    warn!("HorizonTile channel is closed, stopping horizon worker");
+                    return;
+                }
+                made.insert(col);
+            }
+        }
+    }
+}
@@ -2,7 +2,9 @@ use bevy::prelude::{KeyCode, MouseButton};
 use serde::{Deserialize, Serialize};
 use bevy::prelude::Resource;
 
+/// Missing keys take their default binding, so older key binding files still load
 #[derive(Serialize, Deserialize, Resource)]
+#[serde(default)]
 pub struct KeyBinds {
     pub forward: KeyCode,
     pub backward: KeyCode,
@@ -12,7 +14,9 @@ pub struct KeyBinds {
     pub crouch: KeyCode,
     pub hit: MouseButton,
     pub modify: MouseButton,
+    pub drop_item: KeyCode,
     pub toggle_fly: KeyCode,
+    pub set_respawn: KeyCode,
 }
 
 impl Default for KeyBinds {
@@ -26,7 +30,9 @@ impl Default for KeyBinds {
             crouch: KeyCode::ShiftLeft,
             hit: MouseButton::Left,
             modify: MouseButton::Right,
+            drop_item: KeyCode::KeyQ,
             toggle_fly: KeyCode::F1,
+            set_respawn: KeyCode::F2,
         }
     }
 }
\ No newline at end of file
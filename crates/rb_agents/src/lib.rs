@@ -1,13 +1,18 @@
+pub mod chest_state;
 pub mod furnace_state;
 pub mod game_state;
 pub mod sound_components;
 pub mod terrain_load_plugin;
 
 mod block_action;
+mod dropped_item;
+mod horizon_load;
 mod key_binds;
 mod player;
 
 pub use block_action::*;
+pub use chest_state::OpenChest;
+pub use dropped_item::{DROPPED_ITEM_SIZE, DroppedItem, spawn_dropped_item};
 pub use furnace_state::OpenFurnace;
 pub use game_state::{
     CursorGrabbed, Dragging, GameUiState, Inventory, ScrollGrabbed, SelectedHotbarSlot, UIAction,
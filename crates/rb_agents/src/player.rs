@@ -1,4 +1,6 @@
-use super::{block_action::BlockActionPlugin, key_binds::KeyBinds};
+use super::{
+    block_action::BlockActionPlugin, dropped_item::DroppedItemPlugin, key_binds::KeyBinds,
+};
 use crate::{
     game_state::CursorGrabbed,
     sound_components::{BlockSoundCD, FootstepCD},
@@ -11,19 +13,17 @@ use bevy::{
 use leafwing_input_manager::prelude::*;
 use rb_block::Block;
 use rb_camera::{CameraSpawn, FpsCam, PlayerControlled};
+use rb_generation::{SPAWN_POINTS_PATH, SpawnPoints};
 use rb_items::{InventoryTrait, Item, ItemHolder, Stack, new_inventory};
 use rb_physics::*;
-use rb_world::{BlockRayCastHit, Realm};
+use rb_world::{BlockPos, BlockPos2d, BlockRayCastHit, ChunkPos2d, Realm, VoxelWorld};
 use std::{f32::consts::FRAC_PI_2, time::Duration};
 
 const WALK_SPEED: f32 = 7.;
 const FREE_FLY_X_SPEED: f32 = 500.;
 const FREE_FLY_ACC_MULT: f32 = 150.;
-const SPAWN: Vec3 = Vec3 {
-    x: 280.,
-    y: 500.,
-    z: -150.,
-};
+/// Name of the player playing on this machine, respawn points are saved by player name
+pub const LOCAL_PLAYER: &str = "player";
 const CAMERA_PAN_RATE: f32 = 0.001;
 pub const HOTBAR_SLOTS: usize = 8;
 
@@ -36,6 +36,7 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(confy::load_path::<KeyBinds>("key_bindings.toml").unwrap())
             .add_plugins(BlockActionPlugin)
+            .add_plugins(DroppedItemPlugin)
             .add_plugins(InputManagerPlugin::<CameraMovement>::default())
             .add_plugins(InputManagerPlugin::<Dir>::default())
             .add_plugins(InputManagerPlugin::<Action>::default())
@@ -54,6 +55,11 @@ impl Plugin for PlayerPlugin {
                     .run_if(in_state(CursorGrabbed)),
             )
             .add_systems(Update, pan_camera.run_if(in_state(CursorGrabbed)))
+            .add_systems(
+                Update,
+                (respawn_fallen_players, land_spawning_players).chain(),
+            )
+            .add_systems(Update, set_respawn_point.run_if(in_state(CursorGrabbed)))
             .add_systems(OnExit(CursorGrabbed), reset_heading);
     }
 }
@@ -61,6 +67,24 @@ impl Plugin for PlayerPlugin {
 #[derive(Component)]
 pub struct TargetBlock(pub Option<BlockRayCastHit>);
 
+#[derive(Component)]
+pub struct PlayerName(pub String);
+
+/// The player waits for the column it (re)spawned in to be generated
+#[derive(Component)]
+pub struct Spawning {
+    /// At the world spawn the player lands on the surface, trees included, a respawn point is kept as is
+    on_surface: bool,
+}
+
+impl Spawning {
+    fn at_spawn_of(spawn_points: &SpawnPoints, player: &str) -> Self {
+        Spawning {
+            on_surface: !spawn_points.respawns.contains_key(player),
+        }
+    }
+}
+
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect)]
 pub enum Dir {
     Front,
@@ -88,11 +112,14 @@ impl From<Dir> for Vec3 {
 pub enum Action {
     Hit,
     Modify,
+    /// Throws an item of the selected slot
+    Drop,
 }
 
 #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Debug, Hash, Reflect)]
 pub enum DevCommand {
     ToggleFly,
+    SetRespawn,
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Reflect, Hash)]
@@ -106,9 +133,15 @@ impl Actionlike for CameraMovement {
     }
 }
 
+/// Position of a player standing in the middle of the block
+fn standing_in(pos: BlockPos, aabb: &AABB) -> Vec3 {
+    Vec3::from(pos) + Vec3::new(0.5 - aabb.0.x / 2., 0., 0.5 - aabb.0.z / 2.)
+}
+
 pub fn spawn_player(
     mut commands: Commands,
     key_binds: Res<KeyBinds>,
+    spawn_points: Res<SpawnPoints>,
     mut cursor_options: Query<&mut CursorOptions>,
 ) {
     let input_map = InputMap::default().with_dual_axis(CameraMovement::Pan, MouseMove::default());
@@ -135,7 +168,7 @@ pub fn spawn_player(
         .insert(FpsCam::default())
         .id();
 
-    let realm = Realm::Overworld;
+    let spawn = spawn_points.spawn_of(LOCAL_PLAYER);
     let mut inventory = new_inventory::<HOTBAR_SLOTS>();
     inventory.try_add(Stack::Some(Item::Block(Block::Smelter), 1));
     inventory.try_add(Stack::Some(Item::Coal, 20));
@@ -143,11 +176,12 @@ pub fn spawn_player(
     commands
         .spawn((
             Transform {
-                translation: SPAWN,
+                translation: standing_in(spawn, &aabb),
                 ..default()
             },
             Visibility::default(),
-            realm,
+            spawn.realm,
+            PlayerName(LOCAL_PLAYER.to_string()),
             Gravity(50.),
             Heading(Vec3::default()),
             Speed(WALK_SPEED),
@@ -162,7 +196,11 @@ pub fn spawn_player(
             ItemHolder::Inventory(inventory),
             PlayerControlled,
         ))
-        .insert((Walking, SteppingOn(Block::Air), Crouching(false)))
+        .insert((
+            Spawning::at_spawn_of(&spawn_points, LOCAL_PLAYER),
+            SteppingOn(Block::Air),
+            Crouching(false),
+        ))
         .insert(SpatialListener::new(0.3))
         .insert((FootstepCD(0.), BlockSoundCD(0.)))
         .insert(InputMap::new([
@@ -173,14 +211,17 @@ pub fn spawn_player(
             (Dir::Down, key_binds.crouch),
             (Dir::Up, key_binds.jump),
         ]))
+        .insert(
+            InputMap::new([
+                (Action::Hit, key_binds.hit),
+                (Action::Modify, key_binds.modify),
+            ])
+            .with(Action::Drop, key_binds.drop_item),
+        )
         .insert(InputMap::new([
-            (Action::Hit, key_binds.hit),
-            (Action::Modify, key_binds.modify),
+            (DevCommand::ToggleFly, key_binds.toggle_fly),
+            (DevCommand::SetRespawn, key_binds.set_respawn),
         ]))
-        .insert(InputMap::new([(
-            DevCommand::ToggleFly,
-            key_binds.toggle_fly,
-        )]))
         .add_child(cam);
 
     let mut cursor_options = cursor_options.single_mut().unwrap();
@@ -188,6 +229,74 @@ pub fn spawn_player(
     cursor_options.visible = false;
 }
 
+/// Players that fell out of the world go back to their spawn point
+fn respawn_fallen_players(
+    mut commands: Commands,
+    spawn_points: Res<SpawnPoints>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Realm,
+            &mut Velocity,
+            &AABB,
+            &PlayerName,
+        ),
+        Without<Spawning>,
+    >,
+) {
+    for (entity, mut transform, mut realm, mut velocity, aabb, name) in player_query.iter_mut() {
+        if transform.translation.y >= 0. {
+            continue;
+        }
+        let spawn = spawn_points.spawn_of(&name.0);
+        transform.translation = standing_in(spawn, aabb);
+        *realm = spawn.realm;
+        velocity.0 = Vec3::ZERO;
+        commands
+            .entity(entity)
+            .remove::<(Walking, FreeFly)>()
+            .insert(Spawning::at_spawn_of(&spawn_points, &name.0));
+    }
+}
+
+/// Lets the spawning players go once their column is generated
+fn land_spawning_players(
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    mut player_query: Query<(Entity, &mut Transform, &Realm, &mut Velocity, &Spawning)>,
+) {
+    for (entity, mut transform, realm, mut velocity, spawning) in player_query.iter_mut() {
+        let pos = BlockPos2d::from((transform.translation, *realm));
+        if !world.loaded_columns.contains(&ChunkPos2d::from(pos)) {
+            continue;
+        }
+        if spawning.on_surface {
+            let (_, y) = world.top_block(pos);
+            transform.translation.y = (y + 1) as f32;
+        }
+        velocity.0 = Vec3::ZERO;
+        commands.entity(entity).remove::<Spawning>().insert(Walking);
+    }
+}
+
+/// Saves the block the player is standing in as their respawn point
+fn set_respawn_point(
+    mut spawn_points: ResMut<SpawnPoints>,
+    player_query: Query<(&Transform, &Realm, &PlayerName, &ActionState<DevCommand>)>,
+) {
+    for (transform, realm, name, action_state) in player_query.iter() {
+        if !action_state.just_pressed(&DevCommand::SetRespawn) {
+            continue;
+        }
+        let pos = BlockPos::from((transform.translation, *realm));
+        spawn_points.respawns.insert(name.0.clone(), pos);
+        if let Err(err) = spawn_points.store(SPAWN_POINTS_PATH) {
+            warn!("{err:#}");
+        }
+    }
+}
+
 pub fn move_player(
     mut player_query: Query<(
         &mut Heading,
@@ -230,14 +339,19 @@ pub fn move_player(
 
 fn toggle_fly(
     mut commands: Commands,
-    mut player_query: Query<(
-        Entity,
-        &mut Speed,
-        &ActionState<DevCommand>,
-        Option<&Walking>,
-    )>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Speed,
+            &ActionState<DevCommand>,
+            Option<&Walking>,
+        ),
+        Without<Spawning>,
+    >,
 ) {
-    let (entity, mut speed, action_state, walking_opt) = player_query.single_mut().unwrap();
+    let Ok((entity, mut speed, action_state, walking_opt)) = player_query.single_mut() else {
+        return;
+    };
     for dev_command in action_state.get_just_pressed() {
         if dev_command == DevCommand::ToggleFly {
             if walking_opt.is_some() {
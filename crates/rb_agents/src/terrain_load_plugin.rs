@@ -1,133 +1,177 @@
+use crate::horizon_load::{HorizonCenterSender, HorizonWorker, send_horizon_center};
 use bevy::ecs::entity::EntityIndex;
 use bevy::log::trace;
 use bevy::prelude::*;
 use bevy::tasks::AsyncComputeTaskPool;
 use crossbeam::channel::{Receiver, Sender, unbounded};
-use rb_generation::TerrainGenerator;
+use rb_generation::{GEN_MARGIN, GenStage, Pipeline, TerrainGenerator, WorldMeta};
 use rb_logging::LogData;
 use rb_world::{
-    BlockEntities, ChunkPos2d, ColUnloadEvent, PlayerCol, Realm, VoxelWorld, WorldRng,
-    player_area_diff, unload_block_entities,
+    BlockEntities, ChunkPos2d, ColLoadEvent, ColUnloadEvent, HorizonTileReceiver, PlayerCol,
+    RENDER_DISTANCE, Realm, VoxelWorld, chunk_area, unload_block_entities,
 };
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
 
 pub struct TerrainLoadPlugin;
 
 impl Plugin for TerrainLoadPlugin {
     fn build(&self, app: &mut App) {
         app.add_message::<ColUnloadEvent>()
+            .add_message::<ColLoadEvent>()
             .insert_resource(BlockEntities::default())
             .add_systems(Startup, setup_load_thread)
             .add_systems(Update, send_player_pos_update)
             .add_systems(Update, assign_player_col)
+            .add_systems(Update, send_horizon_center)
+            .add_systems(Update, on_load_col)
             .add_systems(Update, on_unload_col)
             .add_systems(Update, unload_block_entities);
     }
 }
 
-pub fn setup_load_thread(mut commands: Commands, world: Res<VoxelWorld>, world_rng: Res<WorldRng>) {
+pub fn setup_load_thread(
+    mut commands: Commands,
+    world: Res<VoxelWorld>,
+    world_meta: Res<WorldMeta>,
+) {
     let (player_pos_sender, player_pos_recv) = unbounded::<PlayerColumnUpdate>();
     commands.insert_resource(PlayerColumnUpdateSender(player_pos_sender));
     let (unload_sender, unload_recv) = unbounded::<ChunkPos2d>();
     commands.insert_resource(ColUnloadsReciever(unload_recv));
+    let (load_sender, load_recv) = unbounded::<ChunkPos2d>();
+    commands.insert_resource(ColLoadsReciever(load_recv));
     let thread_pool = AsyncComputeTaskPool::get();
     let load_world = world.clone();
-    let seed_value = world_rng.seed;
+    let terrain_gen = Arc::new(
+        TerrainGenerator::new(world_meta.seed, world_meta.preset).with_erosion(world_meta.erosion),
+    );
+    let pipeline = Arc::new(Pipeline::default());
+    // Workers block on the pipeline so they get their own threads instead of starving the task pool
+    for i in 0..terrain_worker_count() {
+        let worker = TerrainWorker {
+            world: world.clone(),
+            terrain_gen: terrain_gen.clone(),
+            pipeline: pipeline.clone(),
+            load_sender: load_sender.clone(),
+            unload_sender: unload_sender.clone(),
+        };
+        thread::Builder::new()
+            .name(format!("terrain worker {i}"))
+            .spawn(move || worker.run())
+            .expect("Failed to spawn terrain worker");
+    }
+    let (horizon_center_sender, center_recv) = unbounded();
+    commands.insert_resource(HorizonCenterSender(horizon_center_sender));
+    let (tile_sender, tile_recv) = unbounded();
+    commands.insert_resource(HorizonTileReceiver(tile_recv));
+    let horizon_worker = HorizonWorker {
+        terrain_gen: terrain_gen.clone(),
+        center_recv,
+        tile_sender,
+    };
+    thread::Builder::new()
+        .name("horizon worker".to_string())
+        .spawn(move || horizon_worker.run())
+        .expect("Failed to spawn horizon worker");
+    let load_worker = TerrainWorker {
+        world: load_world,
+        terrain_gen,
+        pipeline,
+        load_sender,
+        unload_sender,
+    };
 
     thread_pool
         .spawn(async move {
-            let terrain_gen = TerrainGenerator::new(seed_value as u32);
             // local copy of players positions
             let mut players_pos = HashMap::new();
-            // keeps track of which players see which columns
-            let mut player_cols: HashMap<ChunkPos2d, HashSet<EntityIndex>> = HashMap::new();
-            // the list of all columns that must be generated
-            let mut to_load: Vec<ChunkPos2d> = Vec::new();
-            'outer: loop {
-                // Queue load orders and unload terrain based on incoming player positions and RENDER_DISTANCE
-                loop {
-                    // If to_load is empty, we block on player position updates to not waste resources
-                    let player_pos_update = if to_load.len() == 0 {
-                        player_pos_recv
-                            .recv()
-                            .expect("PlayerColumnUpdate channel is closed")
-                    } else {
-                        match player_pos_recv.try_recv() {
-                            Ok(update) => update,
-                            Err(_) => break, // player didn't move since last check, we can start loading
-                        }
-                    };
-                    // Player did move since last check, we modify the load/unload queues accordingly
-                    // Compute the difference in player area
-                    let area_diff =
-                        player_area_diff(&player_pos_update.new_col, player_pos_update.old_col_opt);
+            while let Ok(player_pos_update) = player_pos_recv.recv() {
+                players_pos.insert(player_pos_update.id, player_pos_update.new_col);
+                // only the latest position of each player matters
+                for player_pos_update in player_pos_recv.try_iter() {
                     players_pos.insert(player_pos_update.id, player_pos_update.new_col);
-                    // Handle columns that are no longer in the player's area
-                    for col in area_diff.exclusive_in_other {
-                        let Some(cols) = player_cols.get_mut(&col) else {
-                            continue;
-                        };
-                        cols.remove(&player_pos_update.id);
-                        if !cols.is_empty() {
-                            continue;
-                        }
-                        // we remove it from the list of columns that should be loaded in the world
-                        load_world.loaded_columns.remove(&col);
-                        if let Some(i) = to_load.iter().position(|c| *c == col) {
-                            // the chunk was still in the load queue we remove it
-                            to_load.swap_remove(i);
-                            // even in this case we still need to unload the column after because
-                            // it could have received blocks from neighboring columns generation
-                        }
-                        load_world.unload_col(col);
-                        if unload_sender.send(col).is_err() {
-                            // This means the game is shutting down, so we break the loop
-                            warn!("ColUnloadsReciever channel is closed, stopping terrain thread");
-                            break 'outer;
-                        }
-                    }
-                    // Handle columns that are new in the player's area
-                    for col in area_diff.exclusive_in_self {
-                        let players = player_cols.entry(col).or_default();
-                        if players.is_empty() {
-                            to_load.push(col);
-                            load_world.loaded_columns.insert(col);
-                        }
-                        players.insert(player_pos_update.id);
-                    }
-                    // Deal with unloaded world columns that have data
-                    // (happens when a structure generate blocks in a chunk that was not supposed to be loaded)
-                    while let Some(col) = load_world.unloaded_columns.pop_back() {
-                        load_world.unload_col(*col);
-                        if unload_sender.send(*col).is_err() {
-                            // This means the game is shutting down, so we break the loop
-                            warn!("ColUnloadsReciever channel is closed, stopping terrain thread");
-                            break 'outer;
-                        }
-                    }
                 }
-                // Generate the closest column to any player
-                let (closest_idx, _closest_col) = to_load
-                    .iter()
-                    .enumerate()
-                    .min_by_key(|(_i, col)| {
-                        players_pos
-                            .values()
-                            .map(|player_col| {
-                                (col.x - player_col.x).abs() + (col.z - player_col.z).abs()
-                            })
-                            .min()
-                    })
-                    .unwrap();
-                let col = to_load.remove(closest_idx);
-                terrain_gen.generate(&load_world, col);
-                trace!("{}", LogData::ColGenerated(col));
-                load_world.mark_change_col(col);
+                let unloaded = load_worker.pipeline.update_area(wanted_area(&players_pos));
+                if !load_worker.unload(unloaded) {
+                    break;
+                }
             }
+            load_worker.pipeline.close();
         })
         .detach();
 }
 
+/// Columns in render distance of a player must be fully generated,
+/// and they need a margin of partially generated columns around them.
+/// The priority is the distance to the closest player.
+fn wanted_area(
+    players_pos: &HashMap<EntityIndex, ChunkPos2d>,
+) -> HashMap<ChunkPos2d, (GenStage, i32)> {
+    let mut wanted: HashMap<ChunkPos2d, (GenStage, i32)> = HashMap::new();
+    for player_col in players_pos.values() {
+        for col in chunk_area(player_col, RENDER_DISTANCE + GEN_MARGIN) {
+            let Some(target) = GenStage::target_at(col.dist(*player_col) - RENDER_DISTANCE) else {
+                continue;
+            };
+            let priority = (col.x - player_col.x).abs() + (col.z - player_col.z).abs();
+            wanted
+                .entry(col)
+                .and_modify(|(t, p)| {
+                    *t = (*t).max(target);
+                    *p = (*p).min(priority);
+                })
+                .or_insert((target, priority));
+        }
+    }
+    wanted
+}
+
+/// Leaves some cores for the main thread, rendering and meshing
+fn terrain_worker_count() -> usize {
+    thread::available_parallelism().map_or(1, |n| (n.get() / 2).max(1))
+}
+
+struct TerrainWorker {
+    world: VoxelWorld,
+    terrain_gen: Arc<TerrainGenerator>,
+    pipeline: Arc<Pipeline>,
+    load_sender: Sender<ChunkPos2d>,
+    unload_sender: Sender<ChunkPos2d>,
+}
+
+impl TerrainWorker {
+    fn run(&self) {
+        // Run the stages of the closest columns to any player
+        while let Some(mut job) = self.pipeline.next_job() {
+            self.terrain_gen.run_stage(&self.world, &mut job);
+            if job.stage == GenStage::Lighting {
+                trace!("{}", LogData::ColGenerated(job.col));
+                if self.load_sender.send(job.col).is_err() {
+                    self.pipeline.close();
+                }
+            }
+            let unloaded = self.pipeline.finish(job);
+            if !self.unload(unloaded) {
+                self.pipeline.close();
+            }
+        }
+    }
+
+    /// Returns false if the game is shutting down
+    fn unload(&self, cols: Vec<ChunkPos2d>) -> bool {
+        for col in cols {
+            self.world.unload_col(col);
+            if self.unload_sender.send(col).is_err() {
+                warn!("ColUnloadsReciever channel is closed, stopping terrain thread");
+                return false;
+            }
+        }
+        true
+    }
+}
+
 pub fn assign_player_col(
     mut commands: Commands,
     sender: Res<PlayerColumnUpdateSender>,
@@ -138,7 +182,6 @@ pub fn assign_player_col(
         commands.entity(player).insert(PlayerCol(col));
         let update = PlayerColumnUpdate {
             id: player.index(),
-            old_col_opt: None,
             new_col: col,
         };
         trace!(
@@ -164,7 +207,6 @@ pub fn send_player_pos_update(
             // send the update only if the column has changed
             let update = PlayerColumnUpdate {
                 id: player.index(),
-                old_col_opt: Some(player_col.0),
                 new_col,
             };
             trace!(
@@ -187,10 +229,18 @@ pub struct PlayerColumnUpdateSender(pub Sender<PlayerColumnUpdate>);
 
 pub struct PlayerColumnUpdate {
     id: EntityIndex,
-    old_col_opt: Option<ChunkPos2d>,
     new_col: ChunkPos2d,
 }
 
+#[derive(Resource)]
+pub struct ColLoadsReciever(pub Receiver<ChunkPos2d>);
+
+pub fn on_load_col(load_cols: Res<ColLoadsReciever>, mut load_event: MessageWriter<ColLoadEvent>) {
+    while let Ok(col) = load_cols.0.try_recv() {
+        load_event.write(ColLoadEvent(col));
+    }
+}
+
 #[derive(Resource)]
 pub struct ColUnloadsReciever(pub Receiver<ChunkPos2d>);
 
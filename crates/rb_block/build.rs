@@ -8,7 +8,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     let workspace_dir = Path::new(&manifest_dir).join("../..").canonicalize()?;
     let block_def_path = workspace_dir.join("assets/data/blocks.def");
     let block_def = fs::read_to_string(&block_def_path)?;
-    let rust_code = generate_blocks(&block_def)?;
+    let path = block_def_path.display().to_string();
+    let (rust_code, warnings) = match generate_blocks(&block_def) {
+        Ok(generated) => generated,
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("{}\n", error.render(&path));
+            }
+            return Err(format!("{} error(s) in {path}", errors.len()).into());
+        }
+    };
+    for warning in warnings {
+        println!("cargo::warning={path}:{}:{}: {}", warning.line, warning.column, warning.message);
+    }
     fs::write(&dest_path, rust_code)?;
     println!("cargo::rerun-if-changed={}", block_def_path.display());
     Ok(())
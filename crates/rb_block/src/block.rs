@@ -1,54 +1,44 @@
 use crate::{Block, BlockFamily};
 
 impl Block {
-    pub fn friction(&self) -> f32 {
-        match self {
-            Block::Air => 0.05,
-            Block::Ice => 0.05,
-            _ => 1.
-        }
+    pub fn is_foliage(&self) -> bool {
+        self.families().contains(&BlockFamily::Leaves)
     }
 
-    pub fn slowing(&self) -> f32 {
-        match self {
-            Block::Mud => 0.8,
-            _ => 1.
-        }
+    pub fn is_crop(&self) -> bool {
+        self.families().contains(&BlockFamily::Crop)
     }
 
-    pub fn is_traversable(&self) -> bool {
-        match self {
-            Block::Air | Block::SeaBlock => true,
-            _ => false,
-        }
+    /// Crops can only be planted on tilled soil
+    pub fn can_be_placed_on(&self, below: Block) -> bool {
+        !self.is_crop() || below == Block::TilledSoil
     }
 
-    pub fn is_targetable(&self) -> bool {
+    /// Hoes turn these into tilled soil
+    pub fn is_tillable(&self) -> bool {
         match self {
-            Block::Air | Block::SeaBlock => false,
-            _ => true
-        }
-    }
-    
-    pub fn is_opaque(&self) -> bool {
-        if self.is_foliage() {
-            return false;
-        }
-        match self {
-            Block::Air | Block::SeaBlock | Block::Ice | Block::Glass | Block::Campfire => false,
-            _ => true
+            Block::Dirt | Block::GrassBlock => true,
+            _ => false
         }
     }
 
-    pub fn is_foliage(&self) -> bool {
-        self.families().contains(&BlockFamily::Leaves)
+    pub fn is_sapling(&self) -> bool {
+        self.families().contains(&BlockFamily::Sapling)
     }
 
-    pub fn is_fertile_soil(&self) -> bool {
+    /// The sapling that drops from these leaves
+    pub fn sapling(&self) -> Option<Block> {
         match self {
-            Block::GrassBlock | Block::Podzol | Block::Snow
-                => true,
-            _ => false
+            Block::AcaciaLeaves => Some(Block::AcaciaSapling),
+            Block::BambooLeaves => Some(Block::BambooSapling),
+            Block::BirchLeaves => Some(Block::BirchSapling),
+            Block::ChestnutLeaves => Some(Block::ChestnutSapling),
+            Block::IronwoodLeaves => Some(Block::IronwoodSapling),
+            Block::OakLeaves => Some(Block::OakSapling),
+            Block::PalmLeaves => Some(Block::PalmSapling),
+            Block::SequoiaLeaves => Some(Block::SequoiaSapling),
+            Block::SpruceLeaves => Some(Block::SpruceSapling),
+            _ => None
         }
     }
 }
@@ -1,4 +1,4 @@
-use bevy::ecs::component::Component;
+use bevy::{ecs::component::Component, math::Vec3};
 use strum_macros::EnumIter;
 const UP_SPECIFIER: [FaceSpecifier; 2] = [FaceSpecifier::Specific(Face::Up), FaceSpecifier::All];
 const DOWN_SPECIFIER: [FaceSpecifier; 3] = [FaceSpecifier::Specific(Face::Down), FaceSpecifier::Specific(Face::Up), FaceSpecifier::All];
@@ -25,6 +25,31 @@ pub enum Face {
 }
 
 impl Face {
+    /// The sides of a block, each one a quarter turn from the previous one
+    pub const HORIZONTAL: [Face; 4] = [Face::Front, Face::Right, Face::Back, Face::Left];
+
+    /// The face whose normal is the closest to `dir`, vertical faces win the ties
+    pub fn closest(dir: Vec3) -> Self {
+        let abs = dir.abs();
+        if abs.y >= abs.x && abs.y >= abs.z {
+            if dir.y < 0. { Face::Down } else { Face::Up }
+        } else if abs.x >= abs.z {
+            if dir.x < 0. { Face::Left } else { Face::Right }
+        } else if dir.z < 0. {
+            Face::Back
+        } else {
+            Face::Front
+        }
+    }
+
+    /// Turns the sides by a number of quarter turns, the top and bottom stay in place
+    pub fn turned(self, quarter_turns: usize) -> Self {
+        match Face::HORIZONTAL.iter().position(|&face| face == self) {
+            Some(i) => Face::HORIZONTAL[(i + quarter_turns) % 4],
+            None => self,
+        }
+    }
+
     pub fn n(&self) -> [i32; 3] {
         match self {
             Self::Left => [-1, 0, 0],
@@ -86,6 +111,19 @@ impl From<u8> for Face {
     }
 }
 
+impl From<Face> for u8 {
+    fn from(face: Face) -> Self {
+        match face {
+            Face::Up => 0,
+            Face::Down => 1,
+            Face::Right => 2,
+            Face::Left => 3,
+            Face::Front => 4,
+            Face::Back => 5,
+        }
+    }
+}
+
 impl From<usize> for Face {
     fn from(value: usize) -> Self {
         (value as u8).into()
@@ -1,4 +1,8 @@
 include!(concat!(env!("OUT_DIR"), "/blocks.rs"));
 mod block;
 mod face;
+mod shape;
+mod state;
 pub use face::*;
+pub use shape::*;
+pub use state::*;
@@ -0,0 +1,244 @@
+use crate::{Block, BlockState, Face};
+use bevy::math::Vec3;
+
+const CUBE_BOXES: &[ShapeBox] = &[ShapeBox::FULL];
+const SLAB_BOXES: &[ShapeBox] = &[ShapeBox::new([0, 0, 0], [16, 8, 16])];
+/// The step is on the front side
+const STAIR_BOXES: &[ShapeBox] = &[
+    ShapeBox::new([0, 0, 0], [16, 8, 16]),
+    ShapeBox::new([0, 8, 0], [16, 16, 8]),
+];
+/// Plants are drawn as 2 crossed quads, the box is only there to target them
+const CROSS_BOXES: &[ShapeBox] = &[ShapeBox::new([2, 0, 2], [14, 13, 14])];
+/// Panes and fences are posts with arms reaching toward the neighbors they connect to
+const PANE_BOXES: &[ShapeBox] = &[ShapeBox::new([7, 0, 7], [9, 16, 9])];
+/// The arm going from the post to the front side
+const PANE_ARMS: &[ShapeBox] = &[ShapeBox::new([7, 0, 9], [9, 16, 16])];
+const FENCE_BOXES: &[ShapeBox] = &[ShapeBox::new([6, 0, 6], [10, 16, 10])];
+/// The 2 rails going from the post to the front side
+const FENCE_ARMS: &[ShapeBox] = &[
+    ShapeBox::new([7, 6, 10], [9, 9, 16]),
+    ShapeBox::new([7, 12, 10], [9, 15, 16]),
+];
+const TORCH_BOXES: &[ShapeBox] = &[ShapeBox::new([7, 0, 7], [9, 10, 9])];
+
+/// A box of a block shape, in sixteenths of a block
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ShapeBox {
+    pub min: [u8; 3],
+    pub max: [u8; 3],
+}
+
+impl ShapeBox {
+    pub const FULL: ShapeBox = ShapeBox::new([0, 0, 0], [16, 16, 16]);
+
+    pub const fn new(min: [u8; 3], max: [u8; 3]) -> Self {
+        ShapeBox { min, max }
+    }
+
+    /// The lowest corner, in blocks
+    pub fn min(&self) -> Vec3 {
+        Vec3::new(self.min[0] as f32, self.min[1] as f32, self.min[2] as f32) / 16.
+    }
+
+    /// The highest corner, in blocks
+    pub fn max(&self) -> Vec3 {
+        Vec3::new(self.max[0] as f32, self.max[1] as f32, self.max[2] as f32) / 16.
+    }
+
+    /// Turns the box around the center of the block, a quarter turn brings the front side to the right
+    pub fn turned(self, quarter_turns: usize) -> Self {
+        (0..quarter_turns % 4).fold(self, |b, _| {
+            ShapeBox::new(
+                [b.min[2], b.min[1], 16 - b.max[0]],
+                [b.max[2], b.max[1], 16 - b.min[0]],
+            )
+        })
+    }
+
+    /// Whether the face of the box is on the side of the block
+    pub fn touches(&self, face: Face) -> bool {
+        match face {
+            Face::Left => self.min[0] == 0,
+            Face::Down => self.min[1] == 0,
+            Face::Back => self.min[2] == 0,
+            Face::Right => self.max[0] == 16,
+            Face::Up => self.max[1] == 16,
+            Face::Front => self.max[2] == 16,
+        }
+    }
+
+    /// Where a ray starting at `origin`, relative to the corner of the block, enters the box:
+    /// the distance in lengths of `dir` and the face it goes through. None if the ray misses the box.
+    pub fn ray_hit(&self, origin: Vec3, dir: Vec3) -> Option<(f32, Face)> {
+        let (min, max) = (self.min(), self.max());
+        let mut enter = (f32::NEG_INFINITY, Face::Up);
+        let mut exit = f32::INFINITY;
+        for (axis, [low, high]) in [
+            (0, [Face::Left, Face::Right]),
+            (1, [Face::Down, Face::Up]),
+            (2, [Face::Back, Face::Front]),
+        ] {
+            if dir[axis] == 0. {
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t_low = (min[axis] - origin[axis]) / dir[axis];
+            let t_high = (max[axis] - origin[axis]) / dir[axis];
+            // the ray goes through the side it meets first
+            let (t_near, t_far, face) = if t_low < t_high {
+                (t_low, t_high, low)
+            } else {
+                (t_high, t_low, high)
+            };
+            if t_near > enter.0 {
+                enter = (t_near, face);
+            }
+            exit = exit.min(t_far);
+        }
+        (enter.0 <= exit && exit >= 0.).then_some(enter)
+    }
+}
+
+/// How a block is drawn, collided with and targeted, declared with `shape(<shape>)` in blocks.def
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Shape {
+    Cube,
+    Slab,
+    Stair,
+    Cross,
+    Pane,
+    Fence,
+    Torch,
+}
+
+impl Shape {
+    /// The boxes of the shape when it faces front
+    pub fn boxes(&self) -> &'static [ShapeBox] {
+        match self {
+            Shape::Cube => CUBE_BOXES,
+            Shape::Slab => SLAB_BOXES,
+            Shape::Stair => STAIR_BOXES,
+            Shape::Cross => CROSS_BOXES,
+            Shape::Pane => PANE_BOXES,
+            Shape::Fence => FENCE_BOXES,
+            Shape::Torch => TORCH_BOXES,
+        }
+    }
+
+    /// The boxes added toward the front side when the shape connects to its front neighbor
+    pub fn arms(&self) -> &'static [ShapeBox] {
+        match self {
+            Shape::Pane => PANE_ARMS,
+            Shape::Fence => FENCE_ARMS,
+            _ => &[],
+        }
+    }
+
+    /// Panes and fences connect to their own shape and to opaque cubes
+    pub fn connects_to(&self, neighbor: Block) -> bool {
+        neighbor.shape() == *self || (neighbor.shape() == Shape::Cube && neighbor.is_opaque())
+    }
+
+    /// Thin shapes are not drawn at lower levels of detail, the others are drawn as cubes
+    pub fn is_thin(&self) -> bool {
+        matches!(
+            self,
+            Shape::Cross | Shape::Pane | Shape::Fence | Shape::Torch
+        )
+    }
+}
+
+impl BlockState {
+    /// The boxes of the shape of the block, turned toward its facing.
+    /// `neighbor` gives the block next to it on a horizontal face, for the shapes that connect to their neighbors.
+    pub fn boxes(&self, neighbor: impl Fn(Face) -> Block) -> Vec<ShapeBox> {
+        let shape = self.block.shape();
+        let quarter_turns = self.facing().map_or(0, |facing| facing as usize);
+        let mut boxes: Vec<ShapeBox> = shape
+            .boxes()
+            .iter()
+            .map(|shape_box| shape_box.turned(quarter_turns))
+            .collect();
+        if shape.arms().is_empty() {
+            return boxes;
+        }
+        // the arms toward the front are turned to reach the other sides
+        for (quarter_turns, face) in Face::HORIZONTAL.into_iter().enumerate() {
+            if shape.connects_to(neighbor(face)) {
+                boxes.extend(shape.arms().iter().map(|arm| arm.turned(quarter_turns)));
+            }
+        }
+        boxes
+    }
+
+    /// Traversable blocks such as plants can be walked through whatever their shape
+    pub fn collision_boxes(&self, neighbor: impl Fn(Face) -> Block) -> Vec<ShapeBox> {
+        if self.block.is_traversable() {
+            Vec::new()
+        } else {
+            self.boxes(neighbor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Block, BlockState, Face, Facing, ShapeBox};
+    use bevy::math::Vec3;
+
+    #[test]
+    fn test_turned_box() {
+        // the upper half of a stair is at the back, it goes to the left when facing right
+        let upper = ShapeBox::new([0, 8, 0], [16, 16, 8]);
+        assert_eq!(upper.turned(1), ShapeBox::new([0, 8, 0], [8, 16, 16]));
+        assert_eq!(upper.turned(2), ShapeBox::new([0, 8, 8], [16, 16, 16]));
+        assert_eq!(upper.turned(4), upper);
+        let stair = BlockState::from(Block::OakStairs).with_facing(Facing::Right);
+        assert_eq!(stair.boxes(|_| Block::Air)[1], upper.turned(1));
+    }
+
+    #[test]
+    fn test_connected_boxes() {
+        let fence = BlockState::from(Block::OakFence);
+        // a lone fence is only a post
+        assert_eq!(fence.boxes(|_| Block::Air).len(), 1);
+        // the rails reach the fence on the right and the granite in the back
+        let boxes = fence.boxes(|face| match face {
+            Face::Right => Block::OakFence,
+            Face::Back => Block::Granite,
+            _ => Block::Air,
+        });
+        assert_eq!(boxes.len(), 5);
+        assert!(boxes.iter().any(|rail| rail.touches(Face::Right)));
+        assert!(boxes.iter().any(|rail| rail.touches(Face::Back)));
+        assert!(
+            !boxes
+                .iter()
+                .any(|rail| rail.touches(Face::Front) || rail.touches(Face::Left))
+        );
+        // panes don't connect to fences
+        let pane = BlockState::from(Block::GlassPane);
+        assert_eq!(pane.boxes(|_| Block::OakFence).len(), 1);
+    }
+
+    #[test]
+    fn test_ray_hit() {
+        let slab = ShapeBox::new([0, 0, 0], [16, 8, 16]);
+        // looking down at the slab from above
+        assert_eq!(
+            slab.ray_hit(Vec3::new(0.5, 2., 0.5), Vec3::NEG_Y),
+            Some((1.5, Face::Up))
+        );
+        // going over it
+        assert_eq!(slab.ray_hit(Vec3::new(-1., 0.75, 0.5), Vec3::X), None);
+        assert_eq!(
+            slab.ray_hit(Vec3::new(-1., 0.25, 0.5), Vec3::X),
+            Some((1., Face::Left))
+        );
+        // the slab is behind the ray
+        assert_eq!(slab.ray_hit(Vec3::new(0.5, 2., 0.5), Vec3::Y), None);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{Axis, Block, Face, Facing, Stage};
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// A block along with the values of the states it declares in blocks.def, such as the axis of a log.
+/// The values are packed in `state`, 0 stands for the default value of every state.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+pub struct BlockState {
+    pub block: Block,
+    pub state: u8,
+}
+
+impl From<Block> for BlockState {
+    fn from(block: Block) -> Self {
+        BlockState { block, state: 0 }
+    }
+}
+
+impl Axis {
+    pub fn of(face: Face) -> Self {
+        match face {
+            Face::Left | Face::Right => Axis::X,
+            Face::Down | Face::Up => Axis::Y,
+            Face::Back | Face::Front => Axis::Z,
+        }
+    }
+}
+
+impl Facing {
+    pub fn face(self) -> Face {
+        Face::HORIZONTAL[self as usize]
+    }
+
+    /// None for the vertical faces
+    pub fn of(face: Face) -> Option<Self> {
+        Face::HORIZONTAL
+            .iter()
+            .position(|&horizontal| horizontal == face)
+            .map(|i| Facing::VALUES[i])
+    }
+}
+
+impl BlockState {
+    /// Orients the block when it's placed against the face `normal` of another block, while looking toward `looking`.
+    /// Logs follow the normal, furnaces face away from the wall they're placed on or toward the player.
+    pub fn placed(self, normal: Vec3, looking: Vec3) -> Self {
+        let normal = Face::closest(normal);
+        let facing = Facing::of(normal)
+            .or(Facing::of(Face::closest(looking.with_y(0.)).opposite()))
+            .unwrap_or(Facing::Front);
+        self.with_axis(Axis::of(normal)).with_facing(facing)
+    }
+
+    /// The next growth stage of a crop, None once it's ripe or if the block doesn't grow
+    pub fn next_stage(self) -> Option<Self> {
+        let stage = self.stage()?;
+        Stage::VALUES
+            .get(stage as usize + 1)
+            .map(|&next| self.with_stage(next))
+    }
+
+    /// The face of the textures of the block to draw on `face`, and whether it's turned a quarter
+    pub fn texture_face(&self, face: Face) -> (Face, bool) {
+        if let Some(facing) = self.facing() {
+            return (face.turned(4 - facing as usize), false);
+        }
+        match (self.axis(), face) {
+            (None | Some(Axis::Y), _) => (face, false),
+            // lying logs show their rings on the ends and their grain along the axis
+            (Some(Axis::X), Face::Right) => (Face::Up, false),
+            (Some(Axis::X), Face::Left) => (Face::Down, false),
+            (Some(Axis::X), _) => (face, true),
+            (Some(Axis::Z), Face::Front) => (Face::Up, false),
+            (Some(Axis::Z), Face::Back) => (Face::Down, false),
+            (Some(Axis::Z), Face::Left | Face::Right) => (face, true),
+            (Some(Axis::Z), _) => (face, false),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+use crate::{diagnostic::{line_of, Diagnostic}, parse::{BlockFlag, BlockFrag, Properties, IR}};
+
+/// Properties must be declared and of the declared type
+fn check_properties(ir: &IR, properties: &Properties, diagnostics: &mut Vec<Diagnostic>) {
+    for (name, value) in properties {
+        match ir.properties.get(name) {
+            None => diagnostics.push(Diagnostic::error(ir.source, name, format!("unknown property `{name}`"))),
+            Some(decl) if value.to_rust(decl.ty).is_none() => diagnostics.push(Diagnostic::error(
+                ir.source, name, format!("property `{name}` is a {}, got `{value}`", decl.ty)
+            )),
+            _ => {}
+        }
+    }
+}
+
+/// Errors and warnings about the parsed definitions, the code can only be generated without errors
+pub(crate) fn check(ir: &IR) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for set in ir.sets.values() {
+        if set.variants.is_empty() {
+            diagnostics.push(Diagnostic::error(ir.source, set.name, format!("set `{}` is empty", set.name)));
+        }
+        let mut variants = BTreeSet::new();
+        for variant in &set.variants {
+            if !variants.insert(variant.name) {
+                diagnostics.push(Diagnostic::error(
+                    ir.source, variant.name, format!("`{}` is listed twice in set `{}`", variant.name, set.name)
+                ));
+            }
+            check_properties(ir, &variant.properties, &mut diagnostics);
+        }
+        check_properties(ir, &set.defaults, &mut diagnostics);
+    }
+    for (name, state) in &ir.states {
+        let mut values = BTreeSet::new();
+        for value in &state.values {
+            if !values.insert(value) {
+                diagnostics.push(Diagnostic::error(ir.source, value, format!("`{value}` is listed twice in state `{name}`")));
+            }
+        }
+    }
+    let mut used_sets = BTreeSet::new();
+    let mut used_states = BTreeSet::new();
+    // the pattern that declared each block
+    let mut declared: BTreeMap<String, &str> = BTreeMap::new();
+    for decl in &ir.decl {
+        let mut known_sets = true;
+        for frag in &decl.frags {
+            if let BlockFrag::SetName(set_name) = frag {
+                used_sets.insert(*set_name);
+                if !ir.sets.contains_key(set_name) {
+                    diagnostics.push(Diagnostic::error(ir.source, set_name, format!("unknown set `{set_name}`")));
+                    known_sets = false;
+                }
+            }
+        }
+        check_properties(ir, &decl.properties, &mut diagnostics);
+        if decl.flags.iter().filter(|flag| matches!(flag, BlockFlag::Shape(_))).count() > 1 {
+            diagnostics.push(Diagnostic::error(ir.source, decl.pattern, format!("`{}` is given more than one shape", decl.pattern)));
+        }
+        // the values of every state of a block are packed in a byte
+        let mut state_values = 1;
+        for (i, name) in decl.states.iter().enumerate() {
+            used_states.insert(*name);
+            if decl.states[..i].contains(name) {
+                diagnostics.push(Diagnostic::error(ir.source, name, format!("state `{name}` is added twice")));
+            }
+            match ir.states.get(name) {
+                None => diagnostics.push(Diagnostic::error(ir.source, name, format!("unknown state `{name}`"))),
+                Some(state) => state_values *= state.values.len(),
+            }
+        }
+        if state_values > 256 {
+            diagnostics.push(Diagnostic::error(ir.source, decl.pattern, format!(
+                "the states of `{}` combine into {state_values} values, at most 256 can be stored", decl.pattern
+            )));
+        }
+        if !known_sets {
+            continue;
+        }
+        for (block, _) in ir.expand(decl) {
+            match declared.entry(block) {
+                Entry::Vacant(entry) => { entry.insert(decl.pattern); }
+                Entry::Occupied(entry) => diagnostics.push(Diagnostic::error(ir.source, decl.pattern, format!(
+                    "{} `{}` is already declared on line {}", ir.kind.keyword(), entry.key(), line_of(ir.source, entry.get())
+                )))
+            }
+        }
+    }
+    for set in ir.sets.values() {
+        if !used_sets.contains(set.name) {
+            diagnostics.push(Diagnostic::warning(ir.source, set.name, format!("set `{}` is never used", set.name)));
+        }
+    }
+    for name in ir.states.keys() {
+        if !used_states.contains(name) {
+            diagnostics.push(Diagnostic::warning(ir.source, name, format!("state `{name}` is never used")));
+        }
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{check::check, diagnostic::Severity, parse::{parse_file, DefKind}};
+
+    /// Severity, line, column and message of the diagnostics of a file
+    fn diagnostics(blockdef: &str) -> Vec<(Severity, usize, usize, String)> {
+        let ir = parse_file(blockdef, DefKind::Block).unwrap();
+        check(&ir).into_iter().map(|diagnostic| (diagnostic.severity, diagnostic.line, diagnostic.column, diagnostic.message)).collect()
+    }
+
+    #[test]
+    fn test_property_errors() {
+        assert_eq!(diagnostics("property light: u32 = 0\nblock Torch light(true)"),
+            vec![(Severity::Error, 2, 13, "property `light` is a u32, got `true`".to_string())]);
+        assert_eq!(diagnostics("block Torch light(12)"),
+            vec![(Severity::Error, 1, 13, "unknown property `light`".to_string())]);
+        assert_eq!(diagnostics("set Ore { Iron lght(2) }\nblock {Ore}"),
+            vec![(Severity::Error, 1, 16, "unknown property `lght`".to_string())]);
+    }
+
+    #[test]
+    fn test_unknown_set() {
+        assert_eq!(diagnostics("set Wood { Oak }\nset Log { Log }\nblock {Wod}{Log}"), vec![
+            (Severity::Error, 3, 8, "unknown set `Wod`".to_string()),
+            (Severity::Warning, 1, 5, "set `Wood` is never used".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_duplicate_block() {
+        assert_eq!(diagnostics("set Crystal { Glass, Ice }\nblock {Crystal}\nblock Ice"),
+            vec![(Severity::Error, 3, 7, "block `Ice` is already declared on line 2".to_string())]);
+        assert_eq!(diagnostics("set Ore { Iron, Iron }\nblock {Ore}")[0].3, "`Iron` is listed twice in set `Ore`");
+    }
+
+    #[test]
+    fn test_shape_errors() {
+        assert_eq!(diagnostics("block OakSlab shape(slab) shape(stair)"),
+            vec![(Severity::Error, 1, 7, "`OakSlab` is given more than one shape".to_string())]);
+    }
+
+    #[test]
+    fn test_empty_set() {
+        assert_eq!(diagnostics("set Ore {}\nblock Iron{Ore}"),
+            vec![(Severity::Error, 1, 5, "set `Ore` is empty".to_string())]);
+    }
+
+    #[test]
+    fn test_state_errors() {
+        assert_eq!(diagnostics("state axis: y | x | y\nblock OakLog state(axis) state(axis) state(facing)"), vec![
+            (Severity::Error, 1, 21, "`y` is listed twice in state `axis`".to_string()),
+            (Severity::Error, 2, 32, "state `axis` is added twice".to_string()),
+            (Severity::Error, 2, 44, "unknown state `facing`".to_string()),
+        ]);
+        assert_eq!(diagnostics("state lit: false | true\nblock Torch")[0],
+            (Severity::Warning, 1, 7, "state `lit` is never used".to_string()));
+        let values = (0..20).map(|i| format!("v{i}")).collect::<Vec<_>>().join(" | ");
+        assert_eq!(diagnostics(&format!("state a: {values}\nstate b: {values}\nblock Dial state(a) state(b)"))[0].3,
+            "the states of `Dial` combine into 400 values, at most 256 can be stored");
+    }
+
+    #[test]
+    fn test_rendering() {
+        let ir = parse_file("set Wood { Oak }\nblock {Wod}Log", DefKind::Block).unwrap();
+        let rendered = check(&ir)[0].render("blocks.def");
+        assert_eq!(rendered, "error: unknown set `Wod`\n --> blocks.def:2:8\n  |\n2 | block {Wod}Log\n  |        ^^^");
+    }
+}
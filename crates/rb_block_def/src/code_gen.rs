@@ -1,41 +1,48 @@
 use std::{collections::{BTreeMap, BTreeSet}, fmt::Display};
 use itertools::Itertools;
-use crate::parse::{BlockFlag, BlockFrag, IR};
+use crate::parse::{BlockFlag, BlockFrag, BlockShape, DefKind, IR, PropType, PropValue, Properties, PropertyDecl, StateDecl, ToolFamily};
 
-const BLOCK_FAM: &'static str = "BlockFamily";
-const BLOCKS: &'static str = "Block";
+const BLOCK_FAM: &str = "BlockFamily";
+const BLOCKS: &str = "Block";
+const ITEM_FAM: &str = "ItemFamily";
+const ITEMS: &str = "Item";
 
 fn tab(i: u32) -> String {
     (0..i).map(|_| "\t").collect()
 }
 
-struct BlockEntry {
+struct BlockEntry<'a> {
     name: String,
     families: BTreeSet<String>,
     flags: BTreeSet<BlockFlag>,
+    properties: Properties<'a>,
+    /// In the order they're packed in
+    states: Vec<String>,
+    display_name: String,
+    tool: Option<ToolFamily>,
 }
 
-impl PartialEq for BlockEntry {
+impl PartialEq for BlockEntry<'_> {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
     }
 }
 
-impl Eq for BlockEntry {}
+impl Eq for BlockEntry<'_> {}
 
-impl PartialOrd for BlockEntry {
+impl PartialOrd for BlockEntry<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         self.name.partial_cmp(&other.name)
     }
 }
 
-impl Ord for BlockEntry {
+impl Ord for BlockEntry<'_> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.name.cmp(&other.name)
     }
 }
 
-impl Display for BlockEntry {
+impl Display for BlockEntry<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.name)
     }
@@ -81,20 +88,48 @@ impl MatchFn {
     }
 }
 
-fn generate_enum<T: Display>(name: &str, variants: &BTreeSet<T>) -> String {
+fn pascal_case(snake: &str) -> String {
+    snake.split('_').map(|word| {
+        let mut chars = word.chars();
+        chars.next().map_or(String::new(), |first| first.to_ascii_uppercase().to_string() + chars.as_str())
+    }).collect()
+}
+
+fn generate_enum<T: Display>(name: &str, variants: impl IntoIterator<Item = T>) -> String {
     format!(
         "#[derive(Debug, Display, PartialEq, EnumIter, EnumString, Eq, Serialize, Deserialize, Clone, Copy, Hash)]\npub enum {name} {{\n\t{}\n}}\n", 
         variants.into_iter().join(",\n\t")
     )
 }
 
-fn generate_family_impl(blocks: &BTreeSet<BlockEntry>) -> String {
-    MatchFn::new("families", &format!("Vec<{BLOCK_FAM}>")).with_arms(
+fn generate_family_impl(enum_name: &str, family_name: &str, blocks: &BTreeSet<BlockEntry>) -> MatchFn {
+    MatchFn::new("families", &format!("Vec<{family_name}>")).with_arms(
         blocks.into_iter().map(
             |block| 
-                format!("{BLOCKS}::{} => vec![{}]", block, block.families.iter().map(|f| format!("{BLOCK_FAM}::{f}")).join(", "))
+                format!("{enum_name}::{} => vec![{}]", block, block.families.iter().map(|f| format!("{family_name}::{f}")).join(", "))
             ).collect::<Vec<_>>()
-    ).to_rust(1)
+    )
+}
+
+/// Names given with `name("<name>")` or made of the words of the identifier
+fn generate_display_names(enum_name: &str, blocks: &BTreeSet<BlockEntry>) -> MatchFn {
+    MatchFn::new("display_name", "&'static str").with_arms(
+        blocks.iter().map(|block| format!("{enum_name}::{block} => {:?}", block.display_name)).collect()
+    )
+}
+
+/// `IronPickaxe` is displayed as "Iron Pickaxe"
+fn words(name: &str) -> String {
+    let mut words = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && previous.is_some_and(|p| !p.is_ascii_uppercase()) {
+            words.push(' ');
+        }
+        words.push(c);
+        previous = Some(c);
+    }
+    words
 }
 
 fn generate_flags(blocks: &mut BTreeSet<BlockEntry>) -> String {
@@ -107,7 +142,11 @@ fn generate_flags(blocks: &mut BTreeSet<BlockEntry>) -> String {
                     let depleted_block = BlockEntry {
                         name: format!("Depleted{block}"),
                         families: block.families.clone(),
-                        flags: block.flags.clone().into_iter().filter(|f| !matches!(f, BlockFlag::Renewable(_))).collect()
+                        flags: block.flags.clone().into_iter().filter(|f| !matches!(f, BlockFlag::Renewable(_))).collect(),
+                        properties: block.properties.clone(),
+                        states: block.states.clone(),
+                        display_name: format!("Depleted {}", block.display_name),
+                        tool: None
                     };
                     flag_fns.entry("depleted".to_string()).or_insert(MatchFn::new("depleted", &BLOCKS).with_default("*self")).arms.push(
                         format!("{BLOCKS}::{block} => {BLOCKS}::{depleted_block}")
@@ -120,23 +159,14 @@ fn generate_flags(blocks: &mut BTreeSet<BlockEntry>) -> String {
                     );
                     generated_blocks.insert(depleted_block);
                 },
+                // lit furnaces only differ by their `lit` state
                 BlockFlag::Furnace(temperature) => {
-                    let lit_furnace = BlockEntry {
-                        name: format!("{block}On"),
-                        families: block.families.clone(),
-                        flags: block.flags.clone()
-                    };
-                    flag_fns.entry("on".to_string()).or_insert(MatchFn::new("on", &BLOCKS).with_default("*self")).arms.push(
-                        format!("{BLOCKS}::{block} => {BLOCKS}::{lit_furnace}")
-                    );
-                    flag_fns.entry("off".to_string()).or_insert(MatchFn::new("off", &BLOCKS).with_default("*self")).arms.push(
-                        format!("{BLOCKS}::{lit_furnace} => {BLOCKS}::{block}")
-                    );
                     flag_fns.entry("furnace_temp".to_string()).or_insert(MatchFn::new("furnace_temp", "Option<u32>").with_default("None")).arms.push(
-                        format!("{BLOCKS}::{block} | {BLOCKS}::{lit_furnace} => Some({temperature})")
+                        format!("{BLOCKS}::{block} => Some({temperature})")
                     );
-                    generated_blocks.insert(lit_furnace);
                 },
+                // generated along with the blocks made by the other flags
+                BlockFlag::Shape(_) => {}
                 _ => {
                     let flag_name = format!("is_{:?}", flag).to_lowercase();
                     flag_fns.entry(flag_name.clone()).or_insert(MatchFn::new(&flag_name, "u32").with_default("true")).arms.push(
@@ -150,40 +180,304 @@ fn generate_flags(blocks: &mut BTreeSet<BlockEntry>) -> String {
     flag_fns.values().map(|match_fn| match_fn.to_rust(1)).join("\n\n")
 }
 
-pub fn generate(ir: &IR) -> String {
+/// Blocks that share a shape share an arm, the blocks without one are cubes
+fn generate_shapes(blocks: &BTreeSet<BlockEntry>) -> String {
+    let mut arms: BTreeMap<BlockShape, Vec<&BlockEntry>> = BTreeMap::new();
+    for block in blocks {
+        for flag in &block.flags {
+            if let BlockFlag::Shape(shape) = flag {
+                arms.entry(*shape).or_default().push(block);
+            }
+        }
+    }
+    MatchFn::new("shape", "Shape").with_arms(
+        arms.into_iter().map(|(shape, blocks)|
+            format!("{} => Shape::{shape:?}", blocks.iter().map(|block| format!("{BLOCKS}::{block}")).join(" | "))
+        ).collect()
+    ).with_default("Shape::Cube").to_rust(1)
+}
+
+/// Boolean properties are named `is_<property>`, the others are named after the property.
+/// The values have been checked against the declared types.
+fn generate_properties(enum_name: &str, blocks: &BTreeSet<BlockEntry>, properties: &BTreeMap<&str, PropertyDecl>) -> String {
+    let mut prop_fns = Vec::new();
+    for (name, decl) in properties {
+        let rust_value = |value: PropValue| value.to_rust(decl.ty).expect("Property values are checked before generating");
+        let default = rust_value(decl.default);
+        // blocks that share a value share an arm
+        let mut arms: BTreeMap<String, Vec<&BlockEntry>> = BTreeMap::new();
+        for block in blocks {
+            let Some(value) = block.properties.get(name) else {
+                continue;
+            };
+            let value = rust_value(*value);
+            if value != default {
+                arms.entry(value).or_default().push(block);
+            }
+        }
+        let fn_name = match decl.ty {
+            PropType::Bool => format!("is_{name}"),
+            _ => name.to_string()
+        };
+        prop_fns.push(MatchFn::new(&fn_name, &decl.ty.to_string()).with_arms(
+            arms.into_iter().map(|(value, blocks)| 
+                format!("{} => {value}", blocks.iter().map(|block| format!("{enum_name}::{block}")).join(" | "))
+            ).collect()
+        ).with_default(&default).to_rust(1));
+    }
+    prop_fns.join("\n\n")
+}
+
+/// The values of a state, in the order they're packed, the first one is the default
+fn generate_state_enum(name: &str, decl: &StateDecl) -> String {
+    let ty = pascal_case(name);
+    let values = decl.values.iter().map(|value| pascal_case(value)).collect::<Vec<_>>();
+    format!(
+        "{}impl {ty} {{\n\tpub const VALUES: [{ty}; {}] = [{}];\n}}\n",
+        generate_enum(&ty, &values), values.len(), values.iter().map(|value| format!("{ty}::{value}")).join(", ")
+    )
+}
+
+/// Getters and setters of the states of `BlockState`.
+/// The values of the states of a block are packed in a byte, the first state varies the fastest.
+fn generate_states(blocks: &BTreeSet<BlockEntry>, states: &BTreeMap<&str, StateDecl>) -> String {
+    let mut state_fns = Vec::new();
+    for (name, decl) in states {
+        let ty = pascal_case(name);
+        let count = decl.values.len();
+        // blocks that share a stride share an arm
+        let mut strides: BTreeMap<usize, Vec<&BlockEntry>> = BTreeMap::new();
+        for block in blocks {
+            let mut stride = 1;
+            for state in &block.states {
+                if state == name {
+                    strides.entry(stride).or_default().push(block);
+                    break;
+                }
+                stride *= states[state.as_str()].values.len();
+            }
+        }
+        if strides.is_empty() {
+            continue;
+        }
+        let arms = strides.iter().map(|(stride, blocks)| 
+            format!("{} => {stride}", blocks.iter().map(|block| format!("{BLOCKS}::{block}")).join(" | "))
+        ).join(&format!(",\n{}", tab(3)));
+        state_fns.push(format!(
+            "{}pub fn {name}(&self) -> Option<{ty}> {{\n{}let stride: u8 = match self.block {{\n{}{arms},\n{}_ => return None\n{}}};\n{}Some({ty}::VALUES[(self.state / stride % {count}) as usize])\n{}}}",
+            tab(1), tab(2), tab(3), tab(3), tab(2), tab(2), tab(1)
+        ));
+        state_fns.push(format!(
+            "{}/// Unchanged if the block has no {name}\n{}pub fn with_{name}(mut self, {name}: {ty}) -> Self {{\n{}let stride: u8 = match self.block {{\n{}{arms},\n{}_ => return self\n{}}};\n{}self.state = self.state - self.state / stride % {count} * stride + {name} as u8 * stride;\n{}self\n{}}}",
+            tab(1), tab(1), tab(2), tab(3), tab(3), tab(2), tab(2), tab(2), tab(1)
+        ));
+    }
+    state_fns.join("\n\n")
+}
+
+/// Blocks or items of the declarations, with the properties they inherit from their sets
+fn entries<'a>(ir: &IR<'a>) -> BTreeSet<BlockEntry<'a>> {
     let mut blocks: BTreeSet<BlockEntry> = BTreeSet::new();
-    for block_pattern in ir.decl.iter() {
-        let families = block_pattern.0.0.iter().filter_map(|frag| match frag { 
+    for decl in ir.decl.iter() {
+        let families = decl.frags.iter().filter_map(|frag| match frag { 
             BlockFrag::Ident(_) => None,
-            BlockFrag::SetName(set_name) => Some(set_name.clone()) 
+            BlockFrag::SetName(set_name) => Some(set_name.to_string()) 
         }).collect::<BTreeSet<_>>();
-        for frags in block_pattern.0.0.iter()
-            .map(|frag| match frag {
-                BlockFrag::Ident(ident) => vec![ident],
-                BlockFrag::SetName(set_name) => ir.sets.get(set_name).unwrap().iter().collect()
-            }).multi_cartesian_product()
-        {
-            let block: String = frags.into_iter().map(|s| s.as_str()).collect();
+        // set defaults, the rightmost set of the pattern wins
+        let mut defaults = Properties::new();
+        for frag in decl.frags.iter() {
+            if let BlockFrag::SetName(set_name) = frag {
+                defaults.extend(ir.sets[set_name].defaults.clone());
+            }
+        }
+        for (name, variants) in ir.expand(decl) {
+            // then the properties of the variants the block is made of, then the properties of the block itself
+            let mut properties = defaults.clone();
+            for variant in variants {
+                properties.extend(variant.properties.clone());
+            }
+            properties.extend(decl.properties.clone());
             blocks.insert(BlockEntry {
-                name: block,
+                display_name: decl.display_name.map_or_else(|| words(&name), str::to_string),
+                name,
                 families: families.clone(),
-                flags: block_pattern.0.1.clone()
+                flags: decl.flags.clone(),
+                properties,
+                states: decl.states.iter().map(|state| state.to_string()).collect(),
+                tool: decl.tool
             });
         }
     }
+    blocks
+}
+
+fn generate_blocks(ir: &IR) -> String {
+    let mut blocks = entries(ir);
     let flag_code = generate_flags(&mut blocks);
+    let shape_code = generate_shapes(&blocks);
+    let property_code = generate_properties(BLOCKS, &blocks, &ir.properties);
+    let state_code = generate_states(&blocks, &ir.states);
     let mut code_blocks = Vec::new();
     code_blocks.push("use serde::{Deserialize, Serialize};".to_string());
     code_blocks.push("use strum_macros::{EnumIter, EnumString, Display};".to_string());
     code_blocks.push(String::new());
-    code_blocks.push(generate_enum(BLOCK_FAM, &ir.sets.keys().map(|s| s.to_owned()).collect()));
-    for (family, variants) in ir.sets.iter() {
-        code_blocks.push(generate_enum(family, variants));
+    code_blocks.push(generate_enum(BLOCK_FAM, ir.sets.keys()));
+    for (family, set) in ir.sets.iter() {
+        code_blocks.push(generate_enum(family, set.variants.iter().map(|variant| variant.name).sorted()));
+    }
+    for (state, decl) in ir.states.iter() {
+        code_blocks.push(generate_state_enum(state, decl));
     }
     code_blocks.push(generate_enum(BLOCKS, &blocks));
     code_blocks.push(format!("impl {BLOCKS} {{"));
     code_blocks.push(flag_code);
-    code_blocks.push(generate_family_impl(&blocks));
+    code_blocks.push(shape_code);
+    code_blocks.push(property_code);
+    code_blocks.push(generate_display_names(BLOCKS, &blocks).to_rust(1));
+    code_blocks.push(generate_family_impl(BLOCKS, BLOCK_FAM, &blocks).to_rust(1));
+    code_blocks.push("}".to_string());
+    code_blocks.push("impl BlockState {".to_string());
+    code_blocks.push(state_code);
     code_blocks.push("}".to_string());
     code_blocks.join("\n")
-}
\ No newline at end of file
+}
+
+/// Blocks are items too, through the last variant.
+/// Unlike the sets of blocks, the sets of items don't get an enum of their variants, they only make families.
+fn generate_items(ir: &IR) -> String {
+    let items = entries(ir);
+    // items that share a tool family share an arm
+    let mut tool_arms: BTreeMap<ToolFamily, Vec<&BlockEntry>> = BTreeMap::new();
+    for item in &items {
+        if let Some(family) = item.tool {
+            tool_arms.entry(family).or_default().push(item);
+        }
+    }
+    let tool_code = MatchFn::new("tool_family", "Option<ToolFamily>").with_arms(
+        tool_arms.into_iter().map(|(family, items)|
+            format!("{} => Some(ToolFamily::{family:?})", items.iter().map(|item| format!("{ITEMS}::{item}")).join(" | "))
+        ).collect()
+    ).with_default("None").to_rust(1);
+    let mut display_names = generate_display_names(ITEMS, &items);
+    display_names.arms.push(format!("{ITEMS}::Block(block) => block.display_name()"));
+    let mut code_blocks = Vec::new();
+    code_blocks.push("use serde::{Deserialize, Serialize};".to_string());
+    code_blocks.push("use strum_macros::{EnumIter, EnumString, Display};".to_string());
+    code_blocks.push(String::new());
+    code_blocks.push(generate_enum(ITEM_FAM, ir.sets.keys()));
+    code_blocks.push(format!(
+        "#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]\npub enum {ITEMS} {{\n\t{},\n\t#[serde(untagged)]\n\tBlock(Block)\n}}\n",
+        items.iter().join(",\n\t")
+    ));
+    code_blocks.push(format!("impl {ITEMS} {{"));
+    code_blocks.push(tool_code);
+    code_blocks.push(generate_properties(ITEMS, &items, &ir.properties));
+    code_blocks.push(display_names.to_rust(1));
+    code_blocks.push(generate_family_impl(ITEMS, ITEM_FAM, &items).with_default("Vec::new()").to_rust(1));
+    code_blocks.push("}".to_string());
+    code_blocks.join("\n")
+}
+
+/// The declarations must have passed the checks
+pub fn generate(ir: &IR) -> String {
+    match ir.kind {
+        DefKind::Block => generate_blocks(ir),
+        DefKind::Item => generate_items(ir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{code_gen::generate, parse::{parse_file, DefKind}};
+
+    #[test]
+    fn test_property_inheritance() {
+        let blockdef = r#"
+        property friction: f32 = 1
+        property opaque: bool = true
+
+        set Crystal {
+            Glass,
+            Ice friction(0.05),
+            Quartz
+        } opaque(false) friction(0.5)
+
+        block {Crystal}
+        block Stone
+        block Obsidian friction(2) opaque(false)"#;
+        let ir = parse_file(blockdef, DefKind::Block).unwrap();
+        let code = generate(&ir);
+        assert!(code.contains("Block::Glass | Block::Quartz => 0.5"));
+        assert!(code.contains("Block::Ice => 0.05"));
+        assert!(code.contains("Block::Obsidian => 2.0"));
+        assert!(code.contains("Block::Glass | Block::Ice | Block::Obsidian | Block::Quartz => false"));
+        assert!(code.contains("_ => 1.0"));
+    }
+
+    #[test]
+    fn test_states() {
+        let blockdef = r#"
+        state axis: y | x | z
+        state facing: front | right | back | left
+        state lit: off | on
+
+        block OakLog state(axis)
+        block BirchLog state(axis)
+        block Kiln furnace(1300) state(lit) state(facing)"#;
+        let ir = parse_file(blockdef, DefKind::Block).unwrap();
+        let code = generate(&ir);
+        assert!(code.contains("pub enum Axis {\n\tY,\n\tX,\n\tZ\n}"));
+        assert!(code.contains("pub const VALUES: [Facing; 4] = [Facing::Front, Facing::Right, Facing::Back, Facing::Left];"));
+        assert!(code.contains("Block::BirchLog | Block::OakLog => 1,"));
+        // the facing of a kiln comes after its 2 lit values
+        assert!(code.contains("Block::Kiln => 2,"));
+        assert!(code.contains("Block::Kiln => Some(1300)"));
+        assert!(!code.contains("KilnOn"));
+        assert!(code.contains("Some(Facing::VALUES[(self.state / stride % 4) as usize])"));
+        assert!(code.contains("pub fn with_lit(mut self, lit: Lit) -> Self"));
+    }
+
+    #[test]
+    fn test_shapes() {
+        let blockdef = r#"
+        set Crop { Wheat }
+        state stage: sown | sprouting | ripe
+
+        block {Crop} state(stage) shape(cross)
+        block OakSlab shape(slab)
+        block Stone"#;
+        let ir = parse_file(blockdef, DefKind::Block).unwrap();
+        let code = generate(&ir);
+        // the stages of a crop are states of the same block
+        assert!(code.contains("Block::Wheat => Shape::Cross"));
+        assert!(code.contains("pub fn with_stage(mut self, stage: Stage) -> Self"));
+        assert!(code.contains("Block::OakSlab => Shape::Slab"));
+        assert!(code.contains("_ => Shape::Cube"));
+    }
+
+    #[test]
+    fn test_items() {
+        let itemdef = r#"
+        property efficiency: f32 = 1
+        property stackable: bool = true
+
+        set Food { Carrot, Potato }
+
+        item {Food}
+        item StoneAxe tool(axe) stackable(false)
+        item IronAxe tool(axe) efficiency(2) stackable(false)
+        item IronShovel tool(shovel) efficiency(2) stackable(false) name("Iron Spade")"#;
+        let ir = parse_file(itemdef, DefKind::Item).unwrap();
+        let code = generate(&ir);
+        assert!(code.contains("pub enum Item {\n\tCarrot,\n\tIronAxe,\n\tIronShovel,\n\tPotato,\n\tStoneAxe,\n\t#[serde(untagged)]\n\tBlock(Block)\n}"));
+        assert!(code.contains("Item::IronAxe | Item::StoneAxe => Some(ToolFamily::Axe)"));
+        assert!(code.contains("Item::IronAxe | Item::IronShovel => 2.0"));
+        assert!(code.contains("Item::IronAxe | Item::IronShovel | Item::StoneAxe => false"));
+        assert!(code.contains("Item::IronAxe => \"Iron Axe\""));
+        assert!(code.contains("Item::IronShovel => \"Iron Spade\""));
+        assert!(code.contains("Item::Block(block) => block.display_name()"));
+        assert!(code.contains("Item::Carrot => vec![ItemFamily::Food]"));
+        // the sets of items have no enum of their own
+        assert!(!code.contains("pub enum Food"));
+    }
+}
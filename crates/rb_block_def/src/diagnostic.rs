@@ -0,0 +1,77 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem in the block or item definitions, pointing to the fragment of the file it's about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Starts at 1
+    pub line: usize,
+    /// Starts at 1, counted in characters
+    pub column: usize,
+    /// The line the fragment is on
+    pub source_line: String,
+    /// Length of the fragment in characters, cut at the end of the line
+    pub len: usize,
+}
+
+/// Offset of `fragment` in `source`, the fragment must be a slice of the source
+fn offset(source: &str, fragment: &str) -> usize {
+    (fragment.as_ptr() as usize).checked_sub(source.as_ptr() as usize)
+        .filter(|offset| offset + fragment.len() <= source.len())
+        .expect("The fragment is not a part of the source")
+}
+
+/// The line `fragment` starts on, starting at 1
+pub(crate) fn line_of(source: &str, fragment: &str) -> usize {
+    source[..offset(source, fragment)].matches('\n').count() + 1
+}
+
+impl Diagnostic {
+    pub(crate) fn new(severity: Severity, source: &str, fragment: &str, message: String) -> Self {
+        let before = &source[..offset(source, fragment)];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        let source_line = source[line_start..].lines().next().unwrap_or_default().to_string();
+        let len = fragment.lines().next().unwrap_or_default().chars().count()
+            .min(source_line.chars().count() + 1 - column);
+        Diagnostic { severity, message, line: line_of(source, fragment), column, source_line, len }
+    }
+
+    pub(crate) fn error(source: &str, fragment: &str, message: String) -> Self {
+        Self::new(Severity::Error, source, fragment, message)
+    }
+
+    pub(crate) fn warning(source: &str, fragment: &str, message: String) -> Self {
+        Self::new(Severity::Warning, source, fragment, message)
+    }
+
+    /// The diagnostic as the compiler would print it, for the file at `path`
+    pub fn render(&self, path: &str) -> String {
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        // tabs are kept so the carets line up with the fragment
+        let indent: String = self.source_line.chars().take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "{}: {}\n{gutter}--> {path}:{}:{}\n{gutter} |\n{number} | {}\n{gutter} | {indent}{}",
+            self.severity, self.message, self.line, self.column, self.source_line, "^".repeat(self.len.max(1))
+        )
+    }
+}
@@ -1,11 +1,29 @@
 mod parse;
+mod check;
 mod code_gen;
+mod diagnostic;
+use check::check;
 use code_gen::generate;
-use parse::parse_file;
+use parse::{parse_file, DefKind};
+pub use diagnostic::{Diagnostic, Severity};
 
 
-pub fn generate_blocks(block_def: &str) -> Result<String, std::io::Error> {
-    let (_, ir) = parse_file(block_def).map_err(|e| std::io::Error::other(e.to_owned()))?;
-    let code = generate(&ir);
-    Ok(code)
+/// The code of the blocks along with the warnings about the definitions, or the errors that prevent generating it
+pub fn generate_blocks(block_def: &str) -> Result<(String, Vec<Diagnostic>), Vec<Diagnostic>> {
+    generate_code(block_def, DefKind::Block)
+}
+
+/// The code of the items, items.def is written like blocks.def with `item` declarations
+pub fn generate_items(item_def: &str) -> Result<(String, Vec<Diagnostic>), Vec<Diagnostic>> {
+    generate_code(item_def, DefKind::Item)
+}
+
+fn generate_code(def: &str, kind: DefKind) -> Result<(String, Vec<Diagnostic>), Vec<Diagnostic>> {
+    let ir = parse_file(def, kind).map_err(|err| vec![err])?;
+    let (errors, warnings): (Vec<_>, Vec<_>) = check(&ir).into_iter()
+        .partition(|diagnostic| diagnostic.severity == Severity::Error);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok((generate(&ir), warnings))
 }
@@ -1,15 +1,19 @@
-use std::{collections::{BTreeMap, BTreeSet}, str::FromStr};
+use std::{collections::{BTreeMap, BTreeSet}, fmt::Display, str::FromStr};
 
+use itertools::{Either, Itertools};
 use nom::{
-    bytes::complete::tag, character::complete::{alpha1, line_ending, multispace0, multispace1, space0, space1}, combinator::{eof, fail, opt}, error::{Error, ParseError}, multi::{many1, separated_list0, separated_list1}, sequence::delimited, IResult, Input, Parser
+    branch::alt, bytes::complete::{tag, take_till1, take_while1}, character::complete::{alpha1, char, line_ending, multispace0, multispace1, space0, space1}, combinator::{consumed, cut, eof, fail, map, opt, value, verify}, error::{context, ContextError, ErrorKind, ParseError}, multi::{many0, many1, separated_list0, separated_list1}, number::complete::recognize_float, sequence::{delimited, preceded, terminated}, IResult, Parser
 };
-use ron::de::SpannedError;
+use ron::{de::SpannedError, error::Error as RonError};
 use serde::{Deserialize, Serialize};
 
+use crate::diagnostic::{line_of, Diagnostic};
+
+/// Names are slices of the file, they tell where the diagnostics point to
 #[derive(Debug, PartialEq, Eq)]
-pub(crate) enum BlockFrag {
-    SetName(String),
-    Ident(String)
+pub(crate) enum BlockFrag<'a> {
+    SetName(&'a str),
+    Ident(&'a str)
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -17,7 +21,47 @@ pub(crate) enum BlockFrag {
 pub enum BlockFlag {
     Renewable(u32),
     Transparent,
-    Furnace(u32)
+    Furnace(u32),
+    Shape(BlockShape)
+}
+
+/// Blocks without a shape are cubes
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockShape {
+    Slab,
+    Stair,
+    Cross,
+    Pane,
+    Fence,
+    Torch
+}
+
+/// Tools of a family share their loot entries, declared with `tool(<family>)` in items.def
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolFamily {
+    Pickaxe,
+    Axe,
+    Shovel,
+    Hoe
+}
+
+/// blocks.def and items.def share their syntax, only the declarations of blocks and items differ
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum DefKind {
+    Block,
+    Item
+}
+
+impl DefKind {
+    /// The keyword of the declarations
+    pub(crate) fn keyword(self) -> &'static str {
+        match self {
+            DefKind::Block => "block",
+            DefKind::Item => "item",
+        }
+    }
 }
 
 impl FromStr for BlockFlag {
@@ -28,105 +72,474 @@ impl FromStr for BlockFlag {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct AddBlock(pub(crate) (Vec<BlockFrag>, BTreeSet<BlockFlag>));
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PropType {
+    Bool,
+    F32,
+    U32,
+    I32
+}
+
+impl Display for PropType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropType::Bool => write!(f, "bool"),
+            PropType::F32 => write!(f, "f32"),
+            PropType::U32 => write!(f, "u32"),
+            PropType::I32 => write!(f, "i32"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PropValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64)
+}
+
+impl PropValue {
+    /// The value as Rust code of the given type, None if it doesn't fit the type
+    pub(crate) fn to_rust(self, ty: PropType) -> Option<String> {
+        match (ty, self) {
+            (PropType::Bool, PropValue::Bool(b)) => Some(b.to_string()),
+            (PropType::F32, PropValue::Float(f)) => Some(format!("{:?}", f as f32)),
+            (PropType::F32, PropValue::Int(i)) => Some(format!("{:?}", i as f32)),
+            (PropType::U32, PropValue::Int(i)) if u32::try_from(i).is_ok() => Some(i.to_string()),
+            (PropType::I32, PropValue::Int(i)) if i32::try_from(i).is_ok() => Some(i.to_string()),
+            _ => None
+        }
+    }
+}
+
+impl Display for PropValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropValue::Bool(b) => write!(f, "{b}"),
+            PropValue::Int(i) => write!(f, "{i}"),
+            PropValue::Float(x) => write!(f, "{x:?}"),
+        }
+    }
+}
+
+/// Declared with `property <name>: <type> = <default>`, blocks that don't set it get the default
+#[derive(Debug, PartialEq)]
+pub(crate) struct PropertyDecl {
+    pub(crate) ty: PropType,
+    pub(crate) default: PropValue,
+}
+
+pub(crate) type Properties<'a> = BTreeMap<&'a str, PropValue>;
+
+/// Declared with `state <name>: <value> | <value> ...`, the first value is the default.
+/// Unlike properties, states are stored along with each block of the world.
+#[derive(Debug, PartialEq)]
+pub(crate) struct StateDecl<'a> {
+    pub(crate) values: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct AddBlock<'a> {
+    /// The pattern as written, such as `{Wood}{Log}`
+    pub(crate) pattern: &'a str,
+    pub(crate) frags: Vec<BlockFrag<'a>>,
+    pub(crate) flags: BTreeSet<BlockFlag>,
+    pub(crate) properties: Properties<'a>,
+    /// Added with `state(<name>)`, in order
+    pub(crate) states: Vec<&'a str>,
+    /// Added with `name("<name>")`, the name is split into words otherwise
+    pub(crate) display_name: Option<&'a str>,
+    /// Added with `tool(<family>)`, items only
+    pub(crate) tool: Option<ToolFamily>,
+}
+
+/// A variant of a set, its properties are inherited by the blocks made of it
+#[derive(Debug, PartialEq)]
+pub(crate) struct Variant<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) properties: Properties<'a>,
+}
 
 #[derive(Debug)]
-struct BlockSet {
-    name: String,
-    variants: BTreeSet<String>,
+pub(crate) struct BlockSet<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) variants: Vec<Variant<'a>>,
+    /// Inherited by the blocks declared with the set
+    pub(crate) defaults: Properties<'a>,
 }
 
 #[derive(Debug)]
-pub(crate) struct IR {
-    pub(crate) sets: BTreeMap<String, BTreeSet<String>>,
-    pub(crate) decl: Vec<AddBlock>,
+pub(crate) struct IR<'a> {
+    /// The whole file, the names of the IR are slices of it
+    pub(crate) source: &'a str,
+    pub(crate) kind: DefKind,
+    pub(crate) sets: BTreeMap<&'a str, BlockSet<'a>>,
+    pub(crate) properties: BTreeMap<&'a str, PropertyDecl>,
+    pub(crate) states: BTreeMap<&'a str, StateDecl<'a>>,
+    pub(crate) decl: Vec<AddBlock<'a>>,
+}
+
+impl<'a> IR<'a> {
+    /// Every block a declaration stands for, along with the set variants it's made of.
+    /// The sets of the pattern must be declared.
+    pub(crate) fn expand(&self, decl: &AddBlock<'a>) -> Vec<(String, Vec<&Variant<'a>>)> {
+        decl.frags.iter()
+            .map(|frag| match frag {
+                BlockFrag::Ident(ident) => vec![Either::Left(*ident)],
+                BlockFrag::SetName(set_name) => self.sets[set_name].variants.iter().map(Either::Right).collect()
+            }).multi_cartesian_product()
+            .map(|parts| {
+                let name = parts.iter().map(|part| part.either(|ident| ident, |variant| variant.name)).collect();
+                (name, parts.into_iter().filter_map(Either::right).collect())
+            }).collect()
+    }
 }
 
 #[derive(Debug)]
-enum Either<Left, Right> {
-    Left(Left),
-    Right(Right),
-}
-
-pub fn parse_file(input: &str) -> IResult<&str, IR> {
-    let (input, res): (&str, _) = many1(ws(parse_statement)).parse(input)?;
-    let (input, _) = eof(input)?;
-    let mut sets = BTreeMap::new();
-    let mut decl = Vec::new();
-    for either in res {
-        match either {
-            Either::Left(block_set) => { sets.insert(block_set.name, block_set.variants); }
-            Either::Right(add_block) => { decl.push(add_block); }
+enum Statement<'a> {
+    Set(BlockSet<'a>),
+    Block(AddBlock<'a>),
+    Property(&'a str, PropertyDecl),
+    State(&'a str, StateDecl<'a>),
+}
+
+/// Where parsing stopped and, when it's known, what was expected there
+#[derive(Debug, PartialEq)]
+pub(crate) struct DefError<'a> {
+    input: &'a str,
+    message: Option<String>,
+}
+
+impl<'a> ParseError<&'a str> for DefError<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        DefError { input, message: None }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<'a> ContextError<&'a str> for DefError<'a> {
+    /// The innermost context is the most precise one
+    fn add_context(_input: &'a str, context: &'static str, mut other: Self) -> Self {
+        other.message.get_or_insert_with(|| context.to_string());
+        other
+    }
+}
+
+impl<'a> DefError<'a> {
+    /// Points to the word parsing stopped at
+    fn to_diagnostic(&self, source: &str) -> Diagnostic {
+        let word = self.input.split_whitespace().next().map_or(&self.input[..0], |word| {
+            let start = word.as_ptr() as usize - self.input.as_ptr() as usize;
+            &self.input[start..start + word.len()]
+        });
+        let message = match (&self.message, word) {
+            (Some(message), _) => message.clone(),
+            (None, "") => "unexpected end of file".to_string(),
+            (None, word) => format!("unexpected `{word}`"),
+        };
+        Diagnostic::error(source, word, message)
+    }
+}
+
+type PResult<'a, T> = IResult<&'a str, T, DefError<'a>>;
+
+fn failure<'a>(input: &'a str, message: String) -> nom::Err<DefError<'a>> {
+    nom::Err::Failure(DefError { input, message: Some(message) })
+}
+
+/// Parses the whole file, stops at the first syntax error or conflicting declaration
+pub(crate) fn parse_file(source: &str, kind: DefKind) -> Result<IR<'_>, Diagnostic> {
+    let mut ir = IR { source, kind, sets: BTreeMap::new(), properties: BTreeMap::new(), states: BTreeMap::new(), decl: Vec::new() };
+    let mut input = source.trim_start();
+    while !input.is_empty() {
+        let (rest, statement) = parse_statement(input, kind).map_err(|err| match err {
+            nom::Err::Error(err) | nom::Err::Failure(err) => err.to_diagnostic(source),
+            nom::Err::Incomplete(_) => unreachable!("The parsers are complete")
+        })?;
+        let already_declared = |kind: &str, name: &str, previous: &str| Diagnostic::error(
+            source, name, format!("{kind} `{name}` is already declared on line {}", line_of(source, previous))
+        );
+        match statement {
+            Statement::Set(block_set) => {
+                if let Some(previous) = ir.sets.get(block_set.name) {
+                    return Err(already_declared("set", block_set.name, previous.name));
+                }
+                ir.sets.insert(block_set.name, block_set);
+            }
+            Statement::Block(add_block) => { ir.decl.push(add_block); }
+            Statement::Property(name, decl) => {
+                if let Some((previous, _)) = ir.properties.get_key_value(name) {
+                    return Err(already_declared("property", name, previous));
+                }
+                ir.properties.insert(name, decl);
+            }
+            Statement::State(name, decl) => {
+                if let Some((previous, _)) = ir.states.get_key_value(name) {
+                    return Err(already_declared("state", name, previous));
+                }
+                ir.states.insert(name, decl);
+            }
         }
+        input = rest.trim_start();
     }
-    Ok((input, IR { sets, decl }))
+    Ok(ir)
 }
 
-fn statement_end(input: &str) -> IResult<&str, ()> {
-    line_ending(input).map(|(input, _)| (input, ())).or_else(|_: nom::Err<Error<&str>>| eof(input).map(|(input, _)| (input, ())))
+fn statement_end(input: &str) -> PResult<'_, ()> {
+    alt((value((), line_ending), value((), eof))).parse(input)
 }
 
-fn parse_statement(input: &str) -> IResult<&str, Either<BlockSet, AddBlock>> {
-    let (input, stmt) = parse_set(input)
-        .map(|(input, set)| (input, Either::Left(set)))
-        .or_else(|_| parse_decl(input).map(|(input, decl)| (input, Either::Right(decl))))?;
-    let (input, _) = space0(input)?;
-    let (input, _) = statement_end(input)?;
+fn parse_statement(input: &str, kind: DefKind) -> PResult<'_, Statement<'_>> {
+    let (input, stmt) = match kind {
+        DefKind::Block => alt((
+            map(parse_set, Statement::Set),
+            map(parse_property_decl, |(name, decl)| Statement::Property(name, decl)),
+            map(parse_state_decl, |(name, decl)| Statement::State(name, decl)),
+            map(|input| parse_decl(input, kind), Statement::Block),
+            context("expected a `set`, `property`, `state` or `block` statement", fail()),
+        )).parse(input)?,
+        // items have no states
+        DefKind::Item => alt((
+            map(parse_set, Statement::Set),
+            map(parse_property_decl, |(name, decl)| Statement::Property(name, decl)),
+            map(|input| parse_decl(input, kind), Statement::Block),
+            context("expected a `set`, `property` or `item` statement", fail()),
+        )).parse(input)?,
+    };
+    let (input, _) = cut(context("expected the end of the line", (space0, statement_end))).parse(input)?;
     Ok((input, stmt))
 }
 
-fn parse_set(input: &str) -> IResult<&str, BlockSet> {
-    let (input, _) = tag("set")(input)?;
-    let (input, _) = multispace1(input)?;
-    let (input, name) = parse_ident(input)?;
-    let (input, _) = multispace1(input)?;
-    let (input, _) = tag("{")(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, variants) = separated_list1(ws(tag(",")), parse_ident).parse(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, _) = tag("}")(input)?;
-    Ok((input, BlockSet { name: name.to_string(), variants: BTreeSet::from_iter(variants.into_iter().map(String::from)) }))
-}
-
-fn parse_decl(input: &str) -> IResult<&str, AddBlock> {
-    let (input, (_, _, block_pattern, flags_opt)) = ((tag("block"), space1, many1(parse_block_frag), opt((space1, parse_block_flags)))).parse(input)?;
-    let flags = match flags_opt {
-        None => BTreeSet::new(),
-        Some((_, flags)) => flags
+fn parse_set(input: &str) -> PResult<'_, BlockSet<'_>> {
+    let (input, _) = (tag("set"), multispace1).parse(input)?;
+    cut(parse_set_body).parse(input)
+}
+
+fn parse_set_body(input: &str) -> PResult<'_, BlockSet<'_>> {
+    let (input, name) = context("expected the name of the set", parse_ident).parse(input)?;
+    let (input, _) = context("expected `{` after the name of the set", preceded(multispace0, tag("{"))).parse(input)?;
+    let (input, variants) = delimited(
+        multispace0,
+        terminated(separated_list0(ws(tag(",")), parse_variant), opt(ws(tag(",")))),
+        multispace0
+    ).parse(input)?;
+    let (input, _) = context("expected `,` or `}` after a variant", tag("}")).parse(input)?;
+    let (input, defaults) = parse_properties(input)?;
+    Ok((input, BlockSet { name, variants, defaults }))
+}
+
+/// `Name` followed by the properties of the variant, such as `Ice friction(0.05)`
+fn parse_variant(input: &str) -> PResult<'_, Variant<'_>> {
+    let (input, (name, properties)) = (parse_ident, parse_properties).parse(input)?;
+    Ok((input, Variant { name, properties }))
+}
+
+/// Properties separated by spaces, on the same line
+fn parse_properties(input: &str) -> PResult<'_, Properties<'_>> {
+    let (input, properties) = many0(preceded(space1, parse_property)).parse(input)?;
+    Ok((input, Properties::from_iter(properties)))
+}
+
+fn parse_property_decl(input: &str) -> PResult<'_, (&str, PropertyDecl)> {
+    let (input, _) = (tag("property"), space1).parse(input)?;
+    cut(parse_property_decl_body).parse(input)
+}
+
+fn parse_property_decl_body(input: &str) -> PResult<'_, (&str, PropertyDecl)> {
+    let (input, name) = context("expected the name of the property, in snake case", parse_property_name).parse(input)?;
+    let (input, _) = context("expected `:` after the name of the property", preceded(space0, char(':'))).parse(input)?;
+    let (input, ty) = context("expected a type: `bool`, `f32`, `u32` or `i32`", preceded(space0, parse_prop_type)).parse(input)?;
+    let (input, _) = context("expected `=` after the type", preceded(space0, char('='))).parse(input)?;
+    let (value_input, _) = space0(input)?;
+    let (input, default) = context("expected the default value of the property", parse_prop_value).parse(value_input)?;
+    if default.to_rust(ty).is_none() {
+        return Err(failure(value_input, format!("the default of `{name}` is not a {ty}")));
+    }
+    Ok((input, (name, PropertyDecl { ty, default })))
+}
+
+fn parse_state_decl(input: &str) -> PResult<'_, (&str, StateDecl<'_>)> {
+    let (input, _) = (tag("state"), space1).parse(input)?;
+    cut(parse_state_decl_body).parse(input)
+}
+
+fn parse_state_decl_body(input: &str) -> PResult<'_, (&str, StateDecl<'_>)> {
+    let (input, name) = context("expected the name of the state, in snake case", parse_property_name).parse(input)?;
+    let (input, _) = context("expected `:` after the name of the state", preceded(space0, char(':'))).parse(input)?;
+    let (input, values) = context(
+        "expected the values of the state, such as `y | x | z`",
+        preceded(space0, separated_list1((space0, char('|'), space0), parse_state_value))
+    ).parse(input)?;
+    Ok((input, (name, StateDecl { values })))
+}
+
+/// Values become enum variants, they start with a letter
+fn parse_state_value(input: &str) -> PResult<'_, &str> {
+    verify(
+        take_while1(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+        |value: &str| value.starts_with(|c: char| c.is_ascii_lowercase())
+    ).parse(input)
+}
+
+fn parse_prop_type(input: &str) -> PResult<'_, PropType> {
+    alt((
+        value(PropType::Bool, tag("bool")),
+        value(PropType::F32, tag("f32")),
+        value(PropType::U32, tag("u32")),
+        value(PropType::I32, tag("i32")),
+    )).parse(input)
+}
+
+fn parse_prop_value(input: &str) -> PResult<'_, PropValue> {
+    alt((
+        value(PropValue::Bool(true), tag("true")),
+        value(PropValue::Bool(false), tag("false")),
+        parse_number,
+    )).parse(input)
+}
+
+/// Numbers with a decimal point or an exponent are floats
+fn parse_number(input: &str) -> PResult<'_, PropValue> {
+    let (input_ok, number) = recognize_float(input)?;
+    let value = if number.contains(['.', 'e', 'E']) {
+        number.parse().ok().map(PropValue::Float)
+    } else {
+        number.parse().ok().map(PropValue::Int)
+    };
+    match value {
+        Some(value) => Ok((input_ok, value)),
+        None => fail().parse(input)
+    }
+}
+
+fn parse_property_name(input: &str) -> PResult<'_, &str> {
+    take_while1(|c: char| c.is_ascii_lowercase() || c == '_')(input)
+}
+
+/// `name(value)`, such as `friction(0.05)`
+fn parse_property(input: &str) -> PResult<'_, (&str, PropValue)> {
+    (parse_property_name, delimited(char('('), parse_prop_value, char(')'))).parse(input)
+}
+
+fn parse_decl(input: &str, kind: DefKind) -> PResult<'_, AddBlock<'_>> {
+    let (input, _) = (tag(kind.keyword()), space1).parse(input)?;
+    cut(|input| parse_decl_body(input, kind)).parse(input)
+}
+
+fn parse_decl_body(input: &str, kind: DefKind) -> PResult<'_, AddBlock<'_>> {
+    let expected_name = match kind {
+        DefKind::Block => "expected the name of the block, sets are written as `{Set}`",
+        DefKind::Item => "expected the name of the item, sets are written as `{Set}`",
     };
-    Ok((input, AddBlock((block_pattern, flags))))
+    let (input, (pattern, frags)) = context(expected_name, consumed(many1(parse_block_frag))).parse(input)?;
+    let (input, attrs) = many0(preceded(space1, |input| match kind {
+        DefKind::Block => parse_block_attr(input),
+        DefKind::Item => parse_item_attr(input),
+    })).parse(input)?;
+    let mut flags = BTreeSet::new();
+    let mut properties = Properties::new();
+    let mut states = Vec::new();
+    let mut display_name = None;
+    let mut tool = None;
+    for attr in attrs {
+        match attr {
+            BlockAttr::Flag(flag) => { flags.insert(flag); }
+            BlockAttr::Property(name, value) => { properties.insert(name, value); }
+            BlockAttr::State(name) => states.push(name),
+            BlockAttr::Name(name) => display_name = Some(name),
+            BlockAttr::Tool(family) => tool = Some(family),
+        }
+    }
+    Ok((input, AddBlock { pattern, frags, flags, properties, states, display_name, tool }))
 }
 
-fn parse_block_frag(input: &str) -> IResult<&str, BlockFrag> {
-    parse_set_name(input).or(parse_block_ident(input))
+fn parse_block_frag(input: &str) -> PResult<'_, BlockFrag<'_>> {
+    alt((parse_set_name, parse_block_ident)).parse(input)
 }
 
-fn parse_set_name(input: &str) -> IResult<&str, BlockFrag> {
-    let (input, ident) = delimited(tag("{"), parse_ident, tag("}")).parse(input)?;
-    Ok((input, BlockFrag::SetName(ident.to_string())))
+fn parse_set_name(input: &str) -> PResult<'_, BlockFrag<'_>> {
+    let (input, ident) = delimited(
+        tag("{"),
+        cut(context("expected the name of a set", parse_ident)),
+        cut(context("expected `}` after the name of the set", tag("}")))
+    ).parse(input)?;
+    Ok((input, BlockFrag::SetName(ident)))
 }
 
-fn parse_block_ident(input: &str) -> IResult<&str, BlockFrag> {
+fn parse_block_ident(input: &str) -> PResult<'_, BlockFrag<'_>> {
     let (input, ident) = parse_ident(input)?;
-    Ok((input, BlockFrag::Ident(ident.to_string())))
+    Ok((input, BlockFrag::Ident(ident)))
 }
 
-fn parse_ident(input: &str) -> IResult<&str, &str> {
+fn parse_ident(input: &str) -> PResult<'_, &str> {
     alpha1(input)
 }
 
-fn parse_block_flags(input: &str) -> IResult<&str, BTreeSet<BlockFlag>> {
-    let (input, flags) = separated_list0(space1, parse_block_flag).parse(input)?;
-    Ok((input, BTreeSet::from_iter(flags)))
+enum BlockAttr<'a> {
+    Flag(BlockFlag),
+    Property(&'a str, PropValue),
+    State(&'a str),
+    Name(&'a str),
+    Tool(ToolFamily),
 }
 
-fn parse_block_flag(input: &str) -> IResult<&str, BlockFlag> {
-    let (input_ok, flag_str) = input.split_at_position_complete(char::is_whitespace)?;
-    if let Ok(flag) = BlockFlag::from_str(flag_str) {
-        Ok((input_ok, flag))
-    } else {
-        fail().parse(input)
+/// `name("<name>")`, the name can have spaces but no quotes
+fn parse_display_name(input: &str) -> PResult<'_, &str> {
+    delimited(tag("name(\""), take_till1(|c: char| c == '"' || c == '\n'), tag("\")")).parse(input)
+}
+
+/// A flag, a property, `state(<name>)` or `name("<name>")`, anything else on the line of a block is an error
+fn parse_block_attr(input: &str) -> PResult<'_, BlockAttr<'_>> {
+    if let Ok((input_ok, name)) = parse_display_name(input) {
+        return Ok((input_ok, BlockAttr::Name(name)));
+    }
+    let (input_ok, word) = take_till1(char::is_whitespace)(input)?;
+    let err = match BlockFlag::from_str(word) {
+        Ok(flag) => return Ok((input_ok, BlockAttr::Flag(flag))),
+        Err(err) => err
+    };
+    if let Ok(("", (name, value))) = parse_property(word) {
+        return Ok((input_ok, BlockAttr::Property(name, value)));
+    }
+    if let Ok(("", name)) = delimited(tag("state("), parse_property_name, char(')')).parse(word) {
+        return Ok((input_ok, BlockAttr::State(name)));
+    }
+    let message = match err.code {
+        RonError::NoSuchEnumVariant { expected, found, .. } if word.starts_with("shape(") => format!(
+            "unknown shape `{found}`, expected one of {}", expected.iter().map(|shape| format!("`{shape}`")).join(", ")
+        ),
+        RonError::NoSuchEnumVariant { expected, found, .. } => format!(
+            "unknown flag `{found}`, expected a property, `state(<name>)`, `name(\"<name>\")` or one of {}",
+            expected.iter().map(|flag| format!("`{flag}`")).join(", ")
+        ),
+        code => format!("invalid flag `{word}`: {code}")
+    };
+    Err(failure(input, message))
+}
+
+/// A property, `tool(<family>)` or `name("<name>")`, anything else on the line of an item is an error
+fn parse_item_attr(input: &str) -> PResult<'_, BlockAttr<'_>> {
+    if let Ok((input_ok, name)) = parse_display_name(input) {
+        return Ok((input_ok, BlockAttr::Name(name)));
+    }
+    let (input_ok, word) = take_till1(char::is_whitespace)(input)?;
+    if let Ok(("", (name, value))) = parse_property(word) {
+        return Ok((input_ok, BlockAttr::Property(name, value)));
+    }
+    let Some(family) = word.strip_prefix("tool(").and_then(|rest| rest.strip_suffix(')')) else {
+        return Err(failure(input, format!(
+            "unknown attribute `{word}`, expected a property, `tool(<family>)` or `name(\"<name>\")`"
+        )));
+    };
+    match ron::from_str::<ToolFamily>(family) {
+        Ok(family) => Ok((input_ok, BlockAttr::Tool(family))),
+        Err(SpannedError { code: RonError::NoSuchEnumVariant { expected, found, .. }, .. }) => Err(failure(input, format!(
+            "unknown tool family `{found}`, expected one of {}", expected.iter().map(|family| format!("`{family}`")).join(", ")
+        ))),
+        Err(err) => Err(failure(input, format!("invalid tool family `{family}`: {}", err.code)))
     }
 }
 
@@ -172,20 +585,141 @@ mod tests {
         block Stripped{Wood}Log
         
         block IronOre renewable(10)"#;
-        let (_input, ir) = parse_file(blockdef).unwrap();
+        let ir = parse_file(blockdef, DefKind::Block).unwrap();
         println!("{ir:?}");
     }
 
     #[test]
     fn test_incorrect_flag() {
         let blockdef = r#"block IronOre apodhzipa"#;
-        assert!(parse_statement(&blockdef).is_err())
+        assert!(parse_statement(&blockdef, DefKind::Block).is_err())
     }
 
     #[test]
     fn test_parse_flag() {
         let blockdef = r#"block IronOre renewable(10)"#;
-        let (_, ir) = parse_decl(blockdef).unwrap();
-        assert_eq!(ir, AddBlock((vec![BlockFrag::Ident("IronOre".to_string())], BTreeSet::from([BlockFlag::Renewable(10)]))));
+        let (_, ir) = parse_decl(blockdef, DefKind::Block).unwrap();
+        assert_eq!(ir.frags, vec![BlockFrag::Ident("IronOre")]);
+        assert_eq!(ir.flags, BTreeSet::from([BlockFlag::Renewable(10)]));
+        assert_eq!(ir.properties, Properties::new());
+    }
+
+    #[test]
+    fn test_parse_shape() {
+        let blockdef = r#"block {Crop} shape(cross)"#;
+        let (_, ir) = parse_decl(blockdef, DefKind::Block).unwrap();
+        assert_eq!(ir.pattern, "{Crop}");
+        assert_eq!(ir.frags, vec![BlockFrag::SetName("Crop")]);
+        assert_eq!(ir.flags, BTreeSet::from([BlockFlag::Shape(BlockShape::Cross)]));
+    }
+
+    #[test]
+    fn test_parse_properties() {
+        let blockdef = r#"
+        property friction: f32 = 1.
+        property opaque: bool = true
+
+        set Crystal {
+            Glass,
+            Ice friction(0.05)
+        } opaque(false)
+
+        block {Crystal}
+        block Campfire furnace(600) opaque(false)
+        block Stone friction(2)"#;
+        let ir = parse_file(blockdef, DefKind::Block).unwrap();
+        assert_eq!(ir.properties["friction"], PropertyDecl { ty: PropType::F32, default: PropValue::Float(1.) });
+        assert_eq!(ir.sets["Crystal"].defaults, Properties::from([("opaque", PropValue::Bool(false))]));
+        assert_eq!(ir.sets["Crystal"].variants[1], Variant { name: "Ice", properties: Properties::from([("friction", PropValue::Float(0.05))]) });
+        assert_eq!(ir.decl[1].frags, vec![BlockFrag::Ident("Campfire")]);
+        assert_eq!(ir.decl[1].flags, BTreeSet::from([BlockFlag::Furnace(600)]));
+        assert_eq!(ir.decl[1].properties, Properties::from([("opaque", PropValue::Bool(false))]));
+        assert_eq!(ir.decl[2].properties["friction"], PropValue::Int(2));
+    }
+
+    /// Line, column and message of the error of a file that doesn't parse
+    fn parse_error(blockdef: &str) -> (usize, usize, String) {
+        let err = parse_file(blockdef, DefKind::Block).unwrap_err();
+        (err.line, err.column, err.message)
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(parse_error("block Iron{Ore}
+block Gold{Ore} glitter"),
+            (2, 17, "unknown flag `glitter`, expected a property, `state(<name>)`, `name(\"<name>\")` or one of `renewable`, `transparent`, `furnace`, `shape`".to_string()));
+        assert_eq!(parse_error("block Torch shape(stick)"),
+            (1, 13, "unknown shape `stick`, expected one of `slab`, `stair`, `cross`, `pane`, `fence`, `torch`".to_string()));
+        assert_eq!(parse_error("set Wood {
+    Oak
+    Birch
+}").0, 3);
+        assert_eq!(parse_error("set Wood {
+    Oak
+    Birch
+}").2, "expected `,` or `}` after a variant");
+        assert_eq!(parse_error("block {Wood}{Log
+"), (1, 17, "expected `}` after the name of the set".to_string()));
+        assert_eq!(parse_error("bloc Stone"), (1, 1, "expected a `set`, `property`, `state` or `block` statement".to_string()));
+        assert_eq!(parse_error("state axis: y | X"), (1, 15, "expected the end of the line".to_string()));
+        assert_eq!(parse_error("property light: u32 = -1"), (1, 23, "the default of `light` is not a u32".to_string()));
+        assert_eq!(parse_error("set Ore { Ore }
+set Ore { Ore }"), (2, 5, "set `Ore` is already declared on line 1".to_string()));
+    }
+
+    #[test]
+    fn test_parse_items() {
+        let itemdef = r#"
+        property durability: u32 = 0
+
+        item Coal
+        item IronPickaxe tool(pickaxe) durability(250) name("Iron Pick")"#;
+        let ir = parse_file(itemdef, DefKind::Item).unwrap();
+        assert_eq!(ir.decl[0].frags, vec![BlockFrag::Ident("Coal")]);
+        assert_eq!(ir.decl[0].tool, None);
+        assert_eq!(ir.decl[1].tool, Some(ToolFamily::Pickaxe));
+        assert_eq!(ir.decl[1].properties["durability"], PropValue::Int(250));
+        assert_eq!(ir.decl[1].display_name, Some("Iron Pick"));
+    }
+
+    /// Line, column and message of the error of an items file that doesn't parse
+    fn item_parse_error(itemdef: &str) -> (usize, usize, String) {
+        let err = parse_file(itemdef, DefKind::Item).unwrap_err();
+        (err.line, err.column, err.message)
+    }
+
+    #[test]
+    fn test_item_syntax_errors() {
+        assert_eq!(item_parse_error("item Spoon tool(spoon)"),
+            (1, 12, "unknown tool family `spoon`, expected one of `pickaxe`, `axe`, `shovel`, `hoe`".to_string()));
+        assert_eq!(item_parse_error("item Coal shiny"),
+            (1, 11, "unknown attribute `shiny`, expected a property, `tool(<family>)` or `name(\"<name>\")`".to_string()));
+        assert_eq!(item_parse_error("block Stone"), (1, 1, "expected a `set`, `property` or `item` statement".to_string()));
+        assert_eq!(item_parse_error("state lit: false | true"), (1, 1, "expected a `set`, `property` or `item` statement".to_string()));
+    }
+
+    #[test]
+    fn test_parse_states() {
+        let blockdef = r#"
+        state axis: y | x | z
+        state facing: front|right|back|left
+
+        block OakLog state(axis)
+        block Kiln furnace(1300) state(facing) opaque(false)"#;
+        let ir = parse_file(blockdef, DefKind::Block).unwrap();
+        assert_eq!(ir.states["axis"].values, vec!["y", "x", "z"]);
+        assert_eq!(ir.states["facing"].values, vec!["front", "right", "back", "left"]);
+        assert_eq!(ir.decl[0].states, vec!["axis"]);
+        assert_eq!(ir.decl[1].states, vec!["facing"]);
+        assert_eq!(ir.decl[1].flags, BTreeSet::from([BlockFlag::Furnace(1300)]));
+    }
+
+    #[test]
+    fn test_empty_set() {
+        let ir = parse_file("set Ore { }
+set Log {
+}", DefKind::Block).unwrap();
+        assert!(ir.sets["Ore"].variants.is_empty());
+        assert!(ir.sets["Log"].variants.is_empty());
     }
 }
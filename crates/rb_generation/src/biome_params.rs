@@ -1,4 +1,5 @@
-use crate::{biomes::Biome, coverage::CoverageTrait};
+use crate::coverage::CoverageTrait;
+use rb_world::Biome;
 use std::{
     collections::{BTreeMap, HashMap},
     ops::Index,
@@ -97,7 +98,7 @@ fn dist<const D: usize>(a: &[f32; D], b: &[f32; D]) -> f32 {
     res.sqrt() / (D as f32).sqrt()
 }
 
-/// Every Vec must have the same length, which is the number of block columns in a chunk (CHUNK_S1 * CHUNK_S1).
+/// Every Vec must have the same length, which is the number of block columns in the patch they were sampled over.
 pub struct BiomeParameters(pub HashMap<BiomeParam, Vec<f32>>);
 
 impl BiomeParameters {
@@ -0,0 +1,94 @@
+use crate::biomes::MOUNTAIN_H;
+use rb_world::WATER_H;
+use std::f32::consts::PI;
+
+/// Blocks between the equator and a pole, z = 0 is halfway between them
+const POLE_DISTANCE: f32 = 8000.;
+/// Share of the temperature that comes from the latitude, the rest comes from noise
+const LATITUDE_WEIGHT: f32 = 0.6;
+/// Temperature lost per block above sea level, the highest peaks are always freezing
+const LAPSE_RATE: f32 = 0.6 / (MOUNTAIN_H - WATER_H as f32);
+/// Share of the humidity that comes from the proximity of the ocean, the rest comes from noise
+const OCEAN_WEIGHT: f32 = 0.4;
+/// The prevailing wind blows towards +x, mountains this far upwind catch the rain before it gets there
+pub const UPWIND_DISTANCES: [f32; 3] = [80., 160., 240.];
+const RAIN_SHADOW: f32 = 0.8;
+/// Air forced up the windward slopes drops more rain
+const OROGRAPHIC_RAIN: f32 = 0.3;
+
+/// 1 at the equator, 0 at the poles
+fn latitude_temperature(z: f32) -> f32 {
+    0.5 - 0.5 * (PI * z / POLE_DISTANCE).sin()
+}
+
+/// Rough height of the ground of every block column. The biomes are picked with a temperature cooled down
+/// with it, because the actual height is only known once their layers are blended.
+pub fn estimated_heights(continentalness: &[f32], mountainness: &[f32]) -> Vec<f32> {
+    continentalness
+        .iter()
+        .zip(mountainness)
+        .map(|(continentalness, mountainness)| {
+            let land = ((continentalness - 0.4) / 0.2).clamp(0., 1.);
+            WATER_H as f32 + land * (15. + mountainness * (MOUNTAIN_H - WATER_H as f32 - 15.))
+        })
+        .collect()
+}
+
+/// Mixes the temperature noise with the latitude and cools it down with the height of the ground.
+/// `z` is the position of the first row of the patch, which is `width` blocks wide.
+pub fn apply_temperature(temperature: &mut [f32], z: f32, width: usize, heights: &[f32]) {
+    for (i, t) in temperature.iter_mut().enumerate() {
+        let latitude = latitude_temperature(z + (i / width) as f32);
+        let altitude = (heights[i] - WATER_H as f32).max(0.);
+        *t = (LATITUDE_WEIGHT * latitude + (1. - LATITUDE_WEIGHT) * *t - LAPSE_RATE * altitude)
+            .clamp(0., 1.);
+    }
+}
+
+/// Makes the humidity noise wetter near the ocean and on windward slopes, and dryer behind mountains.
+/// `upwind_mountainness` is sampled at `UPWIND_DISTANCES` from the patch.
+pub fn apply_humidity(
+    humidity: &mut [f32],
+    continentalness: &[f32],
+    mountainness: &[f32],
+    upwind_mountainness: &[Vec<f32>],
+) {
+    for (i, h) in humidity.iter_mut().enumerate() {
+        let ocean = (1. - continentalness[i]).clamp(0., 1.);
+        let upwind = upwind_mountainness.iter().map(|m| m[i]).fold(0., f32::max);
+        let shadow = (upwind - mountainness[i]).max(0.);
+        let lift = (mountainness[i] - upwind_mountainness[0][i]).max(0.);
+        *h = ((1. - OCEAN_WEIGHT) * *h + OCEAN_WEIGHT * ocean + OROGRAPHIC_RAIN * lift
+            - RAIN_SHADOW * shadow)
+            .clamp(0., 1.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cold_poles_and_peaks() {
+        let mut temperature = [0.5; 3];
+        let sea_level = [WATER_H as f32];
+        apply_temperature(&mut temperature[..1], -POLE_DISTANCE / 2., 1, &sea_level);
+        apply_temperature(&mut temperature[1..2], POLE_DISTANCE / 2., 1, &sea_level);
+        apply_temperature(&mut temperature[2..], -POLE_DISTANCE / 2., 1, &[MOUNTAIN_H]);
+        let [equator, pole, peak] = temperature;
+        assert!(equator > 0.7 && pole < 0.3);
+        assert!(peak < equator - 0.5);
+    }
+
+    #[test]
+    fn rain_shadow() {
+        let mut humidity = [0.5; 3];
+        let upwind = |m: f32| UPWIND_DISTANCES.map(|_| vec![m]);
+        // behind a mountain range, on its windward slope, in the open
+        apply_humidity(&mut humidity[..1], &[1.], &[0.], &upwind(1.));
+        apply_humidity(&mut humidity[1..2], &[1.], &[1.], &upwind(0.));
+        apply_humidity(&mut humidity[2..], &[1.], &[0.], &upwind(0.));
+        let [lee, windward, open] = humidity;
+        assert!(lee < open && open < windward);
+    }
+}
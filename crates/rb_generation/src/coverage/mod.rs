@@ -8,6 +8,7 @@ mod tests {
         biome_params::BiomePoints,
         coverage::{CoverageTrait, counter::Counter},
         plant_params::PlantRanges,
+        tree::Tree,
     };
 
     fn print_coverage<const D: usize, E: Clone + PartialEq + std::fmt::Debug>(
@@ -31,7 +32,7 @@ mod tests {
 
     #[test]
     pub fn print_plant_coverage() {
-        let plants: PlantRanges<3> = PlantRanges::from_csv("assets/gen/plants_condition.csv");
+        let plants: PlantRanges<3, Tree> = PlantRanges::from_csv("assets/gen/plants_condition.csv");
         print_coverage(plants, 0.05);
     }
 
@@ -0,0 +1,252 @@
+use crate::regions::{
+    Cached, PAD, PADDED_S, RegionCache, RegionPos, for_each_overlap, overlapping_regions,
+    padded_map,
+};
+use rb_world::{CHUNK_S2, ChunkPos2d};
+
+const MAX_CACHED_REGIONS: usize = 32;
+
+const DROPLETS_PER_CELL: f32 = 0.4;
+const DROPLET_LIFETIME: usize = 40;
+const INERTIA: f32 = 0.05;
+const CAPACITY: f32 = 4.;
+const MIN_CAPACITY: f32 = 0.01;
+const DEPOSIT_RATE: f32 = 0.3;
+const ERODE_RATE: f32 = 0.3;
+/// Caps the amount of ground a droplet can dig out in one step, in blocks
+const MAX_ERODE: f32 = 1.;
+const EVAPORATION: f32 = 0.02;
+const GRAVITY: f32 = 4.;
+
+const THERMAL_ITERATIONS: usize = 12;
+/// Height difference between neighboring block columns above which the ground slumps
+const TALUS: f32 = 1.5;
+const THERMAL_RATE: f32 = 0.25;
+
+/// Hydraulic and thermal erosion of the ground heights.
+/// Regions are eroded with some padding, and the regions overlapping a block column are blended
+/// with weights fading out at their edges, so the result doesn't depend on the order columns are generated in.
+pub struct Erosion {
+    seed: i32,
+    /// Height change of every block of the padded regions
+    regions: RegionCache<Vec<f32>>,
+}
+
+impl Erosion {
+    pub fn new(seed: i32) -> Self {
+        Erosion {
+            seed,
+            regions: RegionCache::new(MAX_CACHED_REGIONS),
+        }
+    }
+
+    /// Height change of every block column of `col`, indexed by `dx + dz * CHUNK_S1`.
+    /// `heights` gives the ground height of every block column of a chunk column before erosion.
+    pub fn column_deltas(
+        &self,
+        col: ChunkPos2d,
+        heights: impl Fn(ChunkPos2d) -> Vec<f32>,
+    ) -> Vec<f32> {
+        let mut deltas = vec![0.; CHUNK_S2];
+        let mut weights = vec![0.; CHUNK_S2];
+        for region in overlapping_regions(col) {
+            let region_deltas = self.region(region, &heights);
+            for_each_overlap(region, col, |i, x, z| {
+                let weight = edge_weight(x) * edge_weight(z);
+                deltas[i] += weight * region_deltas[x + z * PADDED_S];
+                weights[i] += weight;
+            });
+        }
+        for (delta, weight) in deltas.iter_mut().zip(weights) {
+            if weight > 0. {
+                *delta /= weight;
+            }
+        }
+        deltas
+    }
+
+    fn region(
+        &self,
+        region: RegionPos,
+        heights: &impl Fn(ChunkPos2d) -> Vec<f32>,
+    ) -> Cached<Vec<f32>> {
+        self.regions.get_or_compute(region, || {
+            let original = padded_map(region, heights);
+            let mut map = original.clone();
+            let mut rng = XorShift::new(region.prng(self.seed) as u64);
+            erode_hydraulic(&mut map, PADDED_S, &mut rng);
+            erode_thermal(&mut map, PADDED_S);
+            map.iter().zip(original).map(|(h, h0)| h - h0).collect()
+        })
+    }
+}
+
+/// Goes from 0 at the border of a padded region to 1 at twice the padding
+fn edge_weight(x: usize) -> f32 {
+    let edge = x.min(PADDED_S - 1 - x);
+    (edge as f32 / (2 * PAD) as f32).clamp(0., 1.)
+}
+
+/// Small deterministic generator, eroding a region must give the same result on every machine
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        XorShift(seed.max(1))
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Bilinear height and gradient at a point of the map
+fn height_and_gradient(map: &[f32], size: usize, x: f32, z: f32) -> (f32, f32, f32) {
+    let (cx, cz) = (x as usize, z as usize);
+    let (u, v) = (x - cx as f32, z - cz as f32);
+    let i = cx + cz * size;
+    let (h00, h10, h01, h11) = (map[i], map[i + 1], map[i + size], map[i + size + 1]);
+    let gx = (h10 - h00) * (1. - v) + (h11 - h01) * v;
+    let gz = (h01 - h00) * (1. - u) + (h11 - h10) * u;
+    let h = h00 * (1. - u) * (1. - v) + h10 * u * (1. - v) + h01 * (1. - u) * v + h11 * u * v;
+    (h, gx, gz)
+}
+
+/// Adds `amount` to the 4 cells around a point, weighted by proximity
+fn add_bilinear(map: &mut [f32], size: usize, x: f32, z: f32, amount: f32) {
+    let (cx, cz) = (x as usize, z as usize);
+    let (u, v) = (x - cx as f32, z - cz as f32);
+    let i = cx + cz * size;
+    map[i] += amount * (1. - u) * (1. - v);
+    map[i + 1] += amount * u * (1. - v);
+    map[i + size] += amount * (1. - u) * v;
+    map[i + size + 1] += amount * u * v;
+}
+
+/// Droplets roll down the slopes, picking up ground where they speed up and dropping it where they slow down
+fn erode_hydraulic(map: &mut [f32], size: usize, rng: &mut XorShift) {
+    let max = (size - 1) as f32;
+    let droplets = ((size * size) as f32 * DROPLETS_PER_CELL) as usize;
+    for _ in 0..droplets {
+        let (mut x, mut z) = (rng.next_f32() * max, rng.next_f32() * max);
+        let (mut dir_x, mut dir_z) = (0., 0.);
+        let mut speed = 1f32;
+        let mut water = 1.;
+        let mut sediment = 0.;
+        for _ in 0..DROPLET_LIFETIME {
+            let (h, gx, gz) = height_and_gradient(map, size, x, z);
+            dir_x = dir_x * INERTIA - gx * (1. - INERTIA);
+            dir_z = dir_z * INERTIA - gz * (1. - INERTIA);
+            let len = (dir_x * dir_x + dir_z * dir_z).sqrt();
+            if len < 1e-6 {
+                break;
+            }
+            dir_x /= len;
+            dir_z /= len;
+            let (nx, nz) = (x + dir_x, z + dir_z);
+            if nx < 0. || nz < 0. || nx >= max || nz >= max {
+                break;
+            }
+            let dh = height_and_gradient(map, size, nx, nz).0 - h;
+            let capacity = (-dh * speed * water * CAPACITY).max(MIN_CAPACITY);
+            if dh > 0. || sediment > capacity {
+                // fill the pit uphill or drop the excess
+                let amount = if dh > 0. {
+                    dh.min(sediment)
+                } else {
+                    (sediment - capacity) * DEPOSIT_RATE
+                };
+                sediment -= amount;
+                add_bilinear(map, size, x, z, amount);
+            } else {
+                let amount = ((capacity - sediment) * ERODE_RATE).min(-dh).min(MAX_ERODE);
+                sediment += amount;
+                add_bilinear(map, size, x, z, -amount);
+            }
+            speed = (speed * speed - dh * GRAVITY).max(0.).sqrt();
+            water *= 1. - EVAPORATION;
+            x = nx;
+            z = nz;
+        }
+    }
+}
+
+/// Slopes steeper than the talus angle slump onto their lower neighbors
+fn erode_thermal(map: &mut [f32], size: usize) {
+    let mut moved = vec![0.; size * size];
+    for _ in 0..THERMAL_ITERATIONS {
+        moved.fill(0.);
+        for z in 0..size {
+            for x in 0..size {
+                let i = x + z * size;
+                let neighbors = [
+                    (x > 0).then(|| i - 1),
+                    (x + 1 < size).then_some(i + 1),
+                    (z > 0).then(|| i - size),
+                    (z + 1 < size).then_some(i + size),
+                ];
+                for j in neighbors.into_iter().flatten() {
+                    let diff = map[i] - map[j];
+                    if diff > TALUS {
+                        let amount = (diff - TALUS) * THERMAL_RATE / 4.;
+                        moved[i] -= amount;
+                        moved[j] += amount;
+                    }
+                }
+            }
+        }
+        for (h, m) in map.iter_mut().zip(&moved) {
+            *h += m;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rb_world::{CHUNK_S1, CHUNK_S1I, Realm};
+
+    /// A ridge along the x axis
+    fn ridge(col: ChunkPos2d) -> Vec<f32> {
+        let mut heights = vec![0.; CHUNK_S2];
+        for dx in 0..CHUNK_S1 {
+            for dz in 0..CHUNK_S1 {
+                let z = (col.z * CHUNK_S1I + dz as i32) as f32;
+                let x = (col.x * CHUNK_S1I + dx as i32) as f32;
+                heights[dx + dz * CHUNK_S1] = 120. - (z - 40.).abs() + (x * 0.05).sin() * 8.;
+            }
+        }
+        heights
+    }
+
+    fn col(x: i32, z: i32) -> ChunkPos2d {
+        ChunkPos2d {
+            x,
+            z,
+            realm: Realm::default(),
+        }
+    }
+
+    #[test]
+    fn deterministic() {
+        let a = Erosion::new(3).column_deltas(col(0, 0), ridge);
+        let b = Erosion::new(3).column_deltas(col(0, 0), ridge);
+        assert_eq!(a, b);
+        assert!(a.iter().any(|delta| *delta != 0.));
+    }
+
+    #[test]
+    fn independent_of_generation_order() {
+        // the region border runs between these columns, both must see the same blended erosion
+        let erosion = Erosion::new(3);
+        erosion.column_deltas(col(3, 0), ridge);
+        let after_neighbor = erosion.column_deltas(col(4, 0), ridge);
+        assert_eq!(
+            after_neighbor,
+            Erosion::new(3).column_deltas(col(4, 0), ridge)
+        );
+    }
+}
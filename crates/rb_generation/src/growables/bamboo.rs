@@ -0,0 +1,24 @@
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
+use rb_block::Block;
+use super::utils::leaf_disk;
+const CLUMP: [(i32, i32); 6] = [(0, 0), (1, 0), (0, 1), (-1, 1), (1, -1), (-1, -1)];
+
+pub fn grow_bamboo(world: &TreeWorld, pos: BlockPos, seed: i32, dist: f32) {
+    let height = 16-(dist*8.) as i32;
+    let rng = pos.prng(seed);
+    for (i, (dx, dz)) in CLUMP.into_iter().enumerate() {
+        let mut stalk = pos + (dx, 0, dz);
+        // every stalk of the clump needs its own soil to root in
+        if !world.get_block_safe(stalk).is_fertile_soil() {
+            continue;
+        }
+        let stalk_height = height - 2*((rng >> (2*i)) & 0b11) as i32;
+        for _ in 0..stalk_height {
+            world.set_block(stalk, Block::BambooLog);
+            stalk.y += 1;
+        }
+        leaf_disk(world, stalk + (0, -2, 0), 1, Block::BambooLeaves);
+        world.set_if_empty(stalk, Block::BambooLeaves);
+    }
+}
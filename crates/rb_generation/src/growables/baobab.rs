@@ -1,16 +1,17 @@
-use rb_world::{BlockPos, VoxelWorld};
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
 use rb_block::Block;
 use super::utils::leaf_disk;
 const DIRS: [(i32, i32); 8] = [(-1, 1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
 
-fn baobab_leaves(world: &VoxelWorld, pos: BlockPos, dir_x: i32, dir_z: i32, size: usize) {
+fn baobab_leaves(world: &TreeWorld, pos: BlockPos, dir_x: i32, dir_z: i32, size: usize) {
     let pos = pos + (if dir_x == 1 {2} else {-1}, 0, if dir_z == 1 {2} else {-1});
     world.set_block(pos, Block::AcaciaLog);
     leaf_disk(world, pos + (0, -1, 0), 1, Block::AcaciaLeaves);
     leaf_disk(world, pos + (dir_x, 0, dir_z), size as u32, Block::AcaciaLeaves);
 }
 
-pub fn grow_baobab(world: &VoxelWorld, pos: BlockPos, seed: i32, dist: f32) {
+pub fn grow_baobab(world: &TreeWorld, pos: BlockPos, seed: i32, dist: f32) {
     let height = 30-(dist*6.) as i32;
     let mut pos = pos;
     let rng = pos.prng(seed);
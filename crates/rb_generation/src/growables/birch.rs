@@ -1,8 +1,9 @@
-use rb_world::{BlockPos, VoxelWorld};
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
 use rb_block::Block;
 use super::utils::leaf_disk;
 
-pub fn grow_birch(world: &VoxelWorld, pos: BlockPos, _seed: i32, dist: f32) {
+pub fn grow_birch(world: &TreeWorld, pos: BlockPos, _seed: i32, dist: f32) {
     let height = 7-(dist*3.) as i32;
     let mut pos = pos;
     for _ in 0..height {
@@ -0,0 +1,34 @@
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
+use rb_block::Block;
+const DIRS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+fn cactus_arm(world: &TreeWorld, pos: BlockPos, dir_x: i32, dir_z: i32, len: i32) {
+    let mut pos = pos + (dir_x, 0, dir_z);
+    world.set_if_empty(pos, Block::Cactus);
+    for _ in 0..len {
+        pos.y += 1;
+        world.set_if_empty(pos, Block::Cactus);
+    }
+}
+
+pub fn grow_cactus(world: &TreeWorld, pos: BlockPos, seed: i32, dist: f32) {
+    let height = 6-(dist*3.) as i32;
+    let base = pos;
+    let mut pos = pos;
+    let rng = pos.prng(seed);
+    for _ in 0..height {
+        world.set_block(pos, Block::Cactus);
+        pos.y += 1;
+    }
+    if height < 4 {
+        return;
+    }
+    let (dir_x, dir_z) = DIRS[rng & 0b11];
+    let arm_y = 2 + ((rng >> 2) % (height as usize - 3)) as i32;
+    cactus_arm(world, base + (0, arm_y, 0), dir_x, dir_z, 1 + ((rng >> 5) & 0b1) as i32);
+    if (rng >> 6) & 0b1 == 1 {
+        let arm_y = 2 + ((rng >> 7) % (height as usize - 3)) as i32;
+        cactus_arm(world, base + (0, arm_y, 0), -dir_x, -dir_z, 1);
+    }
+}
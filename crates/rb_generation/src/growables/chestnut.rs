@@ -0,0 +1,32 @@
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
+use rb_block::Block;
+use super::utils::leaf_disk;
+const DIRS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+pub fn grow_chestnut(world: &TreeWorld, pos: BlockPos, seed: i32, dist: f32) {
+    let height = 8-(dist*4.) as i32;
+    let mut pos = pos;
+    let rng = pos.prng(seed);
+    for _ in 0..height {
+        world.set_block(pos, Block::ChestnutLog);
+        pos.y += 1;
+    }
+    // the trunk forks into two thick branches holding a wide crown
+    let (dir_x, dir_z) = DIRS[rng & 0b11];
+    for (dx, dz) in [(dir_x, dir_z), (-dir_x, -dir_z)] {
+        world.set_block(pos + (dx, 0, dz), Block::ChestnutLog);
+        world.set_block(pos + (2*dx, 1, 2*dz), Block::ChestnutLog);
+    }
+    let radius = (height/2+1) as u32;
+    pos.y -= 1;
+    leaf_disk(world, pos, radius-1, Block::ChestnutLeaves);
+    pos.y += 1;
+    leaf_disk(world, pos, radius+1, Block::ChestnutLeaves);
+    pos.y += 1;
+    leaf_disk(world, pos, radius+1, Block::ChestnutLeaves);
+    pos.y += 1;
+    leaf_disk(world, pos, radius, Block::ChestnutLeaves);
+    pos.y += 1;
+    leaf_disk(world, pos, radius-2, Block::ChestnutLeaves);
+}
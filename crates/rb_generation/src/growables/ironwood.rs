@@ -0,0 +1,30 @@
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
+use rb_block::Block;
+use super::utils::leaf_disk;
+const DIRS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+pub fn grow_ironwood(world: &TreeWorld, pos: BlockPos, seed: i32, dist: f32) {
+    let height = 16-(dist*6.) as i32;
+    let mut pos = pos;
+    let rng = pos.prng(seed);
+    for i in 0..height {
+        // short branches stick out of the upper half of the trunk, each bearing a tuft of leaves
+        if i >= height/2 && i % 2 == 0 {
+            let (dir_x, dir_z) = DIRS[((i as usize/2)^rng) & 0b11];
+            let branch = pos + (2*dir_x, 1, 2*dir_z);
+            world.set_block(pos + (dir_x, 0, dir_z), Block::IronwoodLog);
+            world.set_block(branch, Block::IronwoodLog);
+            leaf_disk(world, branch + (0, 1, 0), 1, Block::IronwoodLeaves);
+        }
+        world.set_block(pos, Block::IronwoodLog);
+        pos.y += 1;
+    }
+    // flat, dense canopy on top
+    pos.y -= 1;
+    leaf_disk(world, pos, 3, Block::IronwoodLeaves);
+    pos.y += 1;
+    leaf_disk(world, pos, 3, Block::IronwoodLeaves);
+    pos.y += 1;
+    leaf_disk(world, pos, 2, Block::IronwoodLeaves);
+}
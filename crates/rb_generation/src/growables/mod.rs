@@ -6,10 +6,20 @@ mod spruce;
 mod sequoia;
 mod birch;
 mod cypress;
+mod palm;
+mod cactus;
+mod bamboo;
+mod chestnut;
+mod ironwood;
 pub use acacia::*;
 pub use baobab::*;
 pub use oak::*;
 pub use spruce::*;
 pub use sequoia::*;
 pub use birch::*;
-pub use cypress::*;
\ No newline at end of file
+pub use cypress::*;
+pub use palm::*;
+pub use cactus::*;
+pub use bamboo::*;
+pub use chestnut::*;
+pub use ironwood::*;
\ No newline at end of file
@@ -0,0 +1,29 @@
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
+use rb_block::Block;
+const LEANS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const FRONDS: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+pub fn grow_palm(world: &TreeWorld, pos: BlockPos, seed: i32, dist: f32) {
+    let height = 10-(dist*4.) as i32;
+    let mut pos = pos;
+    let rng = pos.prng(seed);
+    let (lean_x, lean_z) = LEANS[rng & 0b11];
+    for i in 0..height {
+        // the trunk bends away from the vertical as it grows
+        if i >= 3 && i % 3 == 0 {
+            pos = pos + (lean_x, 0, lean_z);
+        }
+        world.set_block(pos, Block::PalmLog);
+        pos.y += 1;
+    }
+    world.set_block(pos, Block::PalmLeaves);
+    for (i, (dx, dz)) in FRONDS.into_iter().enumerate() {
+        // straight fronds are longer than diagonal ones, and they all droop at the tip
+        let len = (if dx == 0 || dz == 0 { 4 } else { 3 }) - ((rng >> (2+i)) & 0b1) as i32;
+        for j in 1..=len {
+            let dy = if j > len/2 { -1 } else { 0 };
+            world.set_if_empty(pos + (dx*j, dy, dz*j), Block::PalmLeaves);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use rb_block::Block;
-use rb_world::{BlockPos, VoxelWorld};
+use crate::tree::TreeWorld;
+use rb_world::BlockPos;
 
 #[inline]
 fn signed_comb(x: i32, z: i32) -> Vec<(i32, i32)> {
@@ -12,7 +13,7 @@ fn signed_comb(x: i32, z: i32) -> Vec<(i32, i32)> {
 }
 
 #[inline]
-pub fn leaf_disk(world: &VoxelWorld, center: BlockPos, dist: u32, leaf: Block) {
+pub fn leaf_disk(world: &TreeWorld, center: BlockPos, dist: u32, leaf: Block) {
     let dist = dist as i32;
     for z in 0..=dist {
         let max_x = ((dist.pow(2) - z.pow(2)) as f32).sqrt() as i32;
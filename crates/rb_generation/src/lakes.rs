@@ -0,0 +1,197 @@
+use crate::regions::{
+    PAD, PADDED_S, REGION_S, RegionCache, for_each_overlap, overlapping_regions, padded_map,
+};
+use rb_world::{CHUNK_S2, ChunkPos2d, WATER_H};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+const MAX_CACHED_REGIONS: usize = 32;
+/// Smaller depressions are left dry
+const MIN_LAKE_SIZE: usize = 4;
+
+/// Ordered so that combining the lakes found in different regions keeps the wettest cell
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LakeCell {
+    #[default]
+    Dry,
+    /// Dry block column next to a lake
+    Shore,
+    /// Height of the water surface
+    Water(i32),
+}
+
+/// Finds the depressions of the ground and fills them up to the height where they would spill over.
+/// A lake belongs to the region its first block (in z then x order) is in, and is only kept if it stays
+/// away from the edges of the padded region, so a lake is the same whatever column it's looked up from.
+/// Depressions below sea level are left to the sea.
+pub struct Lakes {
+    regions: RegionCache<Vec<LakeCell>>,
+}
+
+impl Default for Lakes {
+    fn default() -> Self {
+        Lakes {
+            regions: RegionCache::new(MAX_CACHED_REGIONS),
+        }
+    }
+}
+
+impl Lakes {
+    /// Lakes and shores of every block column of `col`, indexed by `dx + dz * CHUNK_S1`.
+    /// `heights` gives the ground height of every block column of a chunk column.
+    pub fn column_cells(
+        &self,
+        col: ChunkPos2d,
+        heights: impl Fn(ChunkPos2d) -> Vec<i32>,
+    ) -> Vec<LakeCell> {
+        let mut cells = vec![LakeCell::Dry; CHUNK_S2];
+        for region in overlapping_regions(col) {
+            let region_cells = self
+                .regions
+                .get_or_compute(region, || find_lakes(&padded_map(region, &heights)));
+            for_each_overlap(region, col, |i, x, z| {
+                cells[i] = cells[i].max(region_cells[x + z * PADDED_S]);
+            });
+        }
+        cells
+    }
+}
+
+fn neighbors(i: usize) -> impl Iterator<Item = usize> {
+    let (x, z) = (i % PADDED_S, i / PADDED_S);
+    [
+        (x > 0).then(|| i - 1),
+        (x + 1 < PADDED_S).then_some(i + 1),
+        (z > 0).then(|| i - PADDED_S),
+        (z + 1 < PADDED_S).then_some(i + PADDED_S),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+fn find_lakes(heights: &[i32]) -> Vec<LakeCell> {
+    // Priority flood from the edges: the water level of a block is the lowest height
+    // that water has to rise to on its way out of the region
+    let mut levels = vec![None; PADDED_S * PADDED_S];
+    let mut heap = BinaryHeap::new();
+    for i in 0..PADDED_S * PADDED_S {
+        if neighbors(i).count() < 4 {
+            levels[i] = Some(heights[i]);
+            heap.push(Reverse((heights[i], i)));
+        }
+    }
+    while let Some(Reverse((level, i))) = heap.pop() {
+        for j in neighbors(i) {
+            if levels[j].is_none() {
+                let level = level.max(heights[j]);
+                levels[j] = Some(level);
+                heap.push(Reverse((level, j)));
+            }
+        }
+    }
+    let levels = levels.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+    let flooded = |i: usize| levels[i] > heights[i];
+    // Group the flooded blocks into lakes
+    let mut cells = vec![LakeCell::Dry; PADDED_S * PADDED_S];
+    let mut seen = vec![false; PADDED_S * PADDED_S];
+    let core = PAD as usize..(PAD + REGION_S) as usize;
+    for first in 0..PADDED_S * PADDED_S {
+        if seen[first] || !flooded(first) {
+            continue;
+        }
+        seen[first] = true;
+        let mut lake = vec![first];
+        let mut k = 0;
+        while k < lake.len() {
+            for j in neighbors(lake[k]) {
+                if !seen[j] && flooded(j) {
+                    seen[j] = true;
+                    lake.push(j);
+                }
+            }
+            k += 1;
+        }
+        // Neighboring regions may find the lake as well but only the one owning its first block keeps it.
+        // Blocks on the edge are never flooded, a lake touching them might go on past the padded region.
+        let owned = core.contains(&(first % PADDED_S)) && core.contains(&(first / PADDED_S));
+        let fits = lake
+            .iter()
+            .all(|&i| neighbors(i).all(|j| neighbors(j).count() == 4));
+        if !owned || !fits || lake.len() < MIN_LAKE_SIZE || levels[first] <= WATER_H {
+            continue;
+        }
+        for &i in &lake {
+            cells[i] = LakeCell::Water(levels[i]);
+        }
+        for &i in &lake {
+            for j in neighbors(i) {
+                if cells[j] == LakeCell::Dry {
+                    cells[j] = LakeCell::Shore;
+                }
+            }
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rb_world::{CHUNK_S1, CHUNK_S1I, Realm};
+
+    /// Flat ground with a bowl of radius 10 centered on (x, z)
+    fn bowl(x: i32, z: i32) -> impl Fn(ChunkPos2d) -> Vec<i32> {
+        move |col| {
+            let mut heights = vec![70; CHUNK_S2];
+            for dx in 0..CHUNK_S1 {
+                for dz in 0..CHUNK_S1 {
+                    let bx = col.x * CHUNK_S1I + dx as i32 - x;
+                    let bz = col.z * CHUNK_S1I + dz as i32 - z;
+                    let d2 = bx * bx + bz * bz;
+                    if d2 < 100 {
+                        heights[dx + dz * CHUNK_S1] = 60 + d2 / 10;
+                    }
+                }
+            }
+            // the rim is lowest on the east side
+            if col.x == x.div_euclid(CHUNK_S1I) + 1 {
+                heights.iter_mut().for_each(|h| *h = (*h).min(68));
+            }
+            heights
+        }
+    }
+
+    fn col(x: i32, z: i32) -> ChunkPos2d {
+        ChunkPos2d {
+            x,
+            z,
+            realm: Realm::default(),
+        }
+    }
+
+    #[test]
+    fn fills_to_spill_height() {
+        let cells = Lakes::default().column_cells(col(0, 0), bowl(30, 30));
+        assert_eq!(cells[30 + 30 * CHUNK_S1], LakeCell::Water(70));
+        assert_eq!(cells[5 + 5 * CHUNK_S1], LakeCell::Dry);
+        assert!(cells.contains(&LakeCell::Shore));
+    }
+
+    #[test]
+    fn no_lakes_below_sea_level() {
+        let sunken = |col| bowl(30, 30)(col).into_iter().map(|h| h - 20).collect();
+        let cells = Lakes::default().column_cells(col(0, 0), sunken);
+        assert!(cells.iter().all(|cell| *cell == LakeCell::Dry));
+    }
+
+    #[test]
+    fn consistent_across_regions() {
+        // the bowl straddles the border of two regions
+        let (x, z) = (REGION_S - 3, 30);
+        let lakes = Lakes::default();
+        let west = lakes.column_cells(col(x.div_euclid(CHUNK_S1I), 0), bowl(x, z));
+        let east = Lakes::default().column_cells(col(x.div_euclid(CHUNK_S1I) + 1, 0), bowl(x, z));
+        let dx = x.rem_euclid(CHUNK_S1I) as usize;
+        assert_eq!(west[dx + z as usize * CHUNK_S1], LakeCell::Water(68));
+        assert_eq!(east[z as usize * CHUNK_S1], LakeCell::Water(68));
+    }
+}
@@ -1,5 +1,5 @@
 use rb_block::Block;
-use rb_world::CHUNK_S1;
+use rb_world::{MAX_GEN_HEIGHT, WATER_H};
 
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -24,10 +24,27 @@ pub struct Layer {
 }
 
 impl Layer {
-    pub fn height(&self, dx: usize, dz: usize) -> f32 {
+    /// Height at the block column `i` of the patch the layer was generated over
+    pub fn height(&self, i: usize) -> f32 {
         match &self.height {
             Height::Constant(h) => *h,
-            Height::Noise(noise) => noise[dx + dz * CHUNK_S1],
+            Height::Noise(noise) => noise[i],
         }
     }
-}
\ No newline at end of file
+
+    /// Stretches the part of the layer that is above sea level
+    pub fn amplify(&mut self, factor: f32) {
+        if let LayerTag::Fixed { .. } = self.tag {
+            return;
+        }
+        let stretch = |h: &mut f32| {
+            if *h > WATER_H as f32 {
+                *h = (WATER_H as f32 + (*h - WATER_H as f32) * factor).min(MAX_GEN_HEIGHT as f32);
+            }
+        };
+        match &mut self.height {
+            Height::Constant(h) => stretch(h),
+            Height::Noise(noise) => noise.iter_mut().for_each(stretch),
+        }
+    }
+}
@@ -1,11 +1,27 @@
 mod biome_params;
 mod biomes;
+mod climate;
 mod coverage;
+mod erosion;
 mod growables;
+mod lakes;
 mod layer;
+mod patch;
+mod pipeline;
 mod plant_params;
 mod range_utils;
+mod regions;
+mod saplings;
+mod spawn;
 mod terrain;
 mod tree;
-pub use biomes::Biome;
-pub use terrain::TerrainGenerator;
+mod world_meta;
+pub use biome_params::{BiomeParam, BiomeParameters};
+pub use patch::Patch;
+pub use rb_world::Biome;
+pub use pipeline::{GEN_MARGIN, GenJob, GenStage, Pipeline, ProtoColumn};
+pub use saplings::{SaplingFate, SaplingGrowth};
+pub use spawn::{SPAWN_POINTS_PATH, SpawnPoints, find_spawn};
+pub use terrain::{ColumnSample, TerrainGenerator};
+pub use tree::Tree;
+pub use world_meta::{WORLD_META_PATH, WorldMeta, WorldPreset, parse_seed};
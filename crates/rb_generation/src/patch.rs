@@ -0,0 +1,82 @@
+use rb_world::{BlockPos2d, CHUNK_S1, CHUNK_S1I, ChunkPos2d, ChunkedPos2d};
+
+/// A rectangle of block columns, the noise sampled over it is indexed by `dx + dz * width`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Patch {
+    pub origin: BlockPos2d,
+    pub width: usize,
+    pub depth: usize,
+}
+
+impl Patch {
+    /// Every block column of a chunk column
+    pub fn column(col: ChunkPos2d) -> Self {
+        Patch {
+            origin: (col, ChunkedPos2d::default()).into(),
+            width: CHUNK_S1,
+            depth: CHUNK_S1,
+        }
+    }
+
+    pub fn point(pos: BlockPos2d) -> Self {
+        Patch {
+            origin: pos,
+            width: 1,
+            depth: 1,
+        }
+    }
+
+    /// Number of block columns
+    pub fn area(&self) -> usize {
+        self.width * self.depth
+    }
+
+    /// Position of the first block column, as the noise functions take it
+    pub fn real_pos(&self) -> (f32, f32) {
+        (self.origin.x as f32, self.origin.z as f32)
+    }
+
+    pub fn block_pos(&self, i: usize) -> BlockPos2d {
+        BlockPos2d {
+            x: self.origin.x + (i % self.width) as i32,
+            z: self.origin.z + (i / self.width) as i32,
+            realm: self.origin.realm,
+        }
+    }
+
+    /// Index of a block column of the patch
+    pub fn index(&self, pos: BlockPos2d) -> usize {
+        (pos.x - self.origin.x) as usize + (pos.z - self.origin.z) as usize * self.width
+    }
+
+    /// Splits the patch along the borders of the chunk columns it overlaps
+    pub fn split_by_column(&self) -> Vec<(ChunkPos2d, Patch)> {
+        let spans = |start: i32, len: usize| {
+            let end = start + len as i32;
+            (start.div_euclid(CHUNK_S1I)..=(end - 1).div_euclid(CHUNK_S1I)).map(move |c| {
+                let from = start.max(c * CHUNK_S1I);
+                let to = end.min((c + 1) * CHUNK_S1I);
+                (c, from, (to - from) as usize)
+            })
+        };
+        let mut parts = Vec::new();
+        for (cz, z, depth) in spans(self.origin.z, self.depth) {
+            for (cx, x, width) in spans(self.origin.x, self.width) {
+                let realm = self.origin.realm;
+                parts.push((
+                    ChunkPos2d {
+                        x: cx,
+                        z: cz,
+                        realm,
+                    },
+                    Patch {
+                        origin: BlockPos2d { x, z, realm },
+                        width,
+                        depth,
+                    },
+                ));
+            }
+        }
+        parts
+    }
+}
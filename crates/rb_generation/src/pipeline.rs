@@ -0,0 +1,413 @@
+use crate::biome_params::BiomeParameters;
+use itertools::iproduct;
+use rb_world::{Biome, ChunkPos2d};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    sync::{Condvar, Mutex},
+};
+
+/// Rings of partially generated columns needed around the fully generated ones
+pub const GEN_MARGIN: i32 = 3;
+
+/// Generation stages, in the order they run on a column
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GenStage {
+    #[default]
+    Empty,
+    /// Biome parameters
+    Noise,
+    /// Height changes from hydraulic and thermal erosion, when enabled
+    Erosion,
+    /// Blended biome layers and lakes
+    Surface,
+    /// Terrain that's dug out of the surface
+    Carvers,
+    /// Trees and other structures, the only stage that writes into neighboring columns.
+    /// It leaves the neighbors that are already published as they are, in case the column was unloaded and generated again.
+    Features,
+    /// Ground cover
+    Decorations,
+    /// Last stage, there's no light propagation yet so it only publishes the column
+    Lighting,
+}
+
+impl GenStage {
+    pub fn next(self) -> Option<GenStage> {
+        match self {
+            GenStage::Empty => Some(GenStage::Noise),
+            GenStage::Noise => Some(GenStage::Erosion),
+            GenStage::Erosion => Some(GenStage::Surface),
+            GenStage::Surface => Some(GenStage::Carvers),
+            GenStage::Carvers => Some(GenStage::Features),
+            GenStage::Features => Some(GenStage::Decorations),
+            GenStage::Decorations => Some(GenStage::Lighting),
+            GenStage::Lighting => None,
+        }
+    }
+
+    /// The stage all 8 neighboring columns must have reached before this stage can run
+    pub fn neighbor_requirement(self) -> GenStage {
+        match self {
+            GenStage::Empty
+            | GenStage::Noise
+            | GenStage::Erosion
+            | GenStage::Surface
+            | GenStage::Carvers => GenStage::Empty,
+            // features spill over in the neighbors, their terrain must not be written after
+            GenStage::Features => GenStage::Carvers,
+            // ground cover goes around the features of the neighbors as well
+            GenStage::Decorations => GenStage::Features,
+            // no more blocks will be written into the column by its neighbors
+            GenStage::Lighting => GenStage::Decorations,
+        }
+    }
+
+    pub fn writes_neighbors(self) -> bool {
+        self == GenStage::Features
+    }
+
+    /// Stage a column must reach when it's `excess` columns beyond the fully generated area
+    pub fn target_at(excess: i32) -> Option<GenStage> {
+        let mut stage = GenStage::Lighting;
+        for _ in 0..excess {
+            stage = stage.neighbor_requirement();
+        }
+        if stage == GenStage::Empty {
+            None
+        } else {
+            Some(stage)
+        }
+    }
+}
+
+/// Data passed from one stage to the next
+#[derive(Default)]
+pub struct ProtoColumn {
+    pub params: Option<BiomeParameters>,
+    /// Height change of every block column, dropped once the surface is written
+    pub erosion: Option<Vec<f32>>,
+    pub biomes: Vec<Biome>,
+}
+
+/// A stage to run on a column, handed out by the pipeline and returned to it once done
+pub struct GenJob {
+    pub col: ChunkPos2d,
+    pub stage: GenStage,
+    pub proto: ProtoColumn,
+}
+
+struct ColumnState {
+    /// Last stage that completed
+    stage: GenStage,
+    target: GenStage,
+    priority: i32,
+    /// Stage being run by a worker, the proto column is handed to the worker in the meantime
+    busy: Option<GenStage>,
+    proto: Option<ProtoColumn>,
+    /// No longer wanted, will be unloaded as soon as no worker touches it
+    released: bool,
+}
+
+#[derive(Default)]
+struct PipelineState {
+    columns: HashMap<ChunkPos2d, ColumnState>,
+    heap: BinaryHeap<Reverse<(i32, ChunkPos2d)>>,
+    /// Columns that are in the heap, to avoid queuing them twice
+    queued: HashSet<ChunkPos2d>,
+    closed: bool,
+}
+
+fn neighbors(col: ChunkPos2d) -> impl Iterator<Item = ChunkPos2d> {
+    iproduct!(-1..=1, -1..=1)
+        .filter(|&(dx, dz)| dx != 0 || dz != 0)
+        .map(move |(dx, dz)| ChunkPos2d {
+            x: col.x + dx,
+            z: col.z + dz,
+            realm: col.realm,
+        })
+}
+
+impl PipelineState {
+    fn runnable_stage(&self, col: ChunkPos2d) -> Option<GenStage> {
+        let state = self.columns.get(&col)?;
+        if state.released || state.busy.is_some() || state.stage >= state.target {
+            return None;
+        }
+        let next = state.stage.next()?;
+        let requirement = next.neighbor_requirement();
+        for neighbor in neighbors(col) {
+            let neighbor_state = self.columns.get(&neighbor);
+            if requirement > GenStage::Empty
+                && neighbor_state.is_none_or(|n| n.released || n.stage < requirement)
+            {
+                return None;
+            }
+            // two neighbors writing in each other at the same time would make generation non deterministic
+            if next.writes_neighbors()
+                && neighbor_state.is_some_and(|n| n.busy.is_some_and(GenStage::writes_neighbors))
+            {
+                return None;
+            }
+        }
+        Some(next)
+    }
+
+    fn can_unload(&self, col: ChunkPos2d) -> bool {
+        let Some(state) = self.columns.get(&col) else {
+            return false;
+        };
+        state.released
+            && state.busy.is_none()
+            && neighbors(col).all(|neighbor| {
+                self.columns
+                    .get(&neighbor)
+                    .is_none_or(|n| !n.busy.is_some_and(GenStage::writes_neighbors))
+            })
+    }
+
+    fn queue(&mut self, col: ChunkPos2d) {
+        let Some(state) = self.columns.get(&col) else {
+            return;
+        };
+        if state.released || state.busy.is_some() || state.stage >= state.target {
+            return;
+        }
+        if self.queued.insert(col) {
+            self.heap.push(Reverse((state.priority, col)));
+        }
+    }
+}
+
+/// Schedules generation stages on columns, closest columns first.
+/// A column is parked when its neighbors are not ready and queued again when one of them progresses.
+#[derive(Default)]
+pub struct Pipeline {
+    state: Mutex<PipelineState>,
+    available: Condvar,
+}
+
+impl Pipeline {
+    /// Sets the stage every column should reach along with its priority (lowest first).
+    /// Returns the columns that are not wanted anymore and can be unloaded right away,
+    /// the others are returned by `finish` once the workers are done with them.
+    pub fn update_area(&self, wanted: HashMap<ChunkPos2d, (GenStage, i32)>) -> Vec<ChunkPos2d> {
+        let mut state = self.state.lock().unwrap();
+        let released = state
+            .columns
+            .iter_mut()
+            .filter(|(col, _)| !wanted.contains_key(col))
+            .map(|(col, col_state)| {
+                col_state.released = true;
+                *col
+            })
+            .collect::<Vec<_>>();
+        let unloaded = released
+            .into_iter()
+            .filter(|col| state.can_unload(*col))
+            .collect::<Vec<_>>();
+        for col in &unloaded {
+            state.columns.remove(col);
+        }
+        for (col, (target, priority)) in wanted {
+            let col_state = state.columns.entry(col).or_insert_with(|| ColumnState {
+                stage: GenStage::Empty,
+                target,
+                priority,
+                busy: None,
+                proto: Some(ProtoColumn::default()),
+                released: false,
+            });
+            // stages can't be undone
+            col_state.target = target.max(col_state.stage);
+            col_state.priority = priority;
+            col_state.released = false;
+        }
+        state.heap.clear();
+        state.queued.clear();
+        let cols = state.columns.keys().copied().collect::<Vec<_>>();
+        for col in cols {
+            state.queue(col);
+        }
+        self.available.notify_all();
+        unloaded
+    }
+
+    fn take_job(state: &mut PipelineState) -> Option<GenJob> {
+        while let Some(Reverse((_, col))) = state.heap.pop() {
+            state.queued.remove(&col);
+            let Some(stage) = state.runnable_stage(col) else {
+                // parked until a neighbor progresses
+                continue;
+            };
+            let col_state = state.columns.get_mut(&col).unwrap();
+            col_state.busy = Some(stage);
+            let proto = col_state.proto.take().unwrap();
+            return Some(GenJob { col, stage, proto });
+        }
+        None
+    }
+
+    /// Blocks until a stage can run, returns None once the pipeline is closed
+    pub fn next_job(&self) -> Option<GenJob> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.closed {
+                return None;
+            }
+            if let Some(job) = Self::take_job(&mut state) {
+                return Some(job);
+            }
+            state = self.available.wait(state).unwrap();
+        }
+    }
+
+    /// Returns None if no stage can run right now
+    pub fn try_next_job(&self) -> Option<GenJob> {
+        Self::take_job(&mut self.state.lock().unwrap())
+    }
+
+    /// Records the completion of a job, returns the columns that can now be unloaded
+    pub fn finish(&self, job: GenJob) -> Vec<ChunkPos2d> {
+        let mut state = self.state.lock().unwrap();
+        let col_state = state
+            .columns
+            .get_mut(&job.col)
+            .expect("Column was removed while being generated");
+        col_state.stage = job.stage;
+        col_state.busy = None;
+        col_state.proto = Some(job.proto);
+        let unloaded = [job.col]
+            .into_iter()
+            .chain(neighbors(job.col))
+            .filter(|col| state.can_unload(*col))
+            .collect::<Vec<_>>();
+        for col in &unloaded {
+            state.columns.remove(col);
+        }
+        for col in [job.col].into_iter().chain(neighbors(job.col)) {
+            state.queue(col);
+        }
+        self.available.notify_all();
+        unloaded
+    }
+
+    pub fn stage(&self, col: ChunkPos2d) -> Option<GenStage> {
+        let state = self.state.lock().unwrap();
+        state.columns.get(&col).map(|col_state| col_state.stage)
+    }
+
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.available.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rb_world::chunk_area;
+
+    fn area(radius: i32) -> HashMap<ChunkPos2d, (GenStage, i32)> {
+        let center = ChunkPos2d::default();
+        chunk_area(&center, radius + GEN_MARGIN)
+            .filter_map(|col| {
+                let dist = col.dist(center);
+                GenStage::target_at(dist - radius).map(|stage| (col, (stage, dist)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn target_rings() {
+        assert_eq!(GenStage::target_at(0), Some(GenStage::Lighting));
+        assert_eq!(GenStage::target_at(1), Some(GenStage::Decorations));
+        assert_eq!(GenStage::target_at(GEN_MARGIN), Some(GenStage::Carvers));
+        assert_eq!(GenStage::target_at(GEN_MARGIN + 1), None);
+    }
+
+    #[test]
+    fn stages_wait_for_neighbors() {
+        let pipeline = Pipeline::default();
+        let wanted = area(1);
+        assert!(pipeline.update_area(wanted.clone()).is_empty());
+        let mut stages: HashMap<ChunkPos2d, GenStage> = HashMap::new();
+        while let Some(job) = pipeline.try_next_job() {
+            let requirement = job.stage.neighbor_requirement();
+            for neighbor in neighbors(job.col) {
+                let neighbor_stage = stages.get(&neighbor).copied().unwrap_or_default();
+                assert!(
+                    neighbor_stage >= requirement,
+                    "{:?} ran on {:?} with a neighbor at {:?}",
+                    job.stage,
+                    job.col,
+                    neighbor_stage
+                );
+            }
+            stages.insert(job.col, job.stage);
+            assert!(pipeline.finish(job).is_empty());
+        }
+        for (col, (target, _)) in wanted {
+            assert_eq!(pipeline.stage(col), Some(target));
+        }
+    }
+
+    #[test]
+    fn stages_run_once() {
+        let pipeline = Pipeline::default();
+        let wanted = area(1);
+        pipeline.update_area(wanted.clone());
+        let mut ran = HashSet::new();
+        let mut held = None;
+        while let Some(job) = pipeline.try_next_job() {
+            assert!(
+                ran.insert((job.col, job.stage)),
+                "{:?} ran twice on {:?}",
+                job.stage,
+                job.col
+            );
+            // a busy column is not handed out again, even when the area is requeued
+            if held.is_none() {
+                held = Some(job);
+                pipeline.update_area(wanted.clone());
+                continue;
+            }
+            assert_ne!(held.as_ref().map(|held| held.col), Some(job.col));
+            pipeline.finish(job);
+        }
+        let held = held.unwrap();
+        let col = held.col;
+        pipeline.finish(held);
+        while let Some(job) = pipeline.try_next_job() {
+            assert!(
+                ran.insert((job.col, job.stage)),
+                "{:?} ran twice on {:?}",
+                job.stage,
+                job.col
+            );
+            pipeline.finish(job);
+        }
+        assert_eq!(pipeline.stage(col), Some(wanted[&col].0));
+    }
+
+    #[test]
+    fn unload_waits_for_writing_neighbors() {
+        let pipeline = Pipeline::default();
+        let wanted = area(1);
+        pipeline.update_area(wanted.clone());
+        // run jobs until one is writing into its neighbors
+        let job = loop {
+            let job = pipeline.try_next_job().unwrap();
+            if job.stage.writes_neighbors() {
+                break job;
+            }
+            pipeline.finish(job);
+        };
+        let neighbor = neighbors(job.col).next().unwrap();
+        let mut shrunk = wanted.clone();
+        shrunk.remove(&neighbor);
+        assert!(!pipeline.update_area(shrunk).contains(&neighbor));
+        assert!(pipeline.finish(job).contains(&neighbor));
+        assert_eq!(pipeline.stage(neighbor), None);
+    }
+}
@@ -1,15 +1,15 @@
 use std::{ops::Range, str::FromStr};
-use crate::{coverage::CoverageTrait, range_utils::{range_from_str, RangesUtil}, tree::Tree};
+use crate::{coverage::CoverageTrait, range_utils::{range_from_str, RangesUtil}};
 
-pub struct PlantRanges<const D: usize>(Vec<([Range<f32>; D], Tree)>);
+pub struct PlantRanges<const D: usize, E>(Vec<([Range<f32>; D], E)>);
 
-impl<const D: usize> PlantRanges<D> {
+impl<const D: usize, E: FromStr> PlantRanges<D, E> {
     pub fn from_csv(path: &str) -> Self {
         let mut res = Vec::new();
         let mut reader = csv::Reader::from_path(path).unwrap();
         for record in reader.records() {
             let record = record.unwrap();
-            let Ok(elem) = Tree::from_str(&record[0]) else {
+            let Ok(elem) = E::from_str(record[0].trim()) else {
                 panic!("Failed to deserialize value '{}'", &record[0]);
             };
             let intervals: [Range<f32>; D] = core::array::from_fn(|i| range_from_str(&record[i+1]).unwrap());
@@ -19,8 +19,17 @@ impl<const D: usize> PlantRanges<D> {
     }
 }
 
-impl<const D: usize> CoverageTrait<D, Tree> for PlantRanges<D> {
-    fn closest(&self, point: [f32; D]) -> (&Tree, f32) {
+impl<const D: usize, E: PartialEq> PlantRanges<D, E> {
+    /// Signed distance from the point to the ranges of the element, positive inside of them
+    pub fn dist_of(&self, elem: &E, point: &[f32; D]) -> Option<f32> {
+        self.0.iter()
+            .find(|(_, value)| value == elem)
+            .map(|(ranges, _)| ranges.sign_dist(point))
+    }
+}
+
+impl<const D: usize, E: Clone> CoverageTrait<D, E> for PlantRanges<D, E> {
+    fn closest(&self, point: [f32; D]) -> (&E, f32) {
         let mut candidates = self.0.iter()
             .map(|(ranges, value)| (value, ranges.sign_dist(&point)));
         let mut res = candidates.next().unwrap();
@@ -0,0 +1,133 @@
+use itertools::iproduct;
+use rb_world::{CHUNK_S1, CHUNK_S1I, ChunkPos2d, Pos2d};
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    ops::Deref,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+/// Chunk columns per side of a region
+const REGION_COLS: i32 = 4;
+pub const REGION_S: i32 = REGION_COLS * CHUNK_S1I;
+/// Blocks around a region that are processed along with it, so its borders see what's beyond them
+pub const PAD: i32 = 32;
+pub const PADDED_S: usize = (REGION_S + 2 * PAD) as usize;
+
+/// Regions are processed as a whole by the generation passes that need to look far around a column
+pub type RegionPos = Pos2d<{ REGION_S as usize }>;
+
+/// Position of the first block of the padded region
+pub fn padded_origin(region: RegionPos) -> (i32, i32) {
+    (region.x * REGION_S - PAD, region.z * REGION_S - PAD)
+}
+
+/// Regions whose padded area overlaps the chunk column
+pub fn overlapping_regions(col: ChunkPos2d) -> impl Iterator<Item = RegionPos> {
+    let regions =
+        |o: i32| (o - PAD).div_euclid(REGION_S)..=(o + CHUNK_S1I - 1 + PAD).div_euclid(REGION_S);
+    iproduct!(regions(col.x * CHUNK_S1I), regions(col.z * CHUNK_S1I)).map(move |(x, z)| RegionPos {
+        x,
+        z,
+        realm: col.realm,
+    })
+}
+
+/// Calls `f(i, x, z)` for every block column of `col` that is in the padded region,
+/// `i` is the index in the chunk column and `(x, z)` the position in the padded region.
+pub fn for_each_overlap(
+    region: RegionPos,
+    col: ChunkPos2d,
+    mut f: impl FnMut(usize, usize, usize),
+) {
+    let (ox, oz) = padded_origin(region);
+    for dx in 0..CHUNK_S1 {
+        for dz in 0..CHUNK_S1 {
+            let x = col.x * CHUNK_S1I + dx as i32 - ox;
+            let z = col.z * CHUNK_S1I + dz as i32 - oz;
+            if (0..PADDED_S as i32).contains(&x) && (0..PADDED_S as i32).contains(&z) {
+                f(dx + dz * CHUNK_S1, x as usize, z as usize);
+            }
+        }
+    }
+}
+
+/// Gathers a value for every block of the padded region, `values` gives them for a whole chunk column
+pub fn padded_map<T: Copy + Default>(
+    region: RegionPos,
+    values: impl Fn(ChunkPos2d) -> Vec<T>,
+) -> Vec<T> {
+    let (ox, oz) = padded_origin(region);
+    let mut map = vec![T::default(); PADDED_S * PADDED_S];
+    let cols = |o: i32| o.div_euclid(CHUNK_S1I)..=(o + PADDED_S as i32 - 1).div_euclid(CHUNK_S1I);
+    for (x, z) in iproduct!(cols(ox), cols(oz)) {
+        let col = ChunkPos2d {
+            x,
+            z,
+            realm: region.realm,
+        };
+        let col_values = values(col);
+        for_each_overlap(region, col, |i, x, z| map[x + z * PADDED_S] = col_values[i]);
+    }
+    map
+}
+
+/// A computed region, stays valid after it's evicted from the cache
+pub struct Cached<T>(Arc<OnceLock<T>>);
+
+impl<T> Deref for Cached<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.0.get().unwrap()
+    }
+}
+
+/// Keeps the most recently used regions, computed by the first worker that needs them.
+/// Evicted regions are computed again if needed, which must give the same result.
+/// Also keyed by chunk column for values that are costly to compute but only depend on the column.
+pub struct RegionCache<T, K = RegionPos> {
+    capacity: usize,
+    state: Mutex<CacheState<T, K>>,
+}
+
+struct CacheState<T, K> {
+    cells: HashMap<K, Arc<OnceLock<T>>>,
+    /// Insertion order, oldest first
+    order: VecDeque<K>,
+}
+
+impl<T, K: Hash + Eq + Copy> RegionCache<T, K> {
+    pub fn new(capacity: usize) -> Self {
+        RegionCache {
+            capacity,
+            state: Mutex::new(CacheState {
+                cells: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+        }
+    }
+
+    pub fn get_or_compute(&self, region: K, compute: impl FnOnce() -> T) -> Cached<T> {
+        let cell = {
+            let mut state = self.state.lock().unwrap();
+            let CacheState { cells, order } = &mut *state;
+            match cells.get(&region) {
+                Some(cell) => cell.clone(),
+                None => {
+                    let cell = Arc::new(OnceLock::new());
+                    cells.insert(region, cell.clone());
+                    order.push_back(region);
+                    if order.len() > self.capacity {
+                        let oldest = order.pop_front().unwrap();
+                        cells.remove(&oldest);
+                    }
+                    cell
+                }
+            }
+        };
+        // the lock isn't held while computing, workers needing other regions are not blocked
+        cell.get_or_init(compute);
+        Cached(cell)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    coverage::CoverageTrait,
+    plant_params::PlantRanges,
+    terrain::GEN_DATA_DIR,
+    tree::Tree,
+    world_meta::{SeedStream, prng_seed},
+};
+use bevy::prelude::Resource;
+use rb_block::Block;
+use rb_world::{BlockPos, BlockPos2d, MAX_GEN_HEIGHT, VoxelWorld};
+use std::time::Duration;
+
+/// Seconds a sapling takes to grow right in the middle of its climate ranges
+const FASTEST_GROWTH: f32 = 150.;
+/// Seconds a sapling takes to grow at the edge of its climate ranges, or to wither
+const SLOWEST_GROWTH: f32 = 600.;
+/// How far outside of its climate ranges a sapling still has a chance to grow
+const TOLERANCE: f32 = 0.3;
+/// Saplings of trees that are not the best fit for the climate grow slower
+const OUTCOMPETED_SLOWDOWN: f32 = 2.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SaplingFate {
+    /// Grows into a tree, `dist` is the signed distance to the climate ranges of the tree
+    Grows { after: Duration, dist: f32 },
+    /// Turns into a dead bush
+    Withers { after: Duration },
+}
+
+impl SaplingFate {
+    pub fn after(&self) -> Duration {
+        match self {
+            SaplingFate::Grows { after, .. } | SaplingFate::Withers { after } => *after,
+        }
+    }
+}
+
+/// Decides how planted saplings fare, with the same climate ranges that place the trees during generation
+#[derive(Resource)]
+pub struct SaplingGrowth {
+    plant_ranges: PlantRanges<4, Tree>,
+}
+
+impl Default for SaplingGrowth {
+    fn default() -> Self {
+        SaplingGrowth::from_dir(GEN_DATA_DIR)
+    }
+}
+
+impl SaplingGrowth {
+    /// Loads the climate ranges of the trees from the generation data in `dir`
+    pub fn from_dir(dir: &str) -> Self {
+        SaplingGrowth {
+            plant_ranges: PlantRanges::from_csv(&format!("{dir}/plants.csv")),
+        }
+    }
+
+    /// `roll` is a random number in [0; 1[, returns None if the climate of the column is unknown
+    pub fn fate(
+        &self,
+        world: &VoxelWorld,
+        tree: Tree,
+        pos: BlockPos,
+        roll: f32,
+    ) -> Option<SaplingFate> {
+        let climate = world.climate_at(BlockPos2d::from(pos))?;
+        let point = [
+            climate.temperature,
+            climate.humidity,
+            climate.ph,
+            pos.y as f32 / MAX_GEN_HEIGHT as f32,
+        ];
+        let (best, _) = self.plant_ranges.closest(point);
+        let dist = self
+            .plant_ranges
+            .dist_of(&tree, &point)
+            .unwrap_or(f32::NEG_INFINITY);
+        // saplings always take in their climate, and less and less often the further they are from it
+        let chance = (1. + dist / TOLERANCE).clamp(0., 1.);
+        if roll >= chance {
+            return Some(SaplingFate::Withers {
+                after: Duration::from_secs_f32(SLOWEST_GROWTH),
+            });
+        }
+        let mut secs = SLOWEST_GROWTH + (FASTEST_GROWTH - SLOWEST_GROWTH) * dist.clamp(0., 1.);
+        if *best != tree {
+            secs *= OUTCOMPETED_SLOWDOWN;
+        }
+        Some(SaplingFate::Grows {
+            after: Duration::from_secs_f32(secs),
+            dist,
+        })
+    }
+
+    /// Replaces the sapling with its tree, which grows from the soil below.
+    /// `seed` is the seed of the world, trees get the same shapes as the ones generation places.
+    pub fn grow(world: &VoxelWorld, tree: Tree, pos: BlockPos, seed: u64, dist: f32) {
+        let soil = pos + (0, -1, 0);
+        if !tree.grows_on(world.get_block_safe(soil)) {
+            world.set_block(pos, Block::DeadBush);
+            return;
+        }
+        world.set_block(pos, Block::Air);
+        tree.grow(
+            world,
+            soil,
+            prng_seed(seed, SeedStream::TreeShapes),
+            dist.clamp(0., 1.),
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{ColumnSample, TerrainGenerator, WorldMeta};
+use anyhow::Context;
+use bevy::prelude::Resource;
+use rb_block::Block;
+use rb_world::{BlockPos, BlockPos2d, Realm, WATER_H};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+pub const SPAWN_POINTS_PATH: &str = "spawn_points.toml";
+/// Distance between the block columns tried when looking for the world spawn
+const SEARCH_STEP: i32 = 16;
+/// Past this distance from the origin the search gives up and players spawn at the origin
+const SEARCH_RADIUS: i32 = 4096;
+
+/// Where players appear, kept alongside the world
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpawnPoints {
+    /// The world the spawn points were found in, they are dropped when another world is created
+    #[serde(default)]
+    pub world: WorldMeta,
+    /// Found when the world is created, for the players that have no respawn point
+    pub world_spawn: BlockPos,
+    /// Respawn points of the players, by name
+    #[serde(default)]
+    pub respawns: HashMap<String, BlockPos>,
+}
+
+impl SpawnPoints {
+    /// Reopens the spawn points at `path`, or looks for the world spawn if there are none yet
+    /// or if they belong to another world
+    pub fn load_or_create(path: impl AsRef<Path>, world_meta: &WorldMeta) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            let spawn_points: SpawnPoints = confy::load_path(path)
+                .with_context(|| format!("Failed to read spawn points '{}'", path.display()))?;
+            if spawn_points.world == *world_meta {
+                return Ok(spawn_points);
+            }
+        }
+        let terrain_gen = TerrainGenerator::new(world_meta.seed, world_meta.preset)
+            .with_erosion(world_meta.erosion);
+        let spawn_points = SpawnPoints {
+            world: *world_meta,
+            world_spawn: find_spawn(&terrain_gen, Realm::Overworld),
+            respawns: HashMap::new(),
+        };
+        spawn_points.store(path)?;
+        Ok(spawn_points)
+    }
+
+    pub fn store(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        confy::store_path(path, self)
+            .with_context(|| format!("Failed to write spawn points '{}'", path.display()))
+    }
+
+    /// Where the player should (re)spawn
+    pub fn spawn_of(&self, player: &str) -> BlockPos {
+        self.respawns
+            .get(player)
+            .copied()
+            .unwrap_or(self.world_spawn)
+    }
+}
+
+/// Dry land, out of the oceans
+fn is_safe(sample: &ColumnSample) -> bool {
+    !sample.biome.is_ocean()
+        && sample.surface >= WATER_H
+        && !matches!(sample.block, Block::SeaBlock | Block::Ice)
+}
+
+/// Looks for a safe block column around the origin, from the closest rings of columns outwards,
+/// returns the position right above its surface
+pub fn find_spawn(terrain_gen: &TerrainGenerator, realm: Realm) -> BlockPos {
+    for ring in 0..=SEARCH_RADIUS / SEARCH_STEP {
+        for (x, z) in ring_positions(ring) {
+            let pos = BlockPos2d {
+                x: x * SEARCH_STEP,
+                z: z * SEARCH_STEP,
+                realm,
+            };
+            if !is_safe(&terrain_gen.rough_sample(pos)) {
+                continue;
+            }
+            let sample = terrain_gen.sample_column(pos);
+            if is_safe(&sample) {
+                return BlockPos {
+                    x: pos.x,
+                    y: sample.surface + 1,
+                    z: pos.z,
+                    realm,
+                };
+            }
+        }
+    }
+    let sample = terrain_gen.sample_column(BlockPos2d { x: 0, z: 0, realm });
+    BlockPos {
+        x: 0,
+        y: sample.surface + 1,
+        z: 0,
+        realm,
+    }
+}
+
+/// Positions at a Chebyshev distance of `ring` from the origin
+fn ring_positions(ring: i32) -> impl Iterator<Item = (i32, i32)> {
+    (-ring..=ring)
+        .flat_map(move |x| (-ring..=ring).map(move |z| (x, z)))
+        .filter(move |(x, z)| x.abs().max(z.abs()) == ring)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WorldPreset, terrain::TEST_GEN_DATA_DIR};
+
+    #[test]
+    fn rings() {
+        assert_eq!(ring_positions(0).collect::<Vec<_>>(), vec![(0, 0)]);
+        assert_eq!(ring_positions(2).count(), 16);
+    }
+
+    #[test]
+    fn spawns_on_dry_land() {
+        let terrain_gen = TerrainGenerator::from_dir(TEST_GEN_DATA_DIR, 42, WorldPreset::Default);
+        let spawn = find_spawn(&terrain_gen, Realm::Overworld);
+        let sample = terrain_gen.sample_column(spawn.into());
+        assert!(is_safe(&sample));
+        assert_eq!(spawn.y, sample.surface + 1);
+    }
+}
@@ -1,155 +1,670 @@
 use crate::{
-    biome_params::*, biomes::Biome, coverage::CoverageTrait, layer::LayerTag,
+    biome_params::*,
+    biomes::BiomeLayers,
+    climate,
+    coverage::CoverageTrait,
+    erosion::Erosion,
+    lakes::{LakeCell, Lakes},
+    layer::LayerTag,
+    patch::Patch,
+    pipeline::{GenJob, GenStage, ProtoColumn},
     plant_params::PlantRanges,
+    regions::RegionCache,
+    tree::Tree,
+    world_meta::{SeedStream, WorldPreset, noise_seed, prng_seed},
 };
 use rb_block::Block;
 use rb_noise::*;
-use rb_world::{BlockPos2d, CHUNK_S1, ChunkPos2d, ChunkedPos2d, MAX_GEN_HEIGHT, VoxelWorld};
+use rb_world::{
+    Biome, BlockPos2d, CHUNK_S1, CHUNK_S2, ChunkPos2d, ChunkedPos2d, ColClimate, HORIZON_RES,
+    HorizonTile, MAX_GEN_HEIGHT, VoxelWorld, WATER_H, horizon_cell_center,
+};
 use std::collections::HashMap;
+/// Where the generation data is loaded from by default, relative to the working directory
+pub(crate) const GEN_DATA_DIR: &str = "assets/gen";
+/// The generation data for the tests, which don't run from the workspace root
+#[cfg(test)]
+pub(crate) const TEST_GEN_DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/gen");
 const BIOME_SHARPENING: f32 = 100.;
+const MAX_CACHED_CANDIDATES: usize = 64;
+/// 1 block column out of GROUND_COVER_SPARSITY is considered for ground cover
+const GROUND_COVER_SPARSITY: usize = 6;
+/// Lake shores turn to mud and reeds in wet and warm enough climates
+const MARSH_HUMIDITY: f32 = 0.6;
+const MARSH_TEMPERATURE: f32 = 0.3;
+/// 1 marshy shore block column out of REED_SPARSITY grows reeds
+const REED_SPARSITY: usize = 3;
+/// Lakes colder than this are covered with ice
+const FREEZING_TEMPERATURE: f32 = 0.15;
+/// Colors of the horizon tiles covered with water or ice, the others take the color of their biome
+const WATER_COLOR: [f32; 3] = [0.15, 0.3, 0.6];
+const ICE_COLOR: [f32; 3] = [0.8, 0.9, 1.];
+/// How much the Amplified preset stretches the terrain above sea level
+const AMPLIFIED_FACTOR: f32 = 2.;
+/// Blocks of the Superflat preset, from the bottom up, with the height of their top
+const SUPERFLAT_LAYERS: [(Block, i32); 3] = [
+    (Block::Granite, WATER_H - 3),
+    (Block::Dirt, WATER_H + 2),
+    (Block::GrassBlock, WATER_H + 3),
+];
+
+/// A layer of blocks in a block column, going down to the top of the layer below
+struct Segment {
+    block: Block,
+    top: i32,
+    /// Fixed layers (such as water) are not moved by erosion
+    fixed: bool,
+}
+
+/// Height of the ground (ignoring water) of a block column, before erosion
+fn ground_height(segments: &[Segment]) -> i32 {
+    segments
+        .iter()
+        .filter(|segment| !segment.fixed)
+        .map(|segment| segment.top)
+        .max()
+        .unwrap_or(0)
+}
+
+/// What generation places in a block column before the trees and decorations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnSample {
+    pub biome: Biome,
+    /// Top of the ground once eroded, below any water
+    pub ground: i32,
+    /// Highest block of the column, water, ice and reeds included
+    pub block: Block,
+    /// Height of the highest block
+    pub surface: i32,
+}
+
+impl ColumnSample {
+    /// Keeps track of the highest block written by `set_yrange`-like calls
+    fn set_yrange(&mut self, top: i32, height: usize, block: Block) {
+        if height > 0 && top >= 0 && top >= self.surface {
+            self.surface = top;
+            self.block = block;
+        }
+    }
+}
 
 pub struct TerrainGenerator {
     pub biomes_points: BiomePoints<4>,
-    pub plant_ranges: PlantRanges<4>,
-    pub seed: u32,
+    pub plant_ranges: PlantRanges<4, Tree>,
+    pub ground_cover_ranges: PlantRanges<4, Block>,
+    pub soil_ranges: PlantRanges<2, Block>,
+    pub preset: WorldPreset,
+    /// Erosion is optional because it's costly
+    erosion: Option<Erosion>,
+    lakes: Lakes,
+    /// Biomes considered for blending in the recently sampled chunk columns,
+    /// so sampling single block columns doesn't compute the parameters of their whole column every time
+    candidates: RegionCache<Vec<Biome>, ChunkPos2d>,
+    /// Every noise and random source of the generator gets its own seed derived from it
+    pub seed: u64,
+    /// Added to the biome parameters by the SingleBiome preset
+    biome_bias: Option<[f32; 4]>,
 }
 
 impl TerrainGenerator {
-    pub fn new(seed: u32) -> Self {
-        let biomes_points = BiomePoints::from_csv("assets/gen/biomes.csv");
-        let plant_ranges = PlantRanges::from_csv("assets/gen/plants.csv");
-        TerrainGenerator {
+    pub fn new(seed: u64, preset: WorldPreset) -> Self {
+        Self::from_dir(GEN_DATA_DIR, seed, preset)
+    }
+
+    /// Like `new` but loads the generation data from `dir`
+    pub fn from_dir(dir: &str, seed: u64, preset: WorldPreset) -> Self {
+        let biomes_points = BiomePoints::from_csv(&format!("{dir}/biomes.csv"));
+        let plant_ranges = PlantRanges::from_csv(&format!("{dir}/plants.csv"));
+        let ground_cover_ranges = PlantRanges::from_csv(&format!("{dir}/ground_cover.csv"));
+        let soil_ranges = PlantRanges::from_csv(&format!("{dir}/soils_condition.csv"));
+        let mut terrain_gen = TerrainGenerator {
             seed,
+            preset,
             biomes_points,
             plant_ranges,
+            ground_cover_ranges,
+            soil_ranges,
+            erosion: None,
+            lakes: Lakes::default(),
+            candidates: RegionCache::new(MAX_CACHED_CANDIDATES),
+            biome_bias: None,
+        };
+        if let WorldPreset::SingleBiome(biome) = preset {
+            terrain_gen.biome_bias = Some(terrain_gen.bias_towards(biome));
         }
+        terrain_gen
+    }
+
+    fn noise_seed(&self, stream: SeedStream) -> u32 {
+        noise_seed(self.seed, stream)
+    }
+
+    fn prng_seed(&self, stream: SeedStream) -> i32 {
+        prng_seed(self.seed, stream)
+    }
+
+    /// Runs hydraulic and thermal erosion on the ground before the surface is written
+    pub fn with_erosion(mut self, enabled: bool) -> Self {
+        self.erosion = enabled.then(|| Erosion::new(self.prng_seed(SeedStream::Erosion)));
+        self
+    }
+
+    /// Offset that moves the average biome parameters around spawn to the ideal parameters of the biome
+    fn bias_towards(&self, biome: Biome) -> [f32; 4] {
+        let avg_params = self
+            .biome_params_at(ChunkPos2d::default())
+            .average(self.biomes_points.parameters);
+        let ideal_params = self
+            .biomes_points
+            .points
+            .iter()
+            .find(|(_, b)| *b == biome)
+            .unwrap_or_else(|| panic!("Biome {biome:?} is missing from biomes.csv"))
+            .0;
+        std::array::from_fn(|i| ideal_params[i] - avg_params[i])
     }
 
-    pub fn generate_with_params(
+    fn store_climate(
         &self,
         world: &VoxelWorld,
         col: ChunkPos2d,
         params: BiomeParameters,
+        biomes: Vec<Biome>,
     ) {
-        // The biomes that will be considered for blending in this chunk
-        let biomes: Vec<Biome> = self
-            .biomes_points
-            .closest_biomes(params.average(self.biomes_points.parameters), 1.);
-        let all_biome_layers = biomes
+        let BiomeParameters(mut params) = params;
+        let mut take = |param: BiomeParam| {
+            params
+                .remove(&param)
+                .unwrap_or_else(|| panic!("Missing biome parameter '{param:?}'"))
+        };
+        world.climates.insert(
+            col,
+            ColClimate {
+                continentalness: take(BiomeParam::Continentalness),
+                mountainness: take(BiomeParam::Mountainness),
+                temperature: take(BiomeParam::Temperature),
+                humidity: take(BiomeParam::Humidity),
+                ph: take(BiomeParam::Ph),
+                trees: take(BiomeParam::Trees),
+                biomes,
+            },
+        );
+    }
+
+    /// Runs a stage of the generation pipeline on a column
+    pub fn run_stage(&self, world: &VoxelWorld, job: &mut GenJob) {
+        let col = job.col;
+        let ProtoColumn {
+            params,
+            erosion,
+            biomes,
+        } = &mut job.proto;
+        let superflat = self.preset == WorldPreset::Superflat;
+        match job.stage {
+            GenStage::Empty => {}
+            GenStage::Noise => *params = Some(self.biome_params_at(col)),
+            GenStage::Erosion if superflat => {}
+            GenStage::Erosion => {
+                *erosion = self
+                    .erosion
+                    .as_ref()
+                    .map(|e| e.column_deltas(col, |c| self.ground_heights(c)));
+            }
+            GenStage::Surface if superflat => *biomes = self.superflat_surface(world, col),
+            GenStage::Surface => {
+                let params = params.as_mut().unwrap();
+                *biomes = self.surface(world, col, params, erosion.as_ref());
+                *erosion = None;
+                self.fill_lakes(world, col, params);
+            }
+            // nothing carves the terrain yet
+            GenStage::Carvers => {}
+            GenStage::Features | GenStage::Decorations if superflat => {}
+            GenStage::Features => self.grow_trees(world, col, params.as_ref().unwrap()),
+            GenStage::Decorations => self.decorate(world, col, params.as_ref().unwrap()),
+            GenStage::Lighting => {
+                let params = params.take().unwrap();
+                self.store_climate(world, col, params, std::mem::take(biomes));
+                world.loaded_columns.insert(col);
+                // the column is complete, sync it with its neighbors and send it to meshing
+                world.mark_change_col(col);
+            }
+        }
+    }
+
+    /// The biomes that will be considered for blending in a chunk column, picked from its average parameters.
+    /// They are sampled over the whole chunk column when blending only part of it, so that it considers the same biomes.
+    fn candidate_biomes(
+        &self,
+        col: ChunkPos2d,
+        patch: Patch,
+        params: &BiomeParameters,
+    ) -> Vec<Biome> {
+        let closest = |params: &BiomeParameters| {
+            let average = params.average(self.biomes_points.parameters);
+            self.biomes_points.closest_biomes(average, 1.)
+        };
+        if patch == Patch::column(col) {
+            closest(params)
+        } else {
+            self.candidates
+                .get_or_compute(col, || closest(&self.biome_params_at(col)))
+                .clone()
+        }
+    }
+
+    /// Blends the layers of the biomes around, returns the layers and the dominant biome of every block column.
+    /// The patch must fit in the chunk column `col`.
+    fn blend(
+        &self,
+        col: ChunkPos2d,
+        patch: Patch,
+        params: &BiomeParameters,
+    ) -> (Vec<Vec<Segment>>, Vec<Biome>) {
+        let biomes = self.candidate_biomes(col, patch, params);
+        let mut all_biome_layers = biomes
             .iter()
-            .map(|b| b.generate(self.seed, col, &params))
+            .map(|b| b.generate(self.noise_seed(SeedStream::BiomeLayers), patch, params))
             .collect::<Vec<_>>();
+        if self.preset == WorldPreset::Amplified {
+            all_biome_layers
+                .iter_mut()
+                .flatten()
+                .for_each(|layer| layer.amplify(AMPLIFIED_FACTOR));
+        }
         // Blend between biomes
         let mut column_biome_weights = vec![0.0; biomes.len()];
         let mut layer_indexes = vec![0usize; biomes.len()];
+        let mut dominant_biomes = vec![biomes[0]; patch.area()];
+        let mut segments: Vec<Vec<Segment>> = (0..patch.area()).map(|_| Vec::new()).collect();
         let param_points = params.view(self.biomes_points.parameters);
-        for dx in 0..CHUNK_S1 {
-            for dz in 0..CHUNK_S1 {
-                // Compute normalized biome weights for this block column
-                if biomes.len() > 1 {
-                    let biome_params = param_points[dx + dz * CHUNK_S1];
-                    let mut total = 0.;
-                    for (i, &biome) in biomes.iter().enumerate() {
-                        column_biome_weights[i] =
-                            (-self.biomes_points.dist_from(&biome_params, &biome)
-                                * BIOME_SHARPENING)
-                                .exp();
-                        total += column_biome_weights[i];
-                    }
-                    for i in 0..column_biome_weights.len() {
-                        column_biome_weights[i] = column_biome_weights[i] / total;
-                    }
-                } else {
-                    column_biome_weights[0] = 1.
+        for i in 0..patch.area() {
+            // Compute normalized biome weights for this block column
+            if biomes.len() > 1 {
+                let biome_params = param_points[i];
+                let mut total = 0.;
+                for (i, &biome) in biomes.iter().enumerate() {
+                    column_biome_weights[i] =
+                        (-self.biomes_points.dist_from(&biome_params, &biome) * BIOME_SHARPENING)
+                            .exp();
+                    total += column_biome_weights[i];
                 }
-                // Blend biome layers
-                layer_indexes.fill(0);
-                let mut last_height = 0;
-                while let Some(&min_layer_tag) = all_biome_layers
-                    .iter()
-                    .zip(&layer_indexes)
-                    .filter_map(|(layer, &i)| {
-                        if i >= layer.len() {
-                            None
-                        } else {
-                            Some(&layer[i].tag)
-                        }
-                    })
-                    .min()
-                {
-                    let mut n_min = 0.;
-                    let mut h_min = 0.;
-                    let mut h_other = 0.;
-                    let mut dominant_block = None;
-                    let mut max_weight = 0.;
-                    for ((layer_idx, layers), &weight) in layer_indexes
-                        .iter_mut()
-                        .zip(&all_biome_layers)
-                        .zip(&column_biome_weights)
-                    {
-                        if *layer_idx >= layers.len() || layers[*layer_idx].tag != min_layer_tag {
-                            // This layer is above the min tag, we interpolate with the top of the preceeding layer
-                            let target_height = if *layer_idx > 0 {
-                                layers[*layer_idx - 1].height(dx, dz)
-                            } else {
-                                0.
-                            };
-                            h_other += target_height * weight;
-                            continue;
-                        }
-                        h_min += layers[*layer_idx].height(dx, dz) * weight;
-                        n_min += weight;
-                        if weight > max_weight {
-                            max_weight = weight;
-                            dominant_block = Some(layers[*layer_idx].block);
-                        }
-                        *layer_idx += 1;
-                    }
-                    // We shouldn't need this but floats accumulate errors
-                    n_min = n_min.clamp(0., 1.);
-                    h_min /= n_min;
-                    let n_other = 1. - n_min;
-                    if n_other > 0. {
-                        h_other /= n_other;
-                    }
-                    if let LayerTag::Fixed { height } = min_layer_tag {
-                        h_other = height as f32;
-                    }
-                    let height = if h_min < h_other {
-                        h_min
+                for i in 0..column_biome_weights.len() {
+                    column_biome_weights[i] = column_biome_weights[i] / total;
+                }
+            } else {
+                column_biome_weights[0] = 1.
+            }
+            let (dominant_idx, _) = column_biome_weights
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap();
+            dominant_biomes[i] = biomes[dominant_idx];
+            // Blend biome layers
+            layer_indexes.fill(0);
+            while let Some(&min_layer_tag) = all_biome_layers
+                .iter()
+                .zip(&layer_indexes)
+                .filter_map(|(layer, &i)| {
+                    if i >= layer.len() {
+                        None
                     } else {
-                        h_min * n_min + h_other * n_other
+                        Some(&layer[i].tag)
                     }
-                    .round() as i32;
-                    if height < last_height {
-                        continue; // Don't overwrite lower layers
+                })
+                .min()
+            {
+                let mut n_min = 0.;
+                let mut h_min = 0.;
+                let mut h_other = 0.;
+                let mut dominant_block = None;
+                let mut max_weight = 0.;
+                for ((layer_idx, layers), &weight) in layer_indexes
+                    .iter_mut()
+                    .zip(&all_biome_layers)
+                    .zip(&column_biome_weights)
+                {
+                    if *layer_idx >= layers.len() || layers[*layer_idx].tag != min_layer_tag {
+                        // This layer is above the min tag, we interpolate with the top of the preceeding layer
+                        let target_height = if *layer_idx > 0 {
+                            layers[*layer_idx - 1].height(i)
+                        } else {
+                            0.
+                        };
+                        h_other += target_height * weight;
+                        continue;
                     }
-                    let block = dominant_block.unwrap();
-                    let layer_width = (height - last_height).max(1);
-                    if block == Block::GrassBlock {
-                        world.set_yrange(col, ChunkedPos2d { x: dx, z: dz }, height, 1, block);
-                        if layer_width > 1 {
-                            world.set_yrange(
-                                col,
-                                ChunkedPos2d { x: dx, z: dz },
-                                height - 1,
-                                (layer_width - 1) as usize,
-                                Block::Dirt,
-                            );
-                        }
-                    } else {
-                        world.set_yrange(
-                            col,
-                            ChunkedPos2d { x: dx, z: dz },
-                            height,
-                            layer_width as usize,
-                            block,
-                        );
+                    h_min += layers[*layer_idx].height(i) * weight;
+                    n_min += weight;
+                    if weight > max_weight {
+                        max_weight = weight;
+                        dominant_block = Some(layers[*layer_idx].block);
                     }
-                    last_height = height;
+                    *layer_idx += 1;
+                }
+                // We shouldn't need this but floats accumulate errors
+                n_min = n_min.clamp(0., 1.);
+                h_min /= n_min;
+                let n_other = 1. - n_min;
+                if n_other > 0. {
+                    h_other /= n_other;
+                }
+                if let LayerTag::Fixed { height } = min_layer_tag {
+                    h_other = height as f32;
+                }
+                let height = if h_min < h_other {
+                    h_min
+                } else {
+                    h_min * n_min + h_other * n_other
                 }
+                .round() as i32;
+                segments[i].push(Segment {
+                    block: dominant_block.unwrap(),
+                    top: height,
+                    fixed: matches!(min_layer_tag, LayerTag::Fixed { .. }),
+                });
+            }
+        }
+        (segments, dominant_biomes)
+    }
+
+    /// Writes the blended layers, shifted by erosion, returns the dominant biome of every block column.
+    /// The temperature of `params` is cooled down with the ground once it is known.
+    fn surface(
+        &self,
+        world: &VoxelWorld,
+        col: ChunkPos2d,
+        params: &mut BiomeParameters,
+        erosion: Option<&Vec<f32>>,
+    ) -> Vec<Biome> {
+        let (segments, dominant_biomes) = self.blend(col, Patch::column(col), params);
+        let shifts = (0..CHUNK_S2)
+            .map(|i| erosion.map_or(0, |deltas| deltas[i].round() as i32))
+            .collect::<Vec<_>>();
+        let ground = segments
+            .iter()
+            .zip(&shifts)
+            .map(|(segments, shift)| ground_height(segments) + shift)
+            .collect::<Vec<_>>();
+        self.lapse_to_ground(params, Patch::column(col), &ground);
+        for dx in 0..CHUNK_S1 {
+            for dz in 0..CHUNK_S1 {
+                let i = dx + dz * CHUNK_S1;
+                let shift = shifts[i];
+                let climate = [
+                    params[BiomeParam::Temperature][i],
+                    params[BiomeParam::Humidity][i],
+                ];
+                self.write_segments(&segments[i], shift, climate, |top, height, block| {
+                    world.set_yrange(col, ChunkedPos2d { x: dx, z: dz }, top, height, block)
+                });
             }
         }
+        dominant_biomes
+    }
+
+    /// Writes the blended layers of a block column with `set_yrange(top, height, block)`.
+    /// `climate` is the temperature and humidity of the block column.
+    fn write_segments(
+        &self,
+        segments: &[Segment],
+        shift: i32,
+        climate: [f32; 2],
+        mut set_yrange: impl FnMut(i32, usize, Block),
+    ) {
+        let mut last_height = 0;
+        for segment in segments {
+            // erosion moves the ground up or down, leaving fixed layers such as water in place
+            let height = if segment.fixed {
+                segment.top
+            } else {
+                (segment.top + shift).max(0)
+            };
+            if height < last_height {
+                continue; // Don't overwrite lower layers
+            }
+            let block = segment.block;
+            let layer_width = (height - last_height).max(1);
+            if block == Block::GrassBlock {
+                // The surface soil depends on the climate of the block column
+                let (soil, dist) = self.soil_ranges.closest(climate);
+                let soil = if dist >= 0. { *soil } else { block };
+                set_yrange(height, 1, soil);
+                if layer_width > 1 {
+                    set_yrange(height - 1, (layer_width - 1) as usize, Block::Dirt);
+                }
+            } else {
+                set_yrange(height, layer_width as usize, block);
+            }
+            last_height = height;
+        }
+    }
+
+    /// Height of the ground (ignoring water) of every block column, before erosion
+    fn ground_heights(&self, col: ChunkPos2d) -> Vec<f32> {
+        let (segments, _) = self.blend(col, Patch::column(col), &self.biome_params_at(col));
+        segments
+            .iter()
+            .map(|column| ground_height(column) as f32)
+            .collect()
+    }
+
+    /// Height of the ground of every block column once eroded
+    fn eroded_ground_heights(&self, col: ChunkPos2d) -> Vec<i32> {
+        let deltas = self
+            .erosion
+            .as_ref()
+            .map(|e| e.column_deltas(col, |c| self.ground_heights(c)));
+        self.ground_heights(col)
+            .into_iter()
+            .enumerate()
+            .map(|(i, h)| h as i32 + deltas.as_ref().map_or(0, |d| d[i].round() as i32))
+            .collect()
+    }
+
+    /// Coarse surface of a column, straight from the blended layers without writing any block.
+    /// Erosion and lakes are left out, they are too small to be seen from that far.
+    pub fn horizon_tile(&self, col: ChunkPos2d) -> HorizonTile {
+        let cells = HORIZON_RES * HORIZON_RES;
+        if self.preset == WorldPreset::Superflat {
+            let (_, top) = SUPERFLAT_LAYERS[SUPERFLAT_LAYERS.len() - 1];
+            return HorizonTile {
+                col,
+                heights: vec![top; cells],
+                colors: vec![Biome::Plain.color(); cells],
+            };
+        }
+        let (segments, biomes) = self.blend(col, Patch::column(col), &self.biome_params_at(col));
+        let mut heights = Vec::with_capacity(cells);
+        let mut colors = Vec::with_capacity(cells);
+        for z in 0..HORIZON_RES {
+            for x in 0..HORIZON_RES {
+                let i = horizon_cell_center(x) + horizon_cell_center(z) * CHUNK_S1;
+                let top = segments[i].iter().max_by_key(|segment| segment.top);
+                heights.push(top.map_or(0, |segment| segment.top));
+                colors.push(match top.map(|segment| segment.block) {
+                    Some(Block::SeaBlock) => WATER_COLOR,
+                    Some(Block::Ice) => ICE_COLOR,
+                    _ => biomes[i].color(),
+                });
+            }
+        }
+        HorizonTile {
+            col,
+            heights,
+            colors,
+        }
+    }
+
+    /// Fills the depressions of the ground with water, marshy shores get mud and reeds
+    fn fill_lakes(&self, world: &VoxelWorld, col: ChunkPos2d, params: &BiomeParameters) {
+        let cells = self
+            .lakes
+            .column_cells(col, |c| self.eroded_ground_heights(c));
+        if cells.iter().all(|cell| *cell == LakeCell::Dry) {
+            return;
+        }
+        let ground = self.eroded_ground_heights(col);
+        for dx in 0..CHUNK_S1 {
+            for dz in 0..CHUNK_S1 {
+                let i = dx + dz * CHUNK_S1;
+                let pos = ChunkedPos2d { x: dx, z: dz };
+                let climate = [
+                    params[BiomeParam::Temperature][i],
+                    params[BiomeParam::Humidity][i],
+                ];
+                self.write_lake(
+                    cells[i],
+                    ground[i],
+                    climate,
+                    (col, pos).into(),
+                    |top, height, block| world.set_yrange(col, pos, top, height, block),
+                );
+            }
+        }
+    }
+
+    /// Writes the lake cell of a block column with `set_yrange(top, height, block)`.
+    /// `climate` is the temperature and humidity of the block column.
+    fn write_lake(
+        &self,
+        cell: LakeCell,
+        ground: i32,
+        [temperature, humidity]: [f32; 2],
+        pos: BlockPos2d,
+        mut set_yrange: impl FnMut(i32, usize, Block),
+    ) {
+        let marsh = humidity >= MARSH_HUMIDITY && temperature >= MARSH_TEMPERATURE;
+        match cell {
+            LakeCell::Dry => {}
+            LakeCell::Water(level) => {
+                set_yrange(level, (level - ground) as usize, Block::SeaBlock);
+                if temperature < FREEZING_TEMPERATURE {
+                    set_yrange(level, 1, Block::Ice);
+                }
+                if marsh {
+                    set_yrange(ground, 1, Block::Mud);
+                }
+            }
+            LakeCell::Shore if marsh && ground >= WATER_H => {
+                set_yrange(ground, 1, Block::Mud);
+                let rng = pos.prng(self.prng_seed(SeedStream::Reeds));
+                if rng % REED_SPARSITY == 0 {
+                    set_yrange(ground + 1, 1, Block::Reeds);
+                }
+            }
+            LakeCell::Shore => {}
+        }
+    }
+
+    /// What generation places at a block column, see `sample_patch`
+    pub fn sample_column(&self, pos: BlockPos2d) -> ColumnSample {
+        self.sample_patch(Patch::point(pos))[0]
+    }
+
+    /// Height of the highest block generation places at a block column, before the trees and decorations
+    pub fn surface_height(&self, pos: BlockPos2d) -> i32 {
+        self.sample_column(pos).surface
+    }
+
+    /// What generation places in every block column of the patch before the trees and decorations,
+    /// without generating the chunk columns it overlaps. The noise of the patch is sampled and blended,
+    /// but picking the biomes to blend needs the parameters of the whole chunk columns it overlaps,
+    /// and erosion and lakes need the ground of the regions around, all of which are cached once computed.
+    pub fn sample_patch(&self, patch: Patch) -> Vec<ColumnSample> {
+        self.sample_patch_with(patch, true)
+    }
+
+    /// Like `sample_column` but leaves out erosion and lakes, which need the ground of the regions around.
+    /// Much cheaper, good enough to rule out block columns before sampling them exactly.
+    pub fn rough_sample(&self, pos: BlockPos2d) -> ColumnSample {
+        self.sample_patch_with(Patch::point(pos), false)[0]
+    }
+
+    fn sample_patch_with(&self, patch: Patch, exact: bool) -> Vec<ColumnSample> {
+        let mut samples = vec![None; patch.area()];
+        for (col, part) in patch.split_by_column() {
+            for (i, sample) in self
+                .sample_in_column(col, part, exact)
+                .into_iter()
+                .enumerate()
+            {
+                samples[patch.index(part.block_pos(i))] = Some(sample);
+            }
+        }
+        samples.into_iter().map(Option::unwrap).collect()
+    }
+
+    /// Samples a patch that fits in the chunk column `col`, replaying what the surface stage writes
+    fn sample_in_column(&self, col: ChunkPos2d, patch: Patch, exact: bool) -> Vec<ColumnSample> {
+        if self.preset == WorldPreset::Superflat {
+            let (block, top) = SUPERFLAT_LAYERS[SUPERFLAT_LAYERS.len() - 1];
+            return vec![
+                ColumnSample {
+                    biome: Biome::Plain,
+                    ground: top,
+                    block,
+                    surface: top,
+                };
+                patch.area()
+            ];
+        }
+        let mut params = self.biome_params_in(patch);
+        let (segments, biomes) = self.blend(col, patch, &params);
+        let deltas = self
+            .erosion
+            .as_ref()
+            .filter(|_| exact)
+            .map(|e| e.column_deltas(col, |c| self.ground_heights(c)));
+        let shift = |i: usize| {
+            let (_, chunked) = <(ChunkPos2d, ChunkedPos2d)>::from(patch.block_pos(i));
+            deltas
+                .as_ref()
+                .map_or(0, |d| d[chunked.x + chunked.z * CHUNK_S1].round() as i32)
+        };
+        let ground = (0..patch.area())
+            .map(|i| ground_height(&segments[i]) + shift(i))
+            .collect::<Vec<_>>();
+        self.lapse_to_ground(&mut params, patch, &ground);
+        let cells = if exact {
+            self.lakes
+                .column_cells(col, |c| self.eroded_ground_heights(c))
+        } else {
+            vec![LakeCell::Dry; CHUNK_S2]
+        };
+        (0..patch.area())
+            .map(|i| {
+                let pos = patch.block_pos(i);
+                let (_, chunked) = <(ChunkPos2d, ChunkedPos2d)>::from(pos);
+                let ci = chunked.x + chunked.z * CHUNK_S1;
+                let climate = [
+                    params[BiomeParam::Temperature][i],
+                    params[BiomeParam::Humidity][i],
+                ];
+                let mut sample = ColumnSample {
+                    biome: biomes[i],
+                    ground: ground[i],
+                    block: Block::Air,
+                    surface: -1,
+                };
+                self.write_segments(&segments[i], shift(i), climate, |top, height, block| {
+                    sample.set_yrange(top, height, block)
+                });
+                self.write_lake(
+                    cells[ci],
+                    sample.ground,
+                    climate,
+                    pos,
+                    |top, height, block| sample.set_yrange(top, height, block),
+                );
+                sample
+            })
+            .collect()
+    }
+
+    /// Trees can grow over the neighboring columns
+    fn grow_trees(&self, world: &VoxelWorld, col: ChunkPos2d, params: &BiomeParameters) {
         let tree_spots = [
             (0, 0),
             (15, 0),
@@ -174,7 +689,7 @@ impl TerrainGenerator {
                     z: spot.1,
                 },
             ))
-            .prng(self.seed as i32);
+            .prng(self.prng_seed(SeedStream::TreeSpots));
             let dx = spot.0 + (rng & 0b111);
             let dz = spot.1 + ((rng >> 3) & 0b111);
             let i = dx * CHUNK_S1 + dz;
@@ -184,46 +699,347 @@ impl TerrainGenerator {
             }
             let h = (rng >> 6) & 0b11;
             let (block, y) = world.top_block((col, ChunkedPos2d { x: dx, z: dz }).into());
-            if !block.is_fertile_soil() {
-                continue;
-            }
             let (tree, dist) = self.plant_ranges.closest([
                 params[BiomeParam::Temperature][i],
                 params[BiomeParam::Humidity][i],
                 params[BiomeParam::Ph][i],
                 y as f32 / MAX_GEN_HEIGHT as f32,
             ]);
-            if dist >= 0. {
+            if dist >= 0. && tree.grows_on(block) {
                 let pos = (col, (dx, y, dz)).into();
-                tree.grow(world, pos, self.seed as i32, dist + h as f32 / 10.);
+                tree.grow(
+                    world,
+                    pos,
+                    self.prng_seed(SeedStream::TreeShapes),
+                    dist + h as f32 / 10.,
+                );
+            }
+        }
+    }
+
+    fn superflat_surface(&self, world: &VoxelWorld, col: ChunkPos2d) -> Vec<Biome> {
+        for dx in 0..CHUNK_S1 {
+            for dz in 0..CHUNK_S1 {
+                let mut bottom = 0;
+                for (block, top) in SUPERFLAT_LAYERS {
+                    let pos = ChunkedPos2d { x: dx, z: dz };
+                    world.set_yrange(col, pos, top, (top - bottom) as usize, block);
+                    bottom = top;
+                }
+            }
+        }
+        vec![Biome::Plain; CHUNK_S2]
+    }
+
+    /// Scatters small plants on the soil, after the trees have been placed
+    fn decorate(&self, world: &VoxelWorld, col: ChunkPos2d, params: &BiomeParameters) {
+        for dx in 0..CHUNK_S1 {
+            for dz in 0..CHUNK_S1 {
+                let pos2d = <BlockPos2d>::from((col, ChunkedPos2d { x: dx, z: dz }));
+                let rng = pos2d.prng(self.prng_seed(SeedStream::GroundCover));
+                if rng % GROUND_COVER_SPARSITY != 0 {
+                    continue;
+                }
+                let i = dx + dz * CHUNK_S1;
+                let (soil, y) = world.top_block(pos2d);
+                let (plant, dist) = self.ground_cover_ranges.closest([
+                    params[BiomeParam::Temperature][i],
+                    params[BiomeParam::Humidity][i],
+                    params[BiomeParam::Ph][i],
+                    y as f32 / MAX_GEN_HEIGHT as f32,
+                ]);
+                // plants thin out towards the edges of their range
+                let density = ((rng >> 8) & 0xff) as f32 / 255.;
+                if dist < density || !plant_grows_on(*plant, soil) {
+                    continue;
+                }
+                world.set_if_empty((col, (dx, y + 1, dz)).into(), *plant);
             }
         }
     }
 
+    fn mountainness(&self, x: f32, z: f32, patch: Patch) -> Vec<f32> {
+        let mut mountainness = fbm(
+            x,
+            patch.width,
+            z,
+            patch.depth,
+            self.noise_seed(SeedStream::Mountainness),
+            0.001,
+        );
+        let mountain_points = if self.preset == WorldPreset::Amplified {
+            [(0., 0.), (0.65, 0.), (0.8, 0.9), (1., 1.)]
+        } else {
+            [(0., 0.), (0.8, 0.), (0.9, 0.9), (1., 1.)]
+        };
+        points_lerp(&mut mountainness, &mountain_points);
+        mountainness
+    }
+
     pub fn biome_params_at(&self, col: ChunkPos2d) -> BiomeParameters {
-        let (x, z) = col.to_real_pos();
-        let continentalness = fbm(x, CHUNK_S1, z, CHUNK_S1, self.seed, 0.0005);
-        let mut mountainness = fbm(x, CHUNK_S1, z, CHUNK_S1, self.seed + 1, 0.001);
-        points_lerp(
-            &mut mountainness,
-            &[(0., 0.), (0.8, 0.), (0.9, 0.9), (1., 1.)],
+        self.biome_params_in(Patch::column(col))
+    }
+
+    pub fn biome_params_in(&self, patch: Patch) -> BiomeParameters {
+        let (x, z) = patch.real_pos();
+        let (width, depth) = (patch.width, patch.depth);
+        let continentalness = fbm(
+            x,
+            width,
+            z,
+            depth,
+            self.noise_seed(SeedStream::Continentalness),
+            0.0005,
+        );
+        let mountainness = self.mountainness(x, z, patch);
+        let temperature = self.temperature_in(
+            patch,
+            &climate::estimated_heights(&continentalness, &mountainness),
+        );
+        let mut humidity = fbm(
+            x,
+            width,
+            z,
+            depth,
+            self.noise_seed(SeedStream::Humidity),
+            0.002,
         );
-        let temperature = fbm(x, CHUNK_S1, z, CHUNK_S1, self.seed + 3, 0.0005);
-        let humidity = fbm(x, CHUNK_S1, z, CHUNK_S1, self.seed + 4, 0.002);
-        let ph = fbm(x, CHUNK_S1, z, CHUNK_S1, self.seed + 5, 0.005);
-        let trees = fbm(x, CHUNK_S1, z, CHUNK_S1, self.seed + 6, 0.01);
-        BiomeParameters(HashMap::from([
+        let upwind_mountainness =
+            climate::UPWIND_DISTANCES.map(|d| self.mountainness(x - d, z, patch));
+        climate::apply_humidity(
+            &mut humidity,
+            &continentalness,
+            &mountainness,
+            &upwind_mountainness,
+        );
+        let ph = fbm(x, width, z, depth, self.noise_seed(SeedStream::Ph), 0.005);
+        let trees = fbm(x, width, z, depth, self.noise_seed(SeedStream::Trees), 0.01);
+        let mut params = BiomeParameters(HashMap::from([
             (BiomeParam::Continentalness, continentalness),
             (BiomeParam::Mountainness, mountainness),
             (BiomeParam::Temperature, temperature),
             (BiomeParam::Humidity, humidity),
             (BiomeParam::Ph, ph),
             (BiomeParam::Trees, trees),
-        ]))
+        ]));
+        for (param, values) in params.0.iter_mut() {
+            self.apply_bias(*param, values);
+        }
+        params
+    }
+
+    /// Temperature of every block column of the patch, cooled down with the height of its ground
+    fn temperature_in(&self, patch: Patch, heights: &[f32]) -> Vec<f32> {
+        let (x, z) = patch.real_pos();
+        let mut temperature = fbm(
+            x,
+            patch.width,
+            z,
+            patch.depth,
+            self.noise_seed(SeedStream::Temperature),
+            0.0005,
+        );
+        climate::apply_temperature(&mut temperature, z, patch.width, heights);
+        temperature
+    }
+
+    /// Replaces the temperature that picked the biomes, cooled down with an estimate of the ground,
+    /// with the one of the ground they blended into. `ground` is the eroded ground of every block column.
+    fn lapse_to_ground(&self, params: &mut BiomeParameters, patch: Patch, ground: &[i32]) {
+        let heights = ground.iter().map(|&h| h as f32).collect::<Vec<_>>();
+        let mut temperature = self.temperature_in(patch, &heights);
+        self.apply_bias(BiomeParam::Temperature, &mut temperature);
+        params.0.insert(BiomeParam::Temperature, temperature);
+    }
+
+    /// Adds the bias of the SingleBiome preset to a biome parameter
+    fn apply_bias(&self, param: BiomeParam, values: &mut [f32]) {
+        let Some(bias) = self.biome_bias else {
+            return;
+        };
+        if let Some(i) = self
+            .biomes_points
+            .parameters
+            .iter()
+            .position(|p| *p == param)
+        {
+            values.iter_mut().for_each(|v| *v += bias[i]);
+        }
     }
+}
 
-    pub fn generate(&self, world: &VoxelWorld, col: ChunkPos2d) {
-        let params = self.biome_params_at(col);
-        self.generate_with_params(world, col, params);
+fn plant_grows_on(plant: Block, soil: Block) -> bool {
+    match plant {
+        Block::DeadBush => soil == Block::Sand,
+        _ => soil.is_fertile_soil(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{GEN_MARGIN, Pipeline};
+    use rb_world::{ChunkPos, Realm, Y_CHUNKS, chunk_area};
+
+    fn generator() -> TerrainGenerator {
+        TerrainGenerator::from_dir(TEST_GEN_DATA_DIR, 42, WorldPreset::Default)
+    }
+
+    #[test]
+    fn samples_match_generation() {
+        let terrain_gen = generator();
+        let (sender, _receiver) = crossbeam::channel::unbounded();
+        let world = VoxelWorld::new(sender);
+        // in the middle of a region, so lakes only need that region
+        let col = ChunkPos2d {
+            x: 1,
+            z: 2,
+            realm: Realm::Overworld,
+        };
+        let mut job = GenJob {
+            col,
+            stage: GenStage::Noise,
+            proto: ProtoColumn::default(),
+        };
+        for stage in [GenStage::Noise, GenStage::Erosion, GenStage::Surface] {
+            job.stage = stage;
+            terrain_gen.run_stage(&world, &mut job);
+        }
+        let samples = terrain_gen.sample_patch(Patch::column(col));
+        for (i, sample) in samples.iter().enumerate() {
+            let pos = Patch::column(col).block_pos(i);
+            assert_eq!(world.top_block(pos), (sample.block, sample.surface));
+            assert_eq!(job.proto.biomes[i], sample.biome);
+        }
+    }
+
+    #[test]
+    fn stages_publish_column() {
+        let terrain_gen = generator();
+        let (sender, receiver) = crossbeam::channel::unbounded();
+        let world = VoxelWorld::new(sender);
+        let col = ChunkPos2d {
+            x: 1,
+            z: 2,
+            realm: Realm::Overworld,
+        };
+        let mut job = GenJob {
+            col,
+            stage: GenStage::Empty,
+            proto: ProtoColumn::default(),
+        };
+        while let Some(stage) = job.stage.next() {
+            // the column is only published once it's complete
+            assert!(!world.loaded_columns.contains(&col));
+            job.stage = stage;
+            terrain_gen.run_stage(&world, &mut job);
+        }
+        assert_eq!(job.stage, GenStage::Lighting);
+        assert!(world.loaded_columns.contains(&col));
+        assert!(world.climate_at(Patch::column(col).block_pos(0)).is_some());
+        // and sent to meshing
+        assert!(receiver.try_iter().count() > 0);
+    }
+
+    #[test]
+    fn regenerated_neighbors_keep_out_of_published_columns() {
+        let terrain_gen = generator();
+        let (sender, _receiver) = crossbeam::channel::unbounded();
+        let world = VoxelWorld::new(sender);
+        let pipeline = Pipeline::default();
+        let center = ChunkPos2d {
+            x: 1,
+            z: 2,
+            realm: Realm::Overworld,
+        };
+        let wanted = chunk_area(&center, GEN_MARGIN)
+            .filter_map(|col| {
+                let dist = col.dist(center);
+                GenStage::target_at(dist).map(|stage| (col, (stage, dist)))
+            })
+            .collect::<HashMap<_, _>>();
+        let generate = |wanted: HashMap<ChunkPos2d, (GenStage, i32)>| {
+            for col in pipeline.update_area(wanted) {
+                world.unload_col(col);
+            }
+            while let Some(mut job) = pipeline.try_next_job() {
+                terrain_gen.run_stage(&world, &mut job);
+                for col in pipeline.finish(job) {
+                    world.unload_col(col);
+                }
+            }
+        };
+        generate(wanted.clone());
+        assert_eq!(pipeline.stage(center), Some(GenStage::Lighting));
+        // the player digs the published column out entirely
+        world.unload_col(center);
+        world.loaded_columns.insert(center);
+        // its neighbors are unloaded and generated again
+        let mut shrunk = wanted.clone();
+        shrunk.retain(|col, _| col.dist(center) != 1);
+        generate(shrunk);
+        for col in chunk_area(&center, 1).filter(|col| *col != center) {
+            assert_eq!(pipeline.stage(col), None);
+        }
+        generate(wanted);
+        assert_eq!(pipeline.stage(center), Some(GenStage::Lighting));
+        for y in 0..Y_CHUNKS as i32 {
+            let chunk_pos = ChunkPos {
+                x: center.x,
+                y,
+                z: center.z,
+                realm: center.realm,
+            };
+            assert!(!world.chunks.contains_key(&chunk_pos));
+        }
+    }
+
+    #[test]
+    fn horizon_tile_matches_samples() {
+        // the tiles leave out erosion, which moves the ground by a few blocks,
+        // and lakes, which only fill the ground up to their level and put a layer of ice or reeds on top
+        const TOLERANCE: i32 = 8;
+        let terrain_gen = generator().with_erosion(true);
+        let col = ChunkPos2d {
+            x: 1,
+            z: 2,
+            realm: Realm::Overworld,
+        };
+        let tile = terrain_gen.horizon_tile(col);
+        for z in 0..HORIZON_RES {
+            for x in 0..HORIZON_RES {
+                let pos = ChunkedPos2d {
+                    x: horizon_cell_center(x),
+                    z: horizon_cell_center(z),
+                };
+                let sample = terrain_gen.sample_column((col, pos).into());
+                let expected = sample.surface.min(sample.ground.max(WATER_H));
+                let height = tile.heights[x + z * HORIZON_RES];
+                assert!(
+                    (height - expected).abs() <= TOLERANCE,
+                    "tile cell ({x}, {z}) is at {height}, the generated surface at {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn patches_across_columns() {
+        let terrain_gen = generator();
+        let patch = Patch {
+            origin: BlockPos2d {
+                x: 2 * CHUNK_S1 as i32 - 3,
+                z: 2 * CHUNK_S1 as i32 - 2,
+                realm: Realm::Overworld,
+            },
+            width: 5,
+            depth: 4,
+        };
+        assert_eq!(patch.split_by_column().len(), 4);
+        let samples = terrain_gen.sample_patch(patch);
+        for (i, sample) in samples.iter().enumerate() {
+            let pos = patch.block_pos(i);
+            assert_eq!(terrain_gen.sample_column(pos), *sample);
+        }
     }
 }
@@ -1,5 +1,6 @@
 use crate::growables::*;
-use rb_world::{BlockPos, VoxelWorld};
+use rb_block::Block;
+use rb_world::{BlockPos, ChunkPos2d, VoxelWorld};
 use serde::Deserialize;
 use strum_macros::EnumString;
 
@@ -20,19 +21,83 @@ pub enum Tree {
 }
 
 impl Tree {
+    pub fn from_sapling(block: Block) -> Option<Tree> {
+        match block {
+            Block::AcaciaSapling => Some(Tree::Acacia),
+            Block::BambooSapling => Some(Tree::Bamboo),
+            Block::BirchSapling => Some(Tree::Birch),
+            Block::ChestnutSapling => Some(Tree::Chestnut),
+            Block::IronwoodSapling => Some(Tree::Ironwood),
+            Block::OakSapling => Some(Tree::Oak),
+            Block::PalmSapling => Some(Tree::Palm),
+            Block::SequoiaSapling => Some(Tree::Sequoia),
+            Block::SpruceSapling => Some(Tree::Spruce),
+            _ => None,
+        }
+    }
+
+    pub fn grows_on(&self, block: Block) -> bool {
+        match self {
+            Tree::Cactus => block == Block::Sand,
+            _ => block.is_fertile_soil(),
+        }
+    }
+
     pub fn grow(&self, world: &VoxelWorld, pos: BlockPos, seed: i32, dist: f32) {
-        if !world.get_block_safe(pos).is_fertile_soil() {
+        if !self.grows_on(world.get_block_safe(pos)) {
             return;
         }
+        let world = &TreeWorld::new(world, pos);
         match self {
             Tree::Spruce => grow_spruce(world, pos, seed, dist),
             Tree::Birch => grow_birch(world, pos, seed, dist),
             Tree::Cypress => grow_cypress(world, pos, seed, dist),
-            Tree::Oak | Tree::Chestnut | Tree::Ironwood => grow_oak(world, pos, seed, dist),
+            Tree::Oak => grow_oak(world, pos, seed, dist),
+            Tree::Chestnut => grow_chestnut(world, pos, seed, dist),
+            Tree::Ironwood => grow_ironwood(world, pos, seed, dist),
             Tree::Acacia => grow_acacia(world, pos, seed, dist),
             Tree::Sequoia => grow_sequoia(world, pos, seed, dist),
-            Tree::Palm | Tree::Baobab => grow_baobab(world, pos, seed, dist),
-            _ => {}
+            Tree::Baobab => grow_baobab(world, pos, seed, dist),
+            Tree::Palm => grow_palm(world, pos, seed, dist),
+            Tree::Cactus => grow_cactus(world, pos, seed, dist),
+            Tree::Bamboo => grow_bamboo(world, pos, seed, dist),
+        }
+    }
+}
+
+/// The world as seen by a growing tree.
+/// A tree growing in a column that isn't published yet, during generation, leaves the published columns around as they are:
+/// they were already given their share of the tree the first time, and the player may have chopped it since.
+pub struct TreeWorld<'a> {
+    world: &'a VoxelWorld,
+    keeps_published: bool,
+}
+
+impl<'a> TreeWorld<'a> {
+    pub fn new(world: &'a VoxelWorld, pos: BlockPos) -> Self {
+        TreeWorld {
+            world,
+            keeps_published: !world.loaded_columns.contains(&ChunkPos2d::from(pos)),
+        }
+    }
+
+    fn can_write(&self, pos: BlockPos) -> bool {
+        !self.keeps_published || !self.world.loaded_columns.contains(&ChunkPos2d::from(pos))
+    }
+
+    pub fn get_block_safe(&self, pos: BlockPos) -> Block {
+        self.world.get_block_safe(pos)
+    }
+
+    pub fn set_block(&self, pos: BlockPos, block: Block) {
+        if self.can_write(pos) {
+            self.world.set_block(pos, block);
+        }
+    }
+
+    pub fn set_if_empty(&self, pos: BlockPos, block: Block) {
+        if self.can_write(pos) {
+            self.world.set_if_empty(pos, block);
         }
     }
 }
@@ -0,0 +1,224 @@
+use anyhow::{Context, anyhow, bail};
+use bevy::prelude::Resource;
+use rb_world::Biome;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    path::Path,
+    str::FromStr,
+};
+
+pub const WORLD_META_PATH: &str = "world.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WorldPreset {
+    #[default]
+    Default,
+    /// Flat grass plains with no vegetation
+    Superflat,
+    /// Biome parameters are biased so the given biome is everywhere
+    SingleBiome(Biome),
+    /// Terrain above sea level is stretched and mountains are more frequent
+    Amplified,
+}
+
+impl FromStr for WorldPreset {
+    type Err = anyhow::Error;
+
+    /// Parses `default`, `superflat`, `amplified` or `single_biome:<Biome>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(biome) = s.strip_prefix("single_biome:") {
+            let biome = Biome::from_str(biome.trim())
+                .map_err(|_| anyhow!("Unknown biome '{}'", biome.trim()))?;
+            return Ok(WorldPreset::SingleBiome(biome));
+        }
+        match s {
+            "default" => Ok(WorldPreset::Default),
+            "superflat" => Ok(WorldPreset::Superflat),
+            "amplified" => Ok(WorldPreset::Amplified),
+            _ => bail!("Unknown world preset '{s}'"),
+        }
+    }
+}
+
+/// Numbers are used as is, any other text is hashed into a seed
+pub fn parse_seed(seed: &str) -> u64 {
+    let seed = seed.trim();
+    if let Ok(n) = seed.parse::<u64>() {
+        return n;
+    }
+    if let Ok(n) = seed.parse::<i64>() {
+        return n as u64;
+    }
+    // FNV-1a, unlike std hashers it is stable across Rust versions
+    seed.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Uses of the world seed, each one gets its own seed so they don't correlate
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum SeedStream {
+    Continentalness,
+    Mountainness,
+    Temperature,
+    Humidity,
+    Ph,
+    Trees,
+    BiomeLayers,
+    TreeSpots,
+    TreeShapes,
+    GroundCover,
+    Reeds,
+    Erosion,
+}
+
+/// Mixes the whole world seed with the stream using SplitMix64
+fn derive_seed(seed: u64, stream: SeedStream) -> u64 {
+    let mut z = seed.wrapping_add((stream as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Seed of a noise function
+pub(crate) fn noise_seed(seed: u64, stream: SeedStream) -> u32 {
+    derive_seed(seed, stream) as u32
+}
+
+/// Seed of the position based random numbers
+pub(crate) fn prng_seed(seed: u64, stream: SeedStream) -> i32 {
+    derive_seed(seed, stream) as i32
+}
+
+/// What a world was created with, kept alongside the world so it's generated the same way when reopened.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldMeta {
+    #[serde(with = "seed_string")]
+    pub seed: u64,
+    pub preset: WorldPreset,
+    #[serde(default)]
+    pub erosion: bool,
+}
+
+impl WorldMeta {
+    /// Reads `--seed <number or text>`, `--preset <preset>` and `--erosion`,
+    /// a random seed is picked if none is given
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self> {
+        let mut seed = None;
+        let mut preset = WorldPreset::Default;
+        let mut erosion = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().context("Missing value after --seed")?;
+                    seed = Some(parse_seed(&value));
+                }
+                "--preset" => {
+                    let value = args.next().context("Missing value after --preset")?;
+                    preset = WorldPreset::from_str(&value)?;
+                }
+                "--erosion" => erosion = true,
+                _ => bail!("Unknown argument '{arg}'"),
+            }
+        }
+        Ok(WorldMeta {
+            seed: seed.unwrap_or_else(|| RandomState::new().build_hasher().finish()),
+            preset,
+            erosion,
+        })
+    }
+
+    /// Reopens the world described at `path`, or creates it from the command line arguments
+    /// (which are ignored if the world already exists)
+    pub fn load_or_create(
+        path: impl AsRef<Path>,
+        args: impl Iterator<Item = String>,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            return confy::load_path(path)
+                .with_context(|| format!("Failed to read world metadata '{}'", path.display()));
+        }
+        let meta = Self::from_args(args)?;
+        confy::store_path(path, meta)
+            .with_context(|| format!("Failed to write world metadata '{}'", path.display()))?;
+        Ok(meta)
+    }
+}
+
+/// TOML integers are i64, the seed is stored as text to keep all of its 64 bits
+mod seed_string {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&seed.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_seeds_use_all_bits() {
+        // both seeds used to be folded into the same 32 bits
+        let (a, b) = (0, (1 << 32) | 1);
+        assert_ne!(
+            noise_seed(a, SeedStream::Temperature),
+            noise_seed(b, SeedStream::Temperature)
+        );
+        assert_ne!(
+            noise_seed(a, SeedStream::Temperature),
+            noise_seed(a, SeedStream::Humidity)
+        );
+    }
+
+    #[test]
+    fn numeric_seeds() {
+        assert_eq!(parse_seed("42"), 42);
+        assert_eq!(parse_seed(" 18446744073709551615 "), u64::MAX);
+        assert_eq!(parse_seed("-1"), u64::MAX);
+    }
+
+    #[test]
+    fn text_seeds() {
+        assert_eq!(parse_seed("riverbed"), parse_seed("riverbed"));
+        assert_ne!(parse_seed("riverbed"), parse_seed("Riverbed"));
+        assert_eq!(parse_seed(""), 0xcbf29ce484222325);
+    }
+
+    #[test]
+    fn presets() {
+        assert_eq!(
+            WorldPreset::from_str("single_biome:Desert").unwrap(),
+            WorldPreset::SingleBiome(Biome::Desert)
+        );
+        assert_eq!(
+            WorldPreset::from_str("superflat").unwrap(),
+            WorldPreset::Superflat
+        );
+        assert!(WorldPreset::from_str("single_biome:Swamp").is_err());
+        assert!(WorldPreset::from_str("flat").is_err());
+    }
+
+    #[test]
+    fn args() {
+        let args = ["--seed", "7", "--preset", "amplified"].map(String::from);
+        let meta = WorldMeta::from_args(args.into_iter()).unwrap();
+        assert_eq!(meta.seed, 7);
+        assert_eq!(meta.preset, WorldPreset::Amplified);
+        assert!(!meta.erosion);
+        let args = ["--erosion", "--seed", "7"].map(String::from);
+        assert!(WorldMeta::from_args(args.into_iter()).unwrap().erosion);
+        assert!(WorldMeta::from_args(["--seed"].map(String::from).into_iter()).is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use rb_block_def::generate_items;
+use std::{env, error::Error, fs, path::Path};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let dest_path = Path::new(&env::var_os("OUT_DIR").unwrap()).join("items.rs");
+    // CARGO_MANIFEST_DIR is the crates/rb_items directory, workspace root is two levels up
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
+    let workspace_dir = Path::new(&manifest_dir).join("../..").canonicalize()?;
+    let item_def_path = workspace_dir.join("assets/data/items.def");
+    let item_def = fs::read_to_string(&item_def_path)?;
+    let path = item_def_path.display().to_string();
+    let (rust_code, warnings) = match generate_items(&item_def) {
+        Ok(generated) => generated,
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("{}\n", error.render(&path));
+            }
+            return Err(format!("{} error(s) in {path}", errors.len()).into());
+        }
+    };
+    for warning in warnings {
+        println!("cargo::warning={path}:{}:{}: {}", warning.line, warning.column, warning.message);
+    }
+    fs::write(&dest_path, rust_code)?;
+    println!("cargo::rerun-if-changed={}", item_def_path.display());
+    Ok(())
+}
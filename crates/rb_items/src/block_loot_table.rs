@@ -1,5 +1,7 @@
 use crate::item::{Item, ToolKind};
+use rand::{Rng, RngExt};
 use rb_block::{Block, BlockFamily};
+use rb_world::Biome;
 use serde::Deserialize;
 use std::{collections::HashMap, str::FromStr};
 
@@ -7,6 +9,8 @@ use std::{collections::HashMap, str::FromStr};
 enum DropKind {
     #[serde(rename = "Self")]
     Itself,
+    /// The sapling of the tree the block belongs to
+    Sapling,
     #[serde(untagged)]
     Item(Item),
 }
@@ -15,6 +19,7 @@ impl Into<Item> for (DropKind, Block) {
     fn into(self) -> Item {
         match self.0 {
             DropKind::Itself => Item::Block(self.1),
+            DropKind::Sapling => Item::Block(self.1.sapling().unwrap_or(self.1)),
             DropKind::Item(item) => item,
         }
     }
@@ -35,17 +40,133 @@ impl FromStr for BlockKind {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DropQuantity {
     Fixed(u32),
     Range { min: u32, max: u32 },
 }
 
+impl DropQuantity {
+    fn new(min: Option<u32>, max: Option<u32>) -> Self {
+        match min {
+            Some(min) => DropQuantity::Range {
+                min,
+                max: max.unwrap_or(min),
+            },
+            None => DropQuantity::Fixed(1),
+        }
+    }
+}
+
+/// One of the items of a drop given as a list, picked by weight
+#[derive(Debug, Deserialize, Clone, Copy)]
+struct WeightedDrop {
+    item: DropKind,
+    #[serde(default = "default_weight")]
+    weight: f32,
+}
+
+fn default_weight() -> f32 {
+    1.
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+enum DropChoice {
+    One(DropKind),
+    OneOf(Vec<WeightedDrop>),
+}
+
+/// Changes the chance or quantity of a drop when all of its conditions hold,
+/// later modifiers override earlier ones
+#[derive(Debug, Deserialize, Clone, Default)]
+struct DropModifier {
+    tool: Option<ToolKind>,
+    biome: Option<Biome>,
+    /// The lowest tier of the tool
+    tier: Option<u32>,
+    chance: Option<f32>,
+    min: Option<u32>,
+    max: Option<u32>,
+}
+
+impl DropModifier {
+    fn applies(&self, tool_opt: Option<&Item>, biome: Option<Biome>) -> bool {
+        self.tool.is_none_or(|tool_kind| match tool_kind {
+            ToolKind::Default => tool_opt.is_none(),
+            ToolKind::Item(item) => tool_opt == Some(&item),
+            ToolKind::ToolFamily(family) => tool_opt.and_then(Item::tool_family) == Some(family),
+        }) && self.biome.is_none_or(|expected| biome == Some(expected))
+            && self
+                .tier
+                .is_none_or(|tier| tool_opt.map_or(0, Item::tier) >= tier)
+    }
+
+    /// The chance and quantity of a drop once the modifiers that apply to it are applied
+    fn apply_all(
+        modifiers: &[DropModifier],
+        tool_opt: Option<&Item>,
+        biome: Option<Biome>,
+        chance: Option<f32>,
+        quantity: (Option<u32>, Option<u32>),
+    ) -> (f32, DropQuantity) {
+        let (mut chance, (mut min, mut max)) = (chance, quantity);
+        for modifier in modifiers
+            .iter()
+            .filter(|modifier| modifier.applies(tool_opt, biome))
+        {
+            chance = modifier.chance.or(chance);
+            if modifier.min.is_some() {
+                (min, max) = (modifier.min, modifier.max);
+            }
+        }
+        (chance.unwrap_or(1.), DropQuantity::new(min, max))
+    }
+}
+
+/// One of the outputs of a loot entry
+#[derive(Debug, Deserialize, Clone)]
+struct LootDropPartial {
+    item: DropChoice,
+    min: Option<u32>,
+    max: Option<u32>,
+    /// Probability of the drop, 1 by default
+    chance: Option<f32>,
+    #[serde(default)]
+    modifiers: Vec<DropModifier>,
+}
+
+/// A single item like `drops: "Self"`, or a list of drops each with its own chance
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+enum Drops {
+    One(DropKind),
+    List(Vec<LootDropPartial>),
+}
+
+/// What tools under the tier of a block get
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone, Copy, Default)]
+enum UnderTier {
+    /// They break it but it drops nothing
+    #[default]
+    NoDrops,
+    /// They can't break it
+    Unbreakable,
+}
+
 #[derive(Default, Debug, Deserialize)]
 struct LootEntryPartial {
     pub hardness: Option<f32>,
-    pub drops: Option<DropKind>,
+    pub drops: Option<Drops>,
     pub min: Option<u32>,
     pub max: Option<u32>,
+    /// Modifiers of a single drop like `drops: "Self"`, the drops of a list have their own
+    #[serde(default)]
+    pub modifiers: Vec<DropModifier>,
+    /// The lowest tier of the tools that get the loot, tools declare theirs in items.def.
+    /// It belongs to the block so it's only set in the `Default` entries.
+    pub tier: Option<u32>,
+    pub under_tier: Option<UnderTier>,
 }
 
 impl LootEntryPartial {
@@ -54,51 +175,140 @@ impl LootEntryPartial {
             self.hardness = other.hardness.map(|h| h / efficiency);
         }
         if self.drops.is_none() {
-            self.drops = other.drops;
+            self.drops = other.drops.clone();
             self.min = other.min;
             self.max = other.max;
+            self.modifiers = other.modifiers.clone();
+        }
+        if self.tier.is_none() {
+            self.tier = other.tier;
+            self.under_tier = other.under_tier;
         }
     }
 
-    fn is_complete(&self) -> bool {
-        self.hardness.is_some() && self.drops.is_some()
+    /// Resolves the drops for the block, the tool used and the biome it's in
+    fn resolve(self, block: Block, tool_opt: Option<&Item>, biome: Option<Biome>) -> LootEntry {
+        let drops = match self.drops {
+            None => Vec::new(),
+            Some(Drops::One(drop)) => {
+                let (chance, quantity) = DropModifier::apply_all(
+                    &self.modifiers,
+                    tool_opt,
+                    biome,
+                    None,
+                    (self.min, self.max),
+                );
+                vec![LootDrop {
+                    items: vec![((drop, block).into(), 1.)],
+                    quantity,
+                    chance,
+                }]
+            }
+            Some(Drops::List(drops)) => drops
+                .into_iter()
+                .map(|drop| {
+                    let items = match drop.item {
+                        DropChoice::One(kind) => vec![((kind, block).into(), 1.)],
+                        DropChoice::OneOf(choices) => choices
+                            .into_iter()
+                            .map(|choice| ((choice.item, block).into(), choice.weight))
+                            .collect(),
+                    };
+                    let (chance, quantity) = DropModifier::apply_all(
+                        &drop.modifiers,
+                        tool_opt,
+                        biome,
+                        drop.chance,
+                        (drop.min, drop.max),
+                    );
+                    LootDrop {
+                        items,
+                        quantity,
+                        chance,
+                    }
+                })
+                .collect(),
+        };
+        LootEntry {
+            hardness: self.hardness,
+            drops,
+        }
     }
+}
 
-    fn quantity(&self) -> DropQuantity {
-        if let Some(min) = self.min {
-            DropQuantity::Range {
-                min,
-                max: self.max.unwrap(),
-            }
-        } else {
-            DropQuantity::Fixed(1)
+pub struct LootDrop {
+    /// The item dropped is picked among these by weight
+    pub items: Vec<(Item, f32)>,
+    pub quantity: DropQuantity,
+    /// Probability of the drop, between 0 and 1
+    pub chance: f32,
+}
+
+impl LootDrop {
+    fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(Item, u32)> {
+        if !rng.random_bool(self.chance.clamp(0., 1.) as f64) {
+            return None;
         }
+        let total: f32 = self.items.iter().map(|(_, weight)| weight).sum();
+        let mut pick = rng.random::<f32>() * total;
+        let item = self
+            .items
+            .iter()
+            .find(|(_, weight)| {
+                pick -= weight;
+                pick < 0.
+            })
+            .or(self.items.last())?
+            .0;
+        let quantity = match self.quantity {
+            DropQuantity::Fixed(quantity) => quantity,
+            DropQuantity::Range { min, max } => rng.random_range(min..=max.max(min)),
+        };
+        (quantity > 0).then_some((item, quantity))
     }
 }
 
 pub struct LootEntry {
     pub hardness: Option<f32>,
-    pub drops: Option<Item>,
-    pub quantity: Option<DropQuantity>,
-}
-
-impl From<(LootEntryPartial, Block)> for LootEntry {
-    fn from((entry, block): (LootEntryPartial, Block)) -> Self {
-        Self {
-            hardness: entry.hardness,
-            quantity: if entry.drops.is_none() {
-                None
-            } else {
-                Some(entry.quantity())
-            },
-            drops: entry.drops.map(|drop| (drop, block).into()),
-        }
+    pub drops: Vec<LootDrop>,
+}
+
+impl LootEntry {
+    /// Rolls every drop of the entry, returns the items that dropped
+    pub fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<(Item, u32)> {
+        self.drops
+            .iter()
+            .filter_map(|drop| drop.roll(rng))
+            .collect()
     }
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(try_from = "HashMap<ToolKind, HashMap<BlockKind, LootEntryPartial>>")]
 pub struct BlockLootTable(HashMap<ToolKind, HashMap<BlockKind, LootEntryPartial>>);
 
+impl TryFrom<HashMap<ToolKind, HashMap<BlockKind, LootEntryPartial>>> for BlockLootTable {
+    type Error = String;
+
+    fn try_from(
+        entries: HashMap<ToolKind, HashMap<BlockKind, LootEntryPartial>>,
+    ) -> Result<Self, Self::Error> {
+        for (tool_kind, block_entries) in &entries {
+            if *tool_kind == ToolKind::Default {
+                continue;
+            }
+            for (block_kind, entry) in block_entries {
+                if entry.tier.is_some() || entry.under_tier.is_some() {
+                    return Err(format!(
+                        "the tier of {block_kind:?} is set for {tool_kind:?}, tiers are set in the Default entries"
+                    ));
+                }
+            }
+        }
+        Ok(BlockLootTable(entries))
+    }
+}
+
 impl BlockLootTable {
     fn try_to_complete(
         &self,
@@ -114,85 +324,53 @@ impl BlockLootTable {
         }
     }
 
-    pub fn get(&self, tool_opt: Option<&Item>, block: &Block) -> LootEntry {
-        let mut partial_entry = LootEntryPartial::default();
+    /// Goes from the most specific entries to the least specific ones:
+    /// exact tool, tool family then no tool, each for the exact block then its families.
+    /// The first entry that has a value for hardness, drops or tier sets it.
+    /// Drop modifiers are then applied for the tool and the biome the block is in.
+    pub fn get(&self, tool_opt: Option<&Item>, block: &Block, biome: Option<Biome>) -> LootEntry {
+        let block_kinds = [BlockKind::Block(*block)]
+            .into_iter()
+            .chain(block.families().into_iter().map(BlockKind::Family))
+            .collect::<Vec<_>>();
+        let mut tool_kinds = Vec::new();
         if let Some(tool) = tool_opt {
-            // Check exact tool and exact block
-            self.try_to_complete(
-                &mut partial_entry,
-                &ToolKind::Item(*tool),
-                &BlockKind::Block(*block),
-                1.,
-            );
-            if partial_entry.is_complete() {
-                return (partial_entry, *block).into();
-            }
-            // Check exact tool and exact block family
-            for block_family in block.families() {
-                self.try_to_complete(
-                    &mut partial_entry,
-                    &ToolKind::Item(*tool),
-                    &BlockKind::Family(block_family),
-                    1.,
-                );
-                if partial_entry.is_complete() {
-                    return (partial_entry, *block).into();
-                }
-            }
-            // Check tool family and exact block
-            if let Some((tool_family, efficiency)) = tool.tool_family() {
-                self.try_to_complete(
-                    &mut partial_entry,
-                    &ToolKind::ToolFamily(tool_family),
-                    &BlockKind::Block(*block),
-                    efficiency.0,
-                );
-            }
-            if partial_entry.is_complete() {
-                return (partial_entry, *block).into();
+            tool_kinds.push((ToolKind::Item(*tool), 1.));
+            if let Some(tool_family) = tool.tool_family() {
+                tool_kinds.push((ToolKind::ToolFamily(tool_family), tool.efficiency()));
             }
-            // Check tool family and block family
-            if let Some((tool_family, efficiency)) = tool.tool_family() {
-                for block_family in block.families() {
-                    self.try_to_complete(
-                        &mut partial_entry,
-                        &ToolKind::ToolFamily(tool_family),
-                        &BlockKind::Family(block_family),
-                        efficiency.0,
-                    );
-                    if partial_entry.is_complete() {
-                        return (partial_entry, *block).into();
-                    }
-                }
+        }
+        tool_kinds.push((ToolKind::Default, 1.));
+        let mut partial_entry = LootEntryPartial::default();
+        for (tool_kind, efficiency) in &tool_kinds {
+            for block_kind in &block_kinds {
+                self.try_to_complete(&mut partial_entry, tool_kind, block_kind, *efficiency);
             }
         }
-        self.try_to_complete(
-            &mut partial_entry,
-            &ToolKind::Default,
-            &BlockKind::Block(*block),
-            1.,
-        );
-        if partial_entry.is_complete() {
-            return (partial_entry, *block).into();
-        }
-        for block_family in block.families() {
-            self.try_to_complete(
-                &mut partial_entry,
-                &ToolKind::Default,
-                &BlockKind::Family(block_family),
-                1.,
-            );
-            if partial_entry.is_complete() {
-                return (partial_entry, *block).into();
+        let tool_tier = tool_opt.map_or(0, Item::tier);
+        if partial_entry.tier.is_some_and(|tier| tool_tier < tier) {
+            match partial_entry.under_tier.unwrap_or_default() {
+                UnderTier::NoDrops => partial_entry.drops = None,
+                UnderTier::Unbreakable => partial_entry.hardness = None,
             }
         }
-        return (partial_entry, *block).into();
+        partial_entry.resolve(*block, tool_opt, biome)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// The first item the entry can drop, as entries had a single drop before
+    fn first_drop(entry: &LootEntry) -> Option<Item> {
+        entry
+            .drops
+            .first()
+            .and_then(|drop| drop.items.first())
+            .map(|(item, _)| *item)
+    }
 
     #[test]
     fn correct_fallback() {
@@ -213,32 +391,205 @@ mod tests {
         let block_looting: BlockLootTable = json5::from_str(config).unwrap();
         println!("{:?}", block_looting);
         assert_eq!(
-            block_looting
-                .get(Some(&Item::IronPickaxe), &Block::Limestone)
-                .drops,
+            first_drop(&block_looting.get(Some(&Item::IronPickaxe), &Block::Limestone, None)),
             Some(Item::Block(Block::Limestone))
         );
         assert_eq!(
             block_looting
-                .get(Some(&Item::IronPickaxe), &Block::Limestone)
+                .get(Some(&Item::IronPickaxe), &Block::Limestone, None)
                 .hardness,
             Some(1.)
         );
         assert_eq!(
-            block_looting
-                .get(Some(&Item::Stick), &Block::Limestone)
-                .drops,
+            first_drop(&block_looting.get(Some(&Item::Stick), &Block::Limestone, None)),
             Some(Item::Lime)
         );
         assert_eq!(
-            block_looting
-                .get(Some(&Item::Stick), &Block::Cobblestone)
-                .drops,
+            first_drop(&block_looting.get(Some(&Item::Stick), &Block::Cobblestone, None)),
             Some(Item::Rock)
         );
         assert_eq!(
-            block_looting.get(None, &Block::Cobblestone).drops,
+            first_drop(&block_looting.get(None, &Block::Cobblestone, None)),
             Some(Item::Rock)
         );
     }
+
+    #[test]
+    fn leaves_drop_saplings() {
+        let config = r#"{ Default: { Leaves: { hardness: 1, drops: "Sapling" } } }"#;
+        let block_looting: BlockLootTable = json5::from_str(config).unwrap();
+        assert_eq!(
+            first_drop(&block_looting.get(None, &Block::BirchLeaves, None)),
+            Some(Item::Block(Block::BirchSapling))
+        );
+    }
+
+    #[test]
+    fn broken_crops_drop_themselves() {
+        let config = r#"{ Default: { Crop: { hardness: 0.2, drops: "Self" } } }"#;
+        let block_looting: BlockLootTable = json5::from_str(config).unwrap();
+        assert_eq!(
+            first_drop(&block_looting.get(None, &Block::Wheat, None)),
+            Some(Item::Block(Block::Wheat))
+        );
+    }
+
+    #[test]
+    fn crop_loot_in_assets() {
+        let read = |file: &str| {
+            let path = format!("{}/../../assets/data/{file}", env!("CARGO_MANIFEST_DIR"));
+            json5::from_str::<BlockLootTable>(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+        let breaking = read("block_breaking.json5");
+        let harvesting = read("block_harvesting.json5");
+        for (crop, produce) in [
+            (Block::Wheat, Item::Grain),
+            (Block::Carrots, Item::Carrot),
+            (Block::Potatoes, Item::Potato),
+        ] {
+            // breaking gives the crop back to be sown again, harvesting gives its produce
+            assert_eq!(
+                first_drop(&breaking.get(None, &crop, None)),
+                Some(Item::Block(crop))
+            );
+            assert_eq!(
+                first_drop(&harvesting.get(None, &crop, None)),
+                Some(produce)
+            );
+        }
+    }
+
+    #[test]
+    fn tool_tiers() {
+        let config = r#"
+        {
+            Default: {
+                Ore: { tier: 2 },
+                GoldOre: { hardness: 10, drops: "Self" },
+                Stone: { tier: 3, under_tier: "Unbreakable" },
+            },
+            Pickaxe: {
+                IronOre: { hardness: 6, drops: "Self" },
+            },
+            Axe: {
+                Stone: { hardness: 8, drops: "Rock" },
+            }
+        }
+        "#;
+        let block_looting: BlockLootTable = json5::from_str(config).unwrap();
+        let iron_ore = block_looting.get(Some(&Item::IronPickaxe), &Block::IronOre, None);
+        assert_eq!(iron_ore.hardness, Some(3.));
+        assert_eq!(first_drop(&iron_ore), Some(Item::Block(Block::IronOre)));
+        // tools under the tier of the ore still break it but get nothing
+        let gold_ore = block_looting.get(Some(&Item::StoneAxe), &Block::GoldOre, None);
+        assert_eq!(gold_ore.hardness, Some(10.));
+        assert_eq!(first_drop(&gold_ore), None);
+        assert_eq!(
+            first_drop(&block_looting.get(None, &Block::GoldOre, None)),
+            None
+        );
+        // no axe is good enough for stone
+        let granite = block_looting.get(Some(&Item::IronAxe), &Block::Granite, None);
+        assert_eq!(granite.hardness, None);
+        assert_eq!(first_drop(&granite), Some(Item::Rock));
+        // tiers belong to the blocks, not to a tool
+        let tool_tier = r#"{ Pickaxe: { Stone: { hardness: 3, tier: 1 } } }"#;
+        assert!(json5::from_str::<BlockLootTable>(tool_tier).is_err());
+    }
+
+    #[test]
+    fn chance_and_modifiers() {
+        let config = r#"
+        {
+            Default: {
+                Leaves: { hardness: 1, drops: [
+                    { item: "Sapling", chance: 0.05, modifiers: [
+                        { biome: "Jungle", chance: 0.2 },
+                        { tool: "Hoe", chance: 0.5 },
+                    ] },
+                    { item: "Stick", min: 1, max: 2, chance: 0.3 },
+                ] },
+            },
+            Pickaxe: {
+                Stone: { hardness: 3, drops: [
+                    { item: "Self" },
+                    { item: [{ item: "Coal", weight: 3 }, { item: "IronOre" }], chance: 0,
+                      modifiers: [{ tier: 2, chance: 0.1, min: 2, max: 3 }] },
+                ] },
+            }
+        }
+        "#;
+        let block_looting: BlockLootTable = json5::from_str(config).unwrap();
+        let leaves = block_looting.get(None, &Block::OakLeaves, None);
+        assert_eq!(leaves.drops.len(), 2);
+        assert_eq!(
+            leaves.drops[0].items,
+            vec![(Item::Block(Block::OakSapling), 1.)]
+        );
+        assert_eq!(leaves.drops[0].chance, 0.05);
+        assert_eq!(
+            leaves.drops[1].quantity,
+            DropQuantity::Range { min: 1, max: 2 }
+        );
+        let jungle = block_looting.get(None, &Block::OakLeaves, Some(Biome::Jungle));
+        assert_eq!(jungle.drops[0].chance, 0.2);
+        // the later modifier wins
+        let hoe = block_looting.get(
+            Some(&Item::StoneHoe),
+            &Block::OakLeaves,
+            Some(Biome::Jungle),
+        );
+        assert_eq!(hoe.drops[0].chance, 0.5);
+
+        let stone = block_looting.get(Some(&Item::StoneAxe), &Block::Granite, None);
+        assert!(stone.drops.is_empty());
+        let stone = block_looting.get(Some(&Item::IronPickaxe), &Block::Granite, None);
+        assert_eq!(stone.drops[1].chance, 0.1);
+        assert_eq!(
+            stone.drops[1].quantity,
+            DropQuantity::Range { min: 2, max: 3 }
+        );
+        let mut rng = StdRng::seed_from_u64(0);
+        let (mut coal, mut bonus_rolls) = (0, 0);
+        for _ in 0..2000 {
+            let rolled = stone.roll(&mut rng);
+            assert_eq!(rolled[0], (Item::Block(Block::Granite), 1));
+            if let Some((item, quantity)) = rolled.get(1) {
+                assert!((2..=3).contains(quantity));
+                bonus_rolls += 1;
+                coal += (*item == Item::Coal) as u32;
+            }
+        }
+        // 10% of the time, 3 times out of 4 coal
+        assert!((150..250).contains(&bonus_rolls));
+        assert!(coal * 4 > bonus_rolls * 2 && coal < bonus_rolls);
+    }
+
+    #[test]
+    fn single_drop_modifiers() {
+        let config = r#"
+        {
+            Default: {
+                Leaves: { hardness: 1, drops: "Sapling", modifiers: [
+                    { biome: "Jungle", min: 2, max: 3 },
+                    { tool: "Hoe", chance: 0.5 },
+                ] },
+            }
+        }
+        "#;
+        let block_looting: BlockLootTable = json5::from_str(config).unwrap();
+        let leaves = block_looting.get(None, &Block::OakLeaves, None);
+        assert_eq!(leaves.drops[0].chance, 1.);
+        assert_eq!(leaves.drops[0].quantity, DropQuantity::Fixed(1));
+        let jungle = block_looting.get(
+            Some(&Item::StoneHoe),
+            &Block::OakLeaves,
+            Some(Biome::Jungle),
+        );
+        assert_eq!(jungle.drops[0].chance, 0.5);
+        assert_eq!(
+            jungle.drops[0].quantity,
+            DropQuantity::Range { min: 2, max: 3 }
+        );
+    }
 }
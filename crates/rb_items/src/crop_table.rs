@@ -0,0 +1,73 @@
+use crate::BlockKind;
+use bevy::prelude::Resource;
+use rb_block::Block;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// How far out of its ranges a crop can still grow, slower and slower
+const CLIMATE_TOLERANCE: f32 = 0.2;
+/// Share of the growth rate that crops keep without any water nearby
+const DRY_GROWTH: f32 = 0.25;
+
+#[derive(Debug, Deserialize)]
+pub struct CropValue {
+    pub temperature: [f32; 2],
+    pub humidity: [f32; 2],
+    /// Time to go from one stage to the next in the best conditions
+    pub stage_minutes: f32,
+}
+
+/// Growth conditions of the crops
+#[derive(Debug, Resource, Deserialize)]
+pub struct CropTable(HashMap<BlockKind, CropValue>);
+
+fn range_factor([min, max]: [f32; 2], value: f32) -> f32 {
+    let outside = (min - value).max(value - max).max(0.);
+    (1. - outside / CLIMATE_TOLERANCE).clamp(0., 1.)
+}
+
+impl CropTable {
+    /// Seconds until the crop reaches its next stage, None if it can't grow in these conditions.
+    /// `moisture` goes from 0 with no water nearby to 1 right next to it.
+    pub fn stage_secs(
+        &self,
+        crop: Block,
+        temperature: f32,
+        humidity: f32,
+        moisture: f32,
+    ) -> Option<f32> {
+        let crop_value = self.0.get(&BlockKind::Block(crop))?;
+        let rate = range_factor(crop_value.temperature, temperature)
+            * range_factor(crop_value.humidity, humidity)
+            * (DRY_GROWTH + (1. - DRY_GROWTH) * moisture.clamp(0., 1.));
+        if rate <= 0. {
+            return None;
+        }
+        Some(crop_value.stage_minutes * 60. / rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn climate_and_water() {
+        let config = r#"
+        {
+            Wheat: { temperature: [0.3, 0.8], humidity: [0.15, 0.6], stage_minutes: 3 },
+        }
+        "#;
+        let crop_table: CropTable = json5::from_str(config).unwrap();
+        let irrigated = crop_table
+            .stage_secs(Block::Wheat, 0.5, 0.3, 1.)
+            .unwrap();
+        let dry = crop_table
+            .stage_secs(Block::Wheat, 0.5, 0.3, 0.)
+            .unwrap();
+        assert_eq!(irrigated, 180.);
+        assert!(dry > irrigated);
+        assert_eq!(crop_table.stage_secs(Block::Wheat, 0.05, 0.3, 1.), None);
+        assert_eq!(crop_table.stage_secs(Block::Dirt, 0.5, 0.3, 1.), None);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{InventoryTrait, Stack};
+use bevy::math::Vec3;
+use std::mem;
+
+/// Players pick up the items this close to them, in blocks
+pub const PICKUP_RADIUS: f32 = 1.5;
+/// Identical items this close to each other merge into a single stack
+pub const MERGE_RADIUS: f32 = 1.;
+
+/// Merges the stack lying at `merged_center` into the one lying at `kept_center` if they're close enough,
+/// what can't be merged stays in `merged`. Returns whether `merged` was emptied.
+pub fn merge_stacks(
+    kept: &mut Stack,
+    kept_center: Vec3,
+    merged: &mut Stack,
+    merged_center: Vec3,
+) -> bool {
+    if *kept == Stack::None
+        || *merged == Stack::None
+        || kept_center.distance(merged_center) > MERGE_RADIUS
+    {
+        return false;
+    }
+    // only stackable items merge, try_add gives the stack back otherwise
+    match kept.try_add(mem::take(merged)) {
+        None => true,
+        Some(rest) => {
+            *merged = rest;
+            false
+        }
+    }
+}
+
+/// Whether an item centered on `item_center` is in reach of a player whose box goes from `player_min` to `player_max`
+pub fn in_pickup_reach(item_center: Vec3, player_min: Vec3, player_max: Vec3) -> bool {
+    item_center
+        .clamp(player_min, player_max)
+        .distance(item_center)
+        <= PICKUP_RADIUS
+}
+
+/// Moves what fits of `stack` into the inventory, returns whether anything was picked up
+pub fn pick_up(inventory: &mut [Stack], stack: &mut Stack) -> bool {
+    let quantity = stack.quantity();
+    let rest = inventory.try_add(mem::take(stack)).unwrap_or_default();
+    let picked_up = rest.quantity() < quantity;
+    *stack = rest;
+    picked_up
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use bevy::math::Vec3;
+
+    #[test]
+    fn test_merge_stacks() {
+        let (near, far) = (Vec3::X * 0.5, Vec3::X * 2.);
+        let mut kept = Stack::Some(Item::Rock, 2);
+        let mut merged = Stack::Some(Item::Rock, 3);
+        assert!(!merge_stacks(&mut kept, Vec3::ZERO, &mut merged, far));
+        assert_eq!(merged, Stack::Some(Item::Rock, 3));
+        assert!(merge_stacks(&mut kept, Vec3::ZERO, &mut merged, near));
+        assert_eq!(kept, Stack::Some(Item::Rock, 5));
+        assert_eq!(merged, Stack::None);
+        // tools and different items stay apart
+        let mut axe = Stack::Tool(Item::IronAxe, 10);
+        let mut other_axe = Stack::Tool(Item::IronAxe, 10);
+        assert!(!merge_stacks(&mut axe, Vec3::ZERO, &mut other_axe, near));
+        assert_eq!(other_axe, Stack::Tool(Item::IronAxe, 10));
+        let mut stick = Stack::Some(Item::Stick, 1);
+        assert!(!merge_stacks(&mut kept, Vec3::ZERO, &mut stick, near));
+        assert_eq!(stick, Stack::Some(Item::Stick, 1));
+    }
+
+    #[test]
+    fn test_pick_up() {
+        let reach = |center| in_pickup_reach(center, Vec3::ZERO, Vec3::new(0.8, 1.8, 0.8));
+        assert!(reach(Vec3::new(0.4, 1., 0.4)));
+        // the distance is measured from the side of the player
+        assert!(reach(Vec3::new(2., 0., 0.4)));
+        assert!(!reach(Vec3::new(2.5, 0., 0.4)));
+        let mut inventory = new_inventory::<2>();
+        inventory[0] = Stack::Tool(Item::IronAxe, 10);
+        let mut rocks = Stack::Some(Item::Rock, 3);
+        assert!(pick_up(&mut inventory, &mut rocks));
+        assert_eq!(rocks, Stack::None);
+        assert_eq!(inventory[1], Stack::Some(Item::Rock, 3));
+        // the inventory is full
+        let mut axe = Stack::Tool(Item::IronAxe, 10);
+        assert!(!pick_up(&mut inventory, &mut axe));
+        assert_eq!(axe, Stack::Tool(Item::IronAxe, 10));
+    }
+}
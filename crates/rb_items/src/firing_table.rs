@@ -1,7 +1,6 @@
 use crate::item_holder::ItemHolder;
 use crate::{Item, Stack};
 use bevy::prelude::{Component, Resource};
-use rb_block::BlockFamily;
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -36,24 +35,7 @@ impl FiringTable {
         let Stack::Some(material_item, _) = material else {
             return None;
         };
-        let fuel_time = match fuel {
-            Stack::Some(item, _) => match item {
-                Item::Coal => 20.,
-                Item::Block(block) => {
-                    let families = block.families();
-                    if families.contains(&BlockFamily::Log) {
-                        10.
-                    } else if families.contains(&BlockFamily::Planks) {
-                        2.
-                    } else {
-                        0.
-                    }
-                }
-                _ => 0.,
-            },
-            Stack::None => 0.,
-        } * 1000.
-            / furnace_temp as f32;
+        let fuel_time = fuel.item().map_or(0., Item::fuel_value) * 1000. / furnace_temp as f32;
         // Fuel is not suitable
         if fuel_time == 0. {
             return None;
@@ -4,37 +4,64 @@ use std::collections::HashMap;
 #[derive(Default, Debug, PartialEq, Eq)]
 pub enum Stack {
     Some(Item, u32),
+    /// A single item that wears out, with the durability it has left
+    Tool(Item, u32),
     #[default]
     None,
 }
 
 impl Stack {
+    /// Items with a durability in items.def come one at a time and as good as new,
+    /// use `new_many` for more than 1 of them
+    pub fn new(item: Item, quantity: u32) -> Stack {
+        if quantity == 0 {
+            Stack::None
+        } else if item.durability() > 0 {
+            assert!(quantity == 1, "{quantity} {item:?} don't fit in one stack");
+            Stack::Tool(item, item.durability())
+        } else {
+            Stack::Some(item, quantity)
+        }
+    }
+
+    /// One stack per item for items with a durability, a single stack otherwise
+    pub fn new_many(item: Item, quantity: u32) -> Vec<Stack> {
+        if item.durability() > 0 {
+            (0..quantity).map(|_| Stack::new(item, 1)).collect()
+        } else {
+            vec![Stack::new(item, quantity)]
+        }
+    }
+
     pub fn can_add(&self, other: Stack) -> bool {
-        let Stack::Some(item, _) = self else {
-            return true;
-        };
-        let Stack::Some(other_item, _) = other else {
-            return true;
-        };
-        item == &other_item
+        match (self, other) {
+            (Stack::None, _) | (_, Stack::None) => true,
+            (Stack::Some(item, _), Stack::Some(other_item, _)) => {
+                *item == other_item && item.is_stackable()
+            }
+            _ => false,
+        }
     }
 
     /// Tries to add other to self, and output what couldn't be added (either None or other in the case of uncapped stacks)
     pub fn try_add(&mut self, other: Stack) -> Option<Stack> {
-        let Stack::Some(other_item, other_stack) = other else {
+        if other == Stack::None {
             return None;
-        };
-
-        if let Stack::Some(item, stack) = self {
-            if *item == other_item {
-                *stack += other_stack;
+        }
+        match self {
+            Stack::None => {
+                *self = other;
                 None
-            } else {
-                Some(other)
             }
-        } else {
-            *self = other;
-            None
+            // items that don't stack, such as tools, each take a slot
+            Stack::Some(item, stack) if item.is_stackable() => match other {
+                Stack::Some(other_item, other_stack) if *item == other_item => {
+                    *stack += other_stack;
+                    None
+                }
+                _ => Some(other),
+            },
+            _ => Some(other),
         }
     }
 
@@ -60,21 +87,52 @@ impl Stack {
     pub fn item(&self) -> Option<&Item> {
         match self {
             Stack::None => None,
-            Stack::Some(item, _) => Some(item),
+            Stack::Some(item, _) | Stack::Tool(item, _) => Some(item),
         }
     }
 
     pub fn quantity(&self) -> u32 {
         match self {
             Stack::Some(_, n) => *n,
+            Stack::Tool(..) => 1,
             Stack::None => 0,
         }
     }
 
+    /// The share of its durability a tool has left, None for other stacks
+    pub fn durability_left(&self) -> Option<f32> {
+        match self {
+            Stack::Tool(item, durability) => Some(*durability as f32 / item.durability() as f32),
+            _ => None,
+        }
+    }
+
+    /// Whether the stack is a tool that lost some of its durability
+    pub fn is_worn(&self) -> bool {
+        self.durability_left().is_some_and(|left| left < 1.)
+    }
+
+    /// Takes `amount` of durability from a tool, the tool breaks when it has none left.
+    /// Returns true if it broke, other stacks are left as they are.
+    pub fn wear(&mut self, amount: u32) -> bool {
+        let Stack::Tool(_, durability) = self else {
+            return false;
+        };
+        *durability = durability.saturating_sub(amount);
+        if *durability == 0 {
+            *self = Stack::None;
+            return true;
+        }
+        false
+    }
+
     pub fn take(&mut self, n: u32) -> Stack {
         if n == 0 {
             return Stack::None;
         }
+        if let Stack::Tool(..) = self {
+            return std::mem::take(self);
+        }
         let Stack::Some(item, amount) = self else {
             return Stack::None;
         };
@@ -91,6 +149,7 @@ impl Stack {
     fn clone(&self) -> Stack {
         match self {
             Stack::Some(item, qty) => Stack::Some(item.clone(), *qty),
+            Stack::Tool(item, durability) => Stack::Tool(item.clone(), *durability),
             Stack::None => Stack::None,
         }
     }
@@ -110,10 +169,12 @@ pub struct InventoryRecipes {
 pub trait InventoryTrait {
     fn try_add(&mut self, stack: Stack) -> Option<Stack>;
 
+    /// Only selects worn tools if `worn_only` is set, so that repairs don't take tools as good as new
     fn try_select_item(
         &self,
         target_item: &Item,
         target_quantity: u32,
+        worn_only: bool,
         selection: &mut HashMap<usize, u32>,
     ) -> bool;
 
@@ -142,15 +203,14 @@ impl InventoryTrait for [Stack] {
         &self,
         target_item: &Item,
         mut target_quantity: u32,
+        worn_only: bool,
         selection: &mut HashMap<usize, u32>,
     ) -> bool {
         for (i, stack) in self.iter().enumerate() {
-            let &Stack::Some(item, mut qty) = stack else {
-                continue;
-            };
-            if item != *target_item {
+            if stack.item() != Some(target_item) || (worn_only && !stack.is_worn()) {
                 continue;
             }
+            let mut qty = stack.quantity();
             qty = (qty - selection.get(&i).unwrap_or(&0)).min(target_quantity);
             *selection.entry(i).or_insert(0) += qty;
             target_quantity -= qty;
@@ -164,7 +224,9 @@ impl InventoryTrait for [Stack] {
     fn is_recipe_craftable(&self, recipe: &Recipe) -> Option<HashMap<usize, u32>> {
         let mut selection: HashMap<usize, u32> = HashMap::new();
         for (ingredient, qty) in &recipe.ingredients {
-            if !self.try_select_item(ingredient, *qty, &mut selection) {
+            // a recipe that makes one of its ingredients repairs it
+            let repair = *ingredient == recipe.out.0;
+            if !self.try_select_item(ingredient, *qty, repair, &mut selection) {
                 return None;
             }
         }
@@ -213,6 +275,7 @@ mod tests {
     use crate::*;
     use itertools::Itertools;
     use rb_block::Block;
+    use std::collections::HashMap;
 
     #[test]
     fn test_recipe_filter() {
@@ -250,4 +313,74 @@ mod tests {
                 .collect_vec()
         );
     }
+
+    #[test]
+    fn test_tools_dont_stack() {
+        let mut inventory = new_inventory::<3>();
+        inventory.try_add(Stack::new(Item::IronAxe, 1));
+        inventory.try_add(Stack::new(Item::Stick, 1));
+        inventory.try_add(Stack::new(Item::IronAxe, 1));
+        inventory.try_add(Stack::new(Item::Stick, 2));
+        assert_eq!(inventory[0], Stack::Tool(Item::IronAxe, 250));
+        assert_eq!(inventory[1], Stack::Some(Item::Stick, 3));
+        assert_eq!(inventory[2], Stack::Tool(Item::IronAxe, 250));
+        // the inventory is full
+        assert_eq!(
+            inventory.try_add(Stack::new(Item::IronAxe, 1)),
+            Some(Stack::Tool(Item::IronAxe, 250))
+        );
+    }
+
+    #[test]
+    fn test_many_tools() {
+        let mut inventory = new_inventory::<3>();
+        for stack in Stack::new_many(Item::IronAxe, 2) {
+            inventory.try_add(stack);
+        }
+        for stack in Stack::new_many(Item::Stick, 4) {
+            inventory.try_add(stack);
+        }
+        assert_eq!(inventory[0], Stack::Tool(Item::IronAxe, 250));
+        assert_eq!(inventory[1], Stack::Tool(Item::IronAxe, 250));
+        assert_eq!(inventory[2], Stack::Some(Item::Stick, 4));
+    }
+
+    #[test]
+    fn test_worn_tools_dont_stack() {
+        let mut inventory = new_inventory::<2>();
+        let mut worn_axe = Stack::new(Item::IronAxe, 1);
+        worn_axe.wear(50);
+        inventory.try_add(Stack::new(Item::IronAxe, 1));
+        inventory.try_add(worn_axe);
+        assert_eq!(inventory[0], Stack::Tool(Item::IronAxe, 250));
+        assert_eq!(inventory[1], Stack::Tool(Item::IronAxe, 200));
+    }
+
+    #[test]
+    fn test_tool_wear() {
+        let mut pickaxe = Stack::new(Item::IronPickaxe, 1);
+        assert_eq!(pickaxe, Stack::Tool(Item::IronPickaxe, 250));
+        assert!(!pickaxe.wear(200));
+        assert_eq!(pickaxe.durability_left(), Some(0.2));
+        assert!(pickaxe.wear(60));
+        assert_eq!(pickaxe, Stack::None);
+    }
+
+    #[test]
+    fn test_repair() {
+        let repair = Recipe {
+            ingredients: vec![(Item::IronPickaxe, 1), (Item::IronIngot, 1)],
+            out: (Item::IronPickaxe, 1),
+        };
+        let mut inventory = new_inventory::<3>();
+        inventory[0] = Stack::new(Item::IronPickaxe, 1);
+        inventory[1] = Stack::Some(Item::IronIngot, 2);
+        // only worn tools get repaired
+        assert_eq!(inventory.is_recipe_craftable(&repair), None);
+        inventory[2] = Stack::Tool(Item::IronPickaxe, 12);
+        assert_eq!(
+            inventory.is_recipe_craftable(&repair),
+            Some(HashMap::from([(2, 1), (1, 1)]))
+        );
+    }
 }
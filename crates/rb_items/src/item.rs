@@ -1,6 +1,5 @@
 use std::str::FromStr;
 
-use serde::Deserialize;
 use rb_block::Block;
 
 #[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
@@ -12,30 +11,17 @@ pub enum ToolKind {
     ToolFamily(ToolFamily)
 }
 
+/// Declared with `tool(<family>)` in items.def
 #[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum ToolFamily {
     Pickaxe,
     Axe,
     Shovel,
+    Hoe,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum Item {
-    Brick,
-    Clay,
-    Coal,
-    Lime,
-    Stick,
-    Rock,
-    StoneAxe,
-    IronOre,
-    IronIngot,
-    IronPickaxe,
-    IronAxe,
-    IronShovel,
-    #[serde(untagged)]
-    Block(Block),
-}
+// Item, ItemFamily and the properties of items, generated from assets/data/items.def
+include!(concat!(env!("OUT_DIR"), "/items.rs"));
 
 impl FromStr for Item {
     type Err = json5::Error;
@@ -45,16 +31,12 @@ impl FromStr for Item {
     }
 }
 
-pub struct Efficiency(pub f32);
-
 impl Item {
-    pub fn tool_family(&self) -> Option<(ToolFamily, Efficiency)> {
+    /// Seconds the item burns for at 1000°, from items.def or blocks.def for blocks
+    pub fn fuel_value(&self) -> f32 {
         match self {
-            Item::StoneAxe => Some((ToolFamily::Axe, Efficiency(1.))),
-            Item::IronPickaxe => Some((ToolFamily::Pickaxe, Efficiency(2.))),
-            Item::IronAxe => Some((ToolFamily::Axe, Efficiency(2.))),
-            Item::IronShovel => Some((ToolFamily::Shovel, Efficiency(2.))),
-            _ => None
+            Item::Block(block) => block.fuel(),
+            item => item.fuel(),
         }
     }
 }
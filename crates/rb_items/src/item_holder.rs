@@ -1,6 +1,7 @@
 use crate::inventory::{InventoryTrait, Stack};
 use crate::item::Item;
 use bevy::prelude::Component;
+use std::mem;
 
 // TODO: If/When trait queries get adopted by Bevy (https://github.com/bevyengine/bevy/issues/15970)
 // get rid of this enum and use a trait instead, item holding components will implement this trait
@@ -46,6 +47,11 @@ pub fn furnace_slots() -> ItemHolder {
     }
 }
 
+/// Convenience constructor for the empty slots of a chest, from the `storage` of its block.
+pub fn chest_slots(slots: usize) -> ItemHolder {
+    ItemHolder::Inventory((0..slots).map(|_| Stack::None).collect())
+}
+
 impl ItemHolder {
     pub fn can_receive(&self, item: &Item, slot_id: usize) -> bool {
         match self {
@@ -53,7 +59,7 @@ impl ItemHolder {
                 let slot_id = slot_id.into();
                 match slot_id {
                     FurnaceSlot::Material => true,
-                    FurnaceSlot::Fuel => item == &Item::Coal,
+                    FurnaceSlot::Fuel => item.fuel_value() > 0.,
                     FurnaceSlot::Output => false,
                 }
             }
@@ -99,12 +105,71 @@ impl ItemHolder {
 
     pub fn try_add(&mut self, stack: Stack) -> Option<Stack> {
         match self {
-            ItemHolder::Furnace {
-                fuel: _,
-                material: _,
-                output: _,
-            } => todo!(),
+            ItemHolder::Furnace { fuel, material, .. } => {
+                // fuel goes in the fuel slot if it can, anything else gets fired
+                let stack = match stack.item() {
+                    Some(item) if item.fuel_value() > 0. => fuel.try_add(stack)?,
+                    _ => stack,
+                };
+                material.try_add(stack)
+            }
             ItemHolder::Inventory(items) => items.try_add(stack),
         }
     }
+
+    /// Empties every slot, to spill the content of a container that gets broken
+    pub fn take_all(&mut self) -> Vec<Stack> {
+        match self {
+            ItemHolder::Furnace {
+                fuel,
+                material,
+                output,
+            } => vec![mem::take(material), mem::take(fuel), mem::take(output)],
+            ItemHolder::Inventory(items) => items.iter_mut().map(mem::take).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_furnace_try_add() {
+        let mut furnace = furnace_slots();
+        assert_eq!(furnace.try_add(Stack::Some(Item::Coal, 3)), None);
+        assert_eq!(furnace.try_add(Stack::Some(Item::IronOre, 2)), None);
+        // the fuel slot is taken by coal and the material slot by ore, there is no room for clay
+        assert_eq!(
+            furnace.try_add(Stack::Some(Item::Clay, 1)),
+            Some(Stack::Some(Item::Clay, 1))
+        );
+        assert_eq!(
+            furnace.get(FurnaceSlot::Fuel.into()),
+            &Stack::Some(Item::Coal, 3)
+        );
+        assert_eq!(
+            furnace.get(FurnaceSlot::Material.into()),
+            &Stack::Some(Item::IronOre, 2)
+        );
+        assert_eq!(
+            furnace.take_all(),
+            vec![
+                Stack::Some(Item::IronOre, 2),
+                Stack::Some(Item::Coal, 3),
+                Stack::None
+            ]
+        );
+        assert_eq!(furnace.get(FurnaceSlot::Fuel.into()), &Stack::None);
+    }
+
+    #[test]
+    fn test_chest_slots() {
+        let mut chest = chest_slots(3);
+        assert_eq!(chest.try_add(Stack::Some(Item::Rock, 4)), None);
+        assert_eq!(chest.try_add(Stack::new(Item::IronAxe, 1)), None);
+        assert_eq!(chest.try_add(Stack::Some(Item::Rock, 2)), None);
+        assert_eq!(chest.get(0), &Stack::Some(Item::Rock, 6));
+        assert_eq!(chest.take_all().len(), 3);
+    }
 }
@@ -2,13 +2,17 @@ mod item;
 mod inventory;
 mod block_loot_table;
 mod craft_table;
+mod crop_table;
 mod firing_table;
+mod dropped_stack;
 pub mod item_holder;
 pub mod item_event;
 pub use inventory::*;
 pub use item::*;
 pub use block_loot_table::*;
 pub use craft_table::*;
+pub use crop_table::*;
 pub use firing_table::*;
+pub use dropped_stack::*;
 pub use item_holder::*;
 pub use item_event::*;
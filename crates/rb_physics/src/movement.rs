@@ -2,11 +2,15 @@ use bevy::{
     prelude::*,
     time::{Time, Timer},
 };
-use itertools::{Itertools, iproduct};
+use itertools::iproduct;
 use rb_block::Block;
 use rb_world::{BlockPos, Realm, VoxelWorld};
 const FREE_FLY_Y_SPEED: f32 = 100.;
 const ACC_MULT: f32 = 150.;
+/// Entities walk up obstacles this high without jumping
+const STEP_HEIGHT: f32 = 0.5;
+/// Gap kept between entities and the blocks they collide with
+const SKIN: f32 = 0.001;
 
 pub struct MovementPlugin;
 
@@ -57,61 +61,26 @@ pub struct Velocity(pub Vec3);
 #[derive(Component)]
 pub struct Gravity(pub f32);
 
-fn extent(v: f32, size: f32) -> Vec<i32> {
-    if size > 0. {
-        ((v.floor() as i32)..=((size + v).floor() as i32)).collect_vec()
-    } else {
-        (((size + v).floor() as i32)..=(v.floor() as i32))
-            .rev()
-            .collect_vec()
-    }
-}
-
-fn blocks_perp_to_axis(
-    pos: Vec3,
-    realm: Realm,
-    aabb: &AABB,
-    normal_axis: usize,
-    plane_axis1: usize,
-    plane_axis2: usize,
-) -> impl Iterator<Item = BlockPos> {
-    let normal_pos = pos[normal_axis].floor() as i32;
-    iproduct!(
-        extent(pos[plane_axis1], aabb.0[plane_axis1]),
-        extent(pos[plane_axis2], aabb.0[plane_axis2])
-    )
-    .map(move |(pos1, pos2)| {
-        let mut pos = BlockPos {
-            realm: realm,
-            ..Default::default()
-        };
-        pos[normal_axis] = normal_pos;
-        pos[plane_axis1] = pos1;
-        pos[plane_axis2] = pos2;
-        pos
-    })
-}
-
 fn update_stepped_block(
     blocks: Res<VoxelWorld>,
     mut query: Query<(&Transform, &Realm, &AABB, &mut SteppingOn)>,
 ) {
     for (transform, realm, aabb, mut stepping_on) in query.iter_mut() {
-        let below = transform.translation + Vec3::new(0., -0.01, 0.);
-        let mut closest_block = Block::Air;
-        let mut min_dist = f32::INFINITY;
-        for block_pos in blocks_perp_to_axis(below, *realm, aabb, 1, 0, 2) {
-            let block = blocks.get_block(block_pos);
-            if block.is_traversable() {
-                continue;
-            }
-            let dist = (below.x - block_pos.x as f32).abs() - (below.y - block_pos.y as f32).abs();
-            if dist < min_dist {
-                min_dist = dist;
-                closest_block = block;
-            }
-        }
-        stepping_on.0 = closest_block;
+        stepping_on.0 = stepped_block(
+            &blocks,
+            *realm,
+            transform.translation,
+            transform.translation + aabb.0,
+        );
+    }
+}
+
+/// The block whose collider holds the box `min..max` up, Air if the box is in the air
+fn stepped_block(blocks: &VoxelWorld, realm: Realm, min: Vec3, max: Vec3) -> Block {
+    let colliders = colliders(blocks, realm, min - Vec3::Y * 0.01, max);
+    match first_hit(&colliders, min, max, 1, -0.01) {
+        (allowed, Some((_, _, block))) if allowed > -0.01 => *block,
+        _ => Block::Air,
     }
 }
 
@@ -183,6 +152,125 @@ fn apply_acc_free_fly(mut query: Query<(&Heading, &mut Velocity), With<FreeFly>>
     }
 }
 
+/// The collision boxes of the blocks around `min..max`, in world coordinates, with the block they belong to
+fn colliders(blocks: &VoxelWorld, realm: Realm, min: Vec3, max: Vec3) -> Vec<(Vec3, Vec3, Block)> {
+    iproduct!(
+        (min.x.floor() as i32)..=(max.x.floor() as i32),
+        (min.y.floor() as i32)..=(max.y.floor() as i32),
+        (min.z.floor() as i32)..=(max.z.floor() as i32)
+    )
+    .flat_map(|(x, y, z)| {
+        let corner = Vec3::new(x as f32, y as f32, z as f32);
+        let pos = BlockPos { realm, x, y, z };
+        let state = blocks.get_block_state(pos);
+        state
+            .collision_boxes(|face| {
+                let [dx, dy, dz] = face.n();
+                blocks.get_block(pos + (dx, dy, dz))
+            })
+            .into_iter()
+            .map(move |shape_box| {
+                (
+                    corner + shape_box.min(),
+                    corner + shape_box.max(),
+                    state.block,
+                )
+            })
+    })
+    .collect()
+}
+
+/// How far the box `min..max` can move along `axis`, up to `motion`, and the collider it hits first if any
+fn first_hit<'a>(
+    colliders: &'a [(Vec3, Vec3, Block)],
+    min: Vec3,
+    max: Vec3,
+    axis: usize,
+    motion: f32,
+) -> (f32, Option<&'a (Vec3, Vec3, Block)>) {
+    let mut allowed = motion;
+    let mut hit = None;
+    for collider in colliders {
+        let (collider_min, collider_max, _) = collider;
+        // only the colliders facing the box along the axis can stop it
+        let facing = (0..3)
+            .filter(|&other| other != axis)
+            .all(|other| collider_min[other] < max[other] && collider_max[other] > min[other]);
+        if !facing {
+            continue;
+        }
+        let collider_allowed = if motion > 0. && collider_min[axis] >= max[axis] {
+            (collider_min[axis] - max[axis] - SKIN).max(0.)
+        } else if motion < 0. && collider_max[axis] <= min[axis] {
+            (collider_max[axis] - min[axis] + SKIN).min(0.)
+        } else {
+            continue;
+        };
+        if collider_allowed.abs() < allowed.abs() {
+            allowed = collider_allowed;
+            hit = Some(collider);
+        }
+    }
+    (allowed, hit)
+}
+
+/// How far the box `min..max` can go along `axis` without hitting anything, up to `motion`
+fn free_motion(
+    blocks: &VoxelWorld,
+    realm: Realm,
+    min: Vec3,
+    max: Vec3,
+    axis: usize,
+    motion: f32,
+) -> f32 {
+    let (mut reach_min, mut reach_max) = (min, max);
+    if motion > 0. {
+        reach_max[axis] += motion;
+    } else {
+        reach_min[axis] += motion;
+    }
+    first_hit(
+        &colliders(blocks, realm, reach_min, reach_max),
+        min,
+        max,
+        axis,
+        motion,
+    )
+    .0
+}
+
+/// The height to climb for the box `min..max` to move along `axis` when it's blocked,
+/// if it stands on the ground and what blocks it is lower than `STEP_HEIGHT`
+fn step_up(
+    blocks: &VoxelWorld,
+    realm: Realm,
+    min: Vec3,
+    max: Vec3,
+    axis: usize,
+    motion: f32,
+) -> Option<f32> {
+    if free_motion(blocks, realm, min, max, 1, -0.01) <= -0.01 {
+        return None;
+    }
+    let lift = Vec3::Y * free_motion(blocks, realm, min, max, 1, STEP_HEIGHT);
+    if free_motion(blocks, realm, min + lift, max + lift, axis, motion) != motion {
+        return None;
+    }
+    // land on top of the obstacle
+    let mut moved = Vec3::ZERO;
+    moved[axis] = motion;
+    let climb = lift.y
+        + free_motion(
+            blocks,
+            realm,
+            min + lift + moved,
+            max + lift + moved,
+            1,
+            -lift.y,
+        );
+    (climb > 0.).then_some(climb)
+}
+
 fn apply_velocity(
     blocks: Res<VoxelWorld>,
     time: Res<Time>,
@@ -194,34 +282,74 @@ fn apply_velocity(
         }
         let applied_velocity = velocity.0 * time.delta_secs();
         // split the motion on all 3 axis, check for collisions, adjust the final speed vector if there's any
-        for (normal, axis1, axis2) in [(0, 1, 2), (1, 0, 2), (2, 0, 1)] {
-            let pos = transform.translation[normal]
-                + if applied_velocity[normal] > 0. {
-                    aabb.0[normal]
-                } else {
-                    0.
-                };
-            let mut stopped = false;
-            for i in extent(pos, applied_velocity[normal]).into_iter().skip(1) {
-                let mut pos = transform.translation;
-                pos[normal] = i as f32;
-                if blocks_perp_to_axis(pos, *realm, aabb, normal, axis1, axis2)
-                    .any(|pos| !blocks.get_block(pos).is_traversable())
-                {
-                    // there's a collision in this direction, stop at the block limit
-                    if applied_velocity[normal] > 0. {
-                        transform.translation[normal] = pos[normal] - aabb.0[normal] - 0.001;
-                    } else {
-                        transform.translation[normal] = pos[normal] + 1.001;
-                    }
-                    velocity.0[normal] = 0.;
-                    stopped = true;
-                    break;
-                }
+        for axis in 0..3 {
+            let motion = applied_velocity[axis];
+            if motion == 0. {
+                continue;
+            }
+            let (min, max) = (transform.translation, transform.translation + aabb.0);
+            let allowed = free_motion(&blocks, *realm, min, max, axis, motion);
+            if allowed == motion {
+                transform.translation[axis] += motion;
+                continue;
             }
-            if !stopped {
-                transform.translation[normal] += applied_velocity[normal];
+            // walk up slabs and stairs instead of stopping
+            if axis != 1
+                && let Some(climb) = step_up(&blocks, *realm, min, max, axis, motion)
+            {
+                transform.translation.y += climb;
+                transform.translation[axis] += motion;
+                continue;
             }
+            // there's a collision in this direction, stop against it
+            transform.translation[axis] += allowed;
+            velocity.0[axis] = 0.;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER_AABB: Vec3 = Vec3::new(0.6, 1.8, 0.6);
+
+    fn world_with(block: Block) -> VoxelWorld {
+        let (sender, _receiver) = crossbeam::channel::unbounded();
+        let world = VoxelWorld::new(sender);
+        let pos = BlockPos {
+            realm: Realm::Overworld,
+            x: 0,
+            y: 10,
+            z: 0,
+        };
+        world.set_block(pos, block);
+        world
+    }
+
+    fn stepped_at(world: &VoxelWorld, y: f32) -> Block {
+        let min = Vec3::new(0.2, y, 0.2);
+        stepped_block(world, Realm::Overworld, min, min + PLAYER_AABB)
+    }
+
+    #[test]
+    fn standing_on_slab() {
+        let world = world_with(Block::OakSlab);
+        assert_eq!(stepped_at(&world, 10.5 + SKIN), Block::OakSlab);
+    }
+
+    #[test]
+    fn airborne_above_slab() {
+        let world = world_with(Block::OakSlab);
+        // still in the cell of the slab, but above its collider
+        assert_eq!(stepped_at(&world, 10.8), Block::Air);
+        assert_eq!(stepped_at(&world, 11. + SKIN), Block::Air);
+    }
+
+    #[test]
+    fn standing_on_full_block() {
+        let world = world_with(Block::OakPlanks);
+        assert_eq!(stepped_at(&world, 11. + SKIN), Block::OakPlanks);
+        assert_eq!(stepped_at(&world, 11.5), Block::Air);
+    }
+}
@@ -8,7 +8,7 @@ use rb_block::Face;
 pub fn chunk_culling(
     view_query: Query<(&Frustum, &Camera, &GlobalTransform), Changed<Frustum>>,
     mut chunk_query: Query<
-        (&mut Visibility, &Transform, &Face, &Aabb),
+        (&mut Visibility, &Transform, Option<&Face>, &Aabb),
         With<NoFrustumCulling>,
     >,
 ) {
@@ -35,7 +35,10 @@ pub fn chunk_culling(
                 half_extents: aabb.half_extents,
             };
             total.fetch_add(1, Ordering::AcqRel);
-            *visibility = if !face_visible(&chunk_cam_pos, chunk_pos(coord.translation), face)
+            let face_hidden = face.is_some_and(|face| {
+                !face_visible(&chunk_cam_pos, chunk_pos(coord.translation), face)
+            });
+            *visibility = if face_hidden
                 || !frustum.intersects_sphere(&world_sphere, false)
                 || !intersects_aabb(frustum, &world_aabb)
             {
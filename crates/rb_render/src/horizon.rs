@@ -0,0 +1,195 @@
+use bevy::{
+    asset::RenderAssetUsages, mesh::Indices, prelude::*, render::render_resource::PrimitiveTopology,
+};
+use rb_camera::PlayerControlled;
+use rb_world::{
+    CHUNK_S1, ChunkPos2d, ColLoadEvent, ColUnloadEvent, HORIZON_CELL, HORIZON_DISTANCE,
+    HORIZON_RES, HorizonTile, HorizonTileReceiver, PlayerCol, VoxelWorld,
+};
+use std::collections::HashMap;
+
+/// The edges of the tiles hang this far down so the gaps with their neighbors don't show
+const SKIRT_DEPTH: f32 = 32.;
+/// Sides of the cells are a bit darker than their tops
+const SIDE_SHADE: f32 = 0.75;
+
+/// Draws the horizon tiles past the render distance, until the real chunk columns are loaded
+pub struct HorizonPlugin;
+
+impl Plugin for HorizonPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HorizonTiles::default())
+            .add_systems(Startup, setup_horizon_material)
+            .add_systems(
+                Update,
+                (pull_horizon_tiles, drop_far_tiles, toggle_loaded_tiles)
+                    .chain()
+                    .run_if(resource_exists::<HorizonTileReceiver>),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct HorizonTiles(HashMap<ChunkPos2d, Entity>);
+
+#[derive(Resource)]
+struct HorizonMaterial(Handle<StandardMaterial>);
+
+fn setup_horizon_material(mut commands: Commands, mut materials: ResMut<Assets<StandardMaterial>>) {
+    // the tiles are colored by their vertices
+    commands.insert_resource(HorizonMaterial(materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        perceptual_roughness: 1.,
+        reflectance: 0.,
+        ..default()
+    })));
+}
+
+/// Flat topped cells, with sides going down to their lower neighbors or hanging below the tile edges
+fn tile_mesh(tile: &HorizonTile) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    // the quad spans `u` and `v` from `origin`, it faces `u × v`
+    let mut quad = |origin: Vec3, u: Vec3, v: Vec3, [r, g, b]: [f32; 3]| {
+        let start = positions.len() as u32;
+        positions.extend([origin, origin + u, origin + v, origin + u + v].map(|p| p.to_array()));
+        normals.extend([u.cross(v).normalize().to_array(); 4]);
+        colors.extend([[r, g, b, 1.]; 4]);
+        indices.extend([start, start + 1, start + 2, start + 2, start + 1, start + 3]);
+    };
+    let height_at = |x: i32, z: i32| {
+        let res = HORIZON_RES as i32;
+        ((0..res).contains(&x) && (0..res).contains(&z))
+            .then(|| (tile.heights[(x + z * res) as usize] + 1) as f32)
+    };
+    let s = HORIZON_CELL;
+    for z in 0..HORIZON_RES as i32 {
+        for x in 0..HORIZON_RES as i32 {
+            let i = x as usize + z as usize * HORIZON_RES;
+            let top = height_at(x, z).unwrap();
+            let color = tile.colors[i];
+            let side_color = color.map(|c| c * SIDE_SHADE);
+            let (x0, z0) = (x as f32 * s, z as f32 * s);
+            quad(
+                Vec3::new(x0, top, z0),
+                Vec3::new(0., 0., s),
+                Vec3::new(s, 0., 0.),
+                color,
+            );
+            let bottom = |dx: i32, dz: i32| height_at(x + dx, z + dz).unwrap_or(top - SKIRT_DEPTH);
+            let b = bottom(1, 0);
+            if b < top {
+                let dy = Vec3::new(0., top - b, 0.);
+                quad(
+                    Vec3::new(x0 + s, b, z0),
+                    dy,
+                    Vec3::new(0., 0., s),
+                    side_color,
+                );
+            }
+            let b = bottom(-1, 0);
+            if b < top {
+                let dy = Vec3::new(0., top - b, 0.);
+                quad(Vec3::new(x0, b, z0), Vec3::new(0., 0., s), dy, side_color);
+            }
+            let b = bottom(0, 1);
+            if b < top {
+                let dy = Vec3::new(0., top - b, 0.);
+                quad(
+                    Vec3::new(x0, b, z0 + s),
+                    Vec3::new(s, 0., 0.),
+                    dy,
+                    side_color,
+                );
+            }
+            let b = bottom(0, -1);
+            if b < top {
+                let dy = Vec3::new(0., top - b, 0.);
+                quad(Vec3::new(x0, b, z0), dy, Vec3::new(s, 0., 0.), side_color);
+            }
+        }
+    }
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Tiles of the chunk columns that are already loaded are hidden right away,
+/// the others are hidden once their column loads
+fn pull_horizon_tiles(
+    mut commands: Commands,
+    tile_receiver: Res<HorizonTileReceiver>,
+    mut tiles: ResMut<HorizonTiles>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    material: Res<HorizonMaterial>,
+    world: Res<VoxelWorld>,
+) {
+    for tile in tile_receiver.0.try_iter() {
+        let visibility = if world.loaded_columns.contains(&tile.col) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        let ent = commands
+            .spawn((
+                Mesh3d(meshes.add(tile_mesh(&tile))),
+                MeshMaterial3d(material.0.clone()),
+                Transform::from_translation(Vec3::new(
+                    (tile.col.x * CHUNK_S1 as i32) as f32,
+                    0.,
+                    (tile.col.z * CHUNK_S1 as i32) as f32,
+                )),
+                visibility,
+            ))
+            .id();
+        if let Some(old_ent) = tiles.0.insert(tile.col, ent) {
+            commands.entity(old_ent).despawn();
+        }
+    }
+}
+
+fn drop_far_tiles(
+    mut commands: Commands,
+    player_query: Single<&PlayerCol, (With<PlayerControlled>, Changed<PlayerCol>)>,
+    mut tiles: ResMut<HorizonTiles>,
+) {
+    let player_col = player_query.0;
+    tiles.0.retain(|col, ent| {
+        let keep = col.dist(player_col) <= HORIZON_DISTANCE;
+        if !keep {
+            commands.entity(*ent).despawn();
+        }
+        keep
+    });
+}
+
+/// Real chunk columns replace the tiles as the player approaches, and tiles come back when they unload
+fn toggle_loaded_tiles(
+    tiles: Res<HorizonTiles>,
+    mut visibilities: Query<&mut Visibility>,
+    mut load_events: MessageReader<ColLoadEvent>,
+    mut unload_events: MessageReader<ColUnloadEvent>,
+    world: Res<VoxelWorld>,
+) {
+    let loaded = load_events.read().map(|ColLoadEvent(col)| col);
+    let unloaded = unload_events.read().map(|ColUnloadEvent(col)| col);
+    for col in loaded.chain(unloaded) {
+        let Some(Ok(mut visibility)) = tiles.0.get(col).map(|ent| visibilities.get_mut(*ent))
+        else {
+            continue;
+        };
+        let wanted = if world.loaded_columns.contains(col) {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        visibility.set_if_neq(wanted);
+    }
+}
@@ -1,4 +1,5 @@
 mod chunk_culling;
+mod horizon;
 mod mesh_draw;
 mod mesh_logic;
 mod mesh_thread;
@@ -6,6 +7,7 @@ mod mesh_utils;
 mod sky;
 mod texture_array;
 mod texture_load;
+mod tint;
 use bevy::prelude::Plugin;
 pub use mesh_thread::{MeshOrderReceiver, MeshOrderSender};
 pub use texture_load::*;
@@ -15,6 +17,7 @@ pub struct RenderPlugin;
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_plugins(mesh_draw::Draw3d)
+            .add_plugins(horizon::HorizonPlugin)
             .add_plugins(sky::SkyPlugin);
     }
 }
@@ -108,20 +108,21 @@ pub fn pull_meshes(
                 warn!("entity wasn't ready to recieve updated mesh");
             }
         } else {
-            let ent = commands
-                .spawn((
-                    Mesh3d(meshes.add(mesh)),
-                    MeshMaterial3d(block_tex_array.0.clone()),
-                    Transform::from_translation(
-                        Vec3::new(chunk_pos.x as f32, chunk_pos.y as f32, chunk_pos.z as f32)
-                            * CHUNK_S1 as f32,
-                    ),
-                    NoFrustumCulling,
-                    chunk_aabb,
-                    lod,
-                    face,
-                ))
-                .id();
+            let mut ent_commands = commands.spawn((
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(block_tex_array.0.clone()),
+                Transform::from_translation(
+                    Vec3::new(chunk_pos.x as f32, chunk_pos.y as f32, chunk_pos.z as f32)
+                        * CHUNK_S1 as f32,
+                ),
+                NoFrustumCulling,
+                chunk_aabb,
+                lod,
+            ));
+            if let Some(face) = face {
+                ent_commands.insert(face);
+            }
+            let ent = ent_commands.id();
             if chunk_ents.0.insert((chunk_pos, face), ent).is_some() {
                 panic!(
                     "2 entities for the same chunk and face: {:?} {:?}",
@@ -142,7 +143,7 @@ pub fn on_col_unload(
 ) {
     for col_ev in ev_unload.read() {
         for chunk_pos in chunks_in_col(&col_ev.0) {
-            for face in Face::iter() {
+            for face in Face::iter().map(Some).chain([None]) {
                 if let Some(ent) = chunk_ents.0.remove(&(chunk_pos, face)) {
                     if let Ok(handle) = mesh_query.get(ent) {
                         meshes.remove(handle);
@@ -155,8 +156,9 @@ pub fn on_col_unload(
     }
 }
 
+/// Chunk mesh entities, one per face plus one (with no face) for non-cube shapes
 #[derive(Resource)]
-pub struct ChunkEntities(pub HashMap<(ChunkPos, Face), Entity>);
+pub struct ChunkEntities(pub HashMap<(ChunkPos, Option<Face>), Entity>);
 
 impl ChunkEntities {
     pub fn new() -> Self {
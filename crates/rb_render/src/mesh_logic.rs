@@ -9,12 +9,17 @@ use bevy::{
 };
 use binary_greedy_meshing as bgm;
 
+use super::mesh_utils::{FaceVertices, box_face_vertices, cross_vertices_packed, quarter_turn_uv};
 use super::texture_array::TextureMapTrait;
+use super::tint::GrassTint;
 use rb_world::CHUNK_S1;
-use rb_block::{Block, Face};
-use rb_world::{CHUNKP_S3, Chunk, ChunkPos, WATER_H, linearize, pad_linearize};
+use rb_block::{Block, Face, Shape};
+use strum::IntoEnumIterator;
+use rb_world::{CHUNKP_S1, CHUNKP_S3, Chunk, ChunkPos, WATER_H, linearize, pad_linearize};
 
 const MASK_XYZ: u64 = 0b111111_111111_111111;
+/// Normal id given to the diagonal quads of plants, the shader lights them as if facing up
+const CROSS_NORMAL: u32 = 6;
 /// TODO: Switch to 16-chunks region instanced quads
 /// first u32:
 ///    - quad-in-chunk position: 3x6 bits (18)
@@ -41,13 +46,38 @@ const MASK_XYZ: u64 = 0b111111_111111_111111;
 pub const ATTRIBUTE_VOXEL_DATA: MeshVertexAttribute =
     MeshVertexAttribute::new("VoxelData", 48757581, VertexFormat::Uint32x2);
 
-/// Map channels between 0.0 and 1.0 to the correct range and pack them
-use super::mesh_utils::FaceVertices;
+/// Only in the mesh of non-cube shapes, it places the vertices within their block
+/// and adds to their texture coordinates, in sixteenths of a block
+/// `0bvvvvv_uuuuu_zzzzz_yyyyy_xxxxx`
+pub const ATTRIBUTE_SHAPE_DATA: MeshVertexAttribute =
+    MeshVertexAttribute::new("ShapeData", 48757582, VertexFormat::Uint32);
 
+/// Map channels between 0.0 and 1.0 to the correct range and pack them
 fn color(r: f32, g: f32, b: f32) -> u32 {
     ((r * 63.) as u32) << 11 | ((g * 63.) as u32) << 5 | (b * 31.) as u32
 }
 
+/// How far the water at the padded position `[x, y, z]` is below the top of its water column, in blocks.
+/// Water going past the top of the chunk is assumed to reach sea level at least, `height` is the height of the water in the world.
+fn water_depth(
+    voxels: &[u16],
+    block: impl Fn(u16) -> Block,
+    [x, y, z]: [usize; 3],
+    height: i32,
+    lod: usize,
+) -> i32 {
+    let mut top = y;
+    while top + 1 < CHUNKP_S1 && block(voxels[linearize(x, top + 1, z)]) == Block::SeaBlock {
+        top += 1;
+    }
+    let depth = ((top - y) * lod) as i32;
+    if top + 1 == CHUNKP_S1 {
+        depth.max(WATER_H - height)
+    } else {
+        depth
+    }
+}
+
 pub trait ChunkMeshing {
     fn voxel_data_lod(&self, lod: usize) -> Vec<u16>;
     fn create_face_meshes(
@@ -55,7 +85,14 @@ pub trait ChunkMeshing {
         texture_map: impl TextureMapTrait,
         lod: usize,
         chunk_pos: ChunkPos,
+        tint: &GrassTint,
     ) -> [Option<Mesh>; 6];
+    fn create_shape_mesh(
+        &self,
+        texture_map: impl TextureMapTrait,
+        lod: usize,
+        tint: &GrassTint,
+    ) -> Option<Mesh>;
 }
 
 impl ChunkMeshing for Chunk {
@@ -85,17 +122,36 @@ impl ChunkMeshing for Chunk {
         texture_map: impl TextureMapTrait,
         lod: usize,
         chunk_pos: ChunkPos,
+        tint: &GrassTint,
     ) -> [Option<Mesh>; 6] {
-        let cy = chunk_pos.y as usize * CHUNK_S1 as usize;
+        let cy = chunk_pos.y * CHUNK_S1 as i32;
         // Gathering binary greedy meshing input data
         let mesh_data_span = info_span!("mesh voxel data", name = "mesh voxel data").entered();
-        let voxels = self.voxel_data_lod(lod);
+        let mut voxels = self.voxel_data_lod(lod);
+        // Non-cube shapes get their own mesh and are seen as air by the greedy mesher,
+        // at lower LODs the thin ones are left out and the others are drawn as cubes
+        let shaped =
+            BTreeSet::from_iter(self.palette.iter().enumerate().filter_map(|(i, state)| {
+                let shape = state.block.shape();
+                if shape != Shape::Cube && (lod == 1 || shape.is_thin()) {
+                    Some(i as u16)
+                } else {
+                    None
+                }
+            }));
+        if !shaped.is_empty() {
+            for voxel in voxels.iter_mut() {
+                if shaped.contains(voxel) {
+                    *voxel = 0;
+                }
+            }
+        }
         let mut mesher: bgm::Mesher<CHUNK_S1> = bgm::Mesher::new();
         mesh_data_span.exit();
         let mesh_build_span = info_span!("mesh build", name = "mesh build").entered();
         let transparents =
-            BTreeSet::from_iter(self.palette.iter().enumerate().filter_map(|(i, block)| {
-                if i != 0 && !block.is_opaque() {
+            BTreeSet::from_iter(self.palette.iter().enumerate().filter_map(|(i, state)| {
+                if i != 0 && !state.block.is_opaque() {
                     Some(i as u16)
                 } else {
                     None
@@ -114,27 +170,44 @@ impl ChunkMeshing for Chunk {
                 let h = quad.height();
                 let xyz = MASK_XYZ & quad.0;
                 let [x, y, z] = quad.xyz();
-                let block = self.palette[voxel_i];
-                let neighbor_block = self.palette[voxels[linearize(
+                let state = self.palette[voxel_i];
+                let block = state.block;
+                let neighbor = [
                     (offset[0] + x as i32 + 1) as usize,
                     (offset[1] + y as i32 + 1) as usize,
                     (offset[2] + z as i32 + 1) as usize,
-                )] as usize];
+                ];
+                let neighbor_block = self.palette
+                    [voxels[linearize(neighbor[0], neighbor[1], neighbor[2])] as usize]
+                    .block;
                 kept_quads += 1;
-                let layer = texture_map.get_texture_index(block, face) as u32;
+                // oriented blocks draw another face of their textures, possibly turned
+                let (texture_face, quarter_turn) = state.texture_face(face);
+                let layer = texture_map.get_texture_index(state, texture_face) as u32;
+                let (col_x, col_z) = (x as usize * lod, z as usize * lod);
                 let (mut r, mut g, mut b) = match (block, face) {
-                    (Block::GrassBlock, Face::Up) => (0.1, 0.9, 0.2),
+                    (Block::GrassBlock, Face::Up) => tint.grass(col_x, col_z),
                     (Block::SeaBlock, _) => (0.1, 0.3, 0.7),
-                    (block, _) if block.is_foliage() => (0.1, 0.8, 0.1),
+                    (block, _) if block.is_foliage() => tint.foliage(col_x, col_z),
                     _ => (1., 1., 1.),
                 };
                 if neighbor_block == Block::SeaBlock {
-                    let dist_to_surface = (WATER_H as usize - cy - y as usize) as f32;
+                    let height = cy + (neighbor[1] as i32 - 1) * lod as i32;
+                    let dist_to_surface = water_depth(
+                        &voxels,
+                        |voxel| self.palette[voxel as usize].block,
+                        neighbor,
+                        height,
+                        lod,
+                    ) as f32;
                     r *= (-dist_to_surface * 0.05).exp();
                     g *= (-dist_to_surface * 0.045).exp();
                     b *= (-dist_to_surface * 0.04).exp();
                 }
-                let vertices = face.vertices_packed(xyz as u32, w as u32, h as u32, lod as u32);
+                let mut vertices = face.vertices_packed(xyz as u32, w as u32, h as u32, lod as u32);
+                if quarter_turn {
+                    vertices = quarter_turn_uv(vertices);
+                }
                 let quad_info = (color(r, g, b) << 15) | (layer << 3) | face_n as u32;
                 voxel_data.extend_from_slice(&[
                     [vertices[0], quad_info],
@@ -156,4 +229,154 @@ impl ChunkMeshing for Chunk {
         mesh_build_span.exit();
         meshes
     }
+
+    /// Non-cube shapes are too small to be worth drawing at lower LODs
+    fn create_shape_mesh(
+        &self,
+        texture_map: impl TextureMapTrait,
+        lod: usize,
+        tint: &GrassTint,
+    ) -> Option<Mesh> {
+        if lod != 1
+            || !self
+                .palette
+                .iter()
+                .any(|state| state.block.shape() != Shape::Cube)
+        {
+            return None;
+        }
+        let voxels = self.data.unpack_u16();
+        let mut voxel_data: Vec<[u32; 2]> = Vec::new();
+        let mut shape_data: Vec<u32> = Vec::new();
+        for y in 0..CHUNK_S1 {
+            for x in 0..CHUNK_S1 {
+                for z in 0..CHUNK_S1 {
+                    let state = self.palette[voxels[pad_linearize(x, y, z)] as usize];
+                    let block = state.block;
+                    let shape = block.shape();
+                    if shape == Shape::Cube {
+                        continue;
+                    }
+                    let (r, g, b) = match block {
+                        Block::Grass | Block::Fern | Block::Reeds => tint.grass(x, z),
+                        _ => (1., 1., 1.),
+                    };
+                    if shape == Shape::Cross {
+                        let layer = texture_map.get_texture_index(state, Face::Front) as u32;
+                        let quad_info = (color(r, g, b) << 15) | (layer << 3) | CROSS_NORMAL;
+                        for vertices in cross_vertices_packed(x as u32, y as u32, z as u32) {
+                            voxel_data.extend(vertices.map(|vertex| [vertex, quad_info]));
+                            shape_data.extend([0; 4]);
+                        }
+                        continue;
+                    }
+                    let neighbor = |face: Face| {
+                        let [dx, dy, dz] = face.n();
+                        self.palette[voxels[linearize(
+                            (x as i32 + 1 + dx) as usize,
+                            (y as i32 + 1 + dy) as usize,
+                            (z as i32 + 1 + dz) as usize,
+                        )] as usize]
+                            .block
+                    };
+                    for shape_box in state.boxes(neighbor) {
+                        for face in Face::iter() {
+                            // the sides of the box against opaque cubes can't be seen
+                            if shape_box.touches(face) {
+                                let neighbor = neighbor(face);
+                                if neighbor.is_opaque() && neighbor.shape() == Shape::Cube {
+                                    continue;
+                                }
+                            }
+                            let (texture_face, _) = state.texture_face(face);
+                            let layer = texture_map.get_texture_index(state, texture_face) as u32;
+                            let quad_info =
+                                (color(r, g, b) << 15) | (layer << 3) | u8::from(face) as u32;
+                            for (vertex, data) in
+                                box_face_vertices(x as u32, y as u32, z as u32, shape_box, face)
+                            {
+                                voxel_data.push([vertex, quad_info]);
+                                shape_data.push(data);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if voxel_data.is_empty() {
+            return None;
+        }
+        let indices = bgm::indices(voxel_data.len() / 4);
+        Some(
+            Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::RENDER_WORLD,
+            )
+            .with_inserted_attribute(ATTRIBUTE_VOXEL_DATA, voxel_data)
+            .with_inserted_attribute(ATTRIBUTE_SHAPE_DATA, shape_data)
+            .with_inserted_indices(Indices::U32(indices)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tint::ColorTable;
+    use bevy::mesh::VertexAttributeValues;
+    use rb_block::BlockState;
+    use rb_world::{ChunkedPos, Realm};
+
+    struct SingleTexture;
+
+    impl TextureMapTrait for SingleTexture {
+        fn get_texture_index(&self, _state: BlockState, _face: Face) -> usize {
+            0
+        }
+    }
+
+    #[test]
+    fn lake_above_sea_level() {
+        // the chunk starts above sea level, with a lake 3 blocks deep in it
+        let chunk_pos = ChunkPos {
+            x: 0,
+            y: 1,
+            z: 0,
+            realm: Realm::Overworld,
+        };
+        assert!(chunk_pos.y * CHUNK_S1 as i32 > WATER_H);
+        let mut chunk = Chunk::new();
+        chunk.set(ChunkedPos { x: 5, y: 0, z: 5 }, Block::Dirt.into());
+        for y in 1..=3 {
+            chunk.set(ChunkedPos { x: 5, y, z: 5 }, Block::SeaBlock.into());
+        }
+        let colors = ColorTable::from_csv(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../assets/gen/grass_color.csv"
+        ));
+        let tint = GrassTint {
+            colors: &colors,
+            climate: None,
+        };
+        let meshes = chunk.create_face_meshes(SingleTexture, 1, chunk_pos, &tint);
+        let up = (0..6).find(|&i| Face::from(i) == Face::Up).unwrap();
+        let Some(VertexAttributeValues::Uint32x2(data)) = meshes[up]
+            .as_ref()
+            .and_then(|mesh| mesh.attribute(ATTRIBUTE_VOXEL_DATA))
+        else {
+            panic!("The up faces have no voxel data");
+        };
+        // the water touching the dirt is 2 blocks below the top of the lake
+        let dirt_color = color(
+            (-2f32 * 0.05).exp(),
+            (-2f32 * 0.045).exp(),
+            (-2f32 * 0.04).exp(),
+        );
+        // the top of the dirt is tinted by the water above it, not by the distance to sea level
+        assert!(
+            data.iter()
+                .any(|[_, quad_info]| quad_info >> 15 == dirt_color)
+        );
+        assert!(data.iter().all(|[_, quad_info]| quad_info >> 15 != 0));
+    }
 }
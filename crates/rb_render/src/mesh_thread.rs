@@ -1,6 +1,7 @@
 use crate::mesh_draw::{LOD, choose_lod_level};
 use crate::mesh_logic::ChunkMeshing;
 use crate::texture_array::TextureMap;
+use crate::tint::{ColorTable, GrassTint};
 use bevy::prelude::*;
 use bevy::tasks::AsyncComputeTaskPool;
 use crossbeam::channel::{Receiver, Sender, unbounded};
@@ -21,6 +22,7 @@ pub fn setup_mesh_thread(
 ) {
     let thread_pool = AsyncComputeTaskPool::get();
     let chunks = voxel_world.chunks.clone();
+    let climates = voxel_world.climates.clone();
     let (mesh_sender, mesh_reciever) = unbounded();
     commands.insert_resource(MeshReciever(mesh_reciever));
     let texture_map = texture_map.0.clone();
@@ -32,6 +34,7 @@ pub fn setup_mesh_thread(
             while texture_map.len() == 0 {
                 yield_now()
             }
+            let grass_colors = ColorTable::from_csv("assets/gen/grass_color.csv");
             let mut mesh_cache: HashSet<ChunkPos> = HashSet::new();
             let mut mesh_orders: Vec<ChunkPos> = Vec::new();
             'outer: loop {
@@ -70,15 +73,26 @@ pub fn setup_mesh_thread(
                 let Some(chunk) = chunks.get(&chunk_pos) else {
                     continue;
                 };
-                let face_meshes =
-                    chunk
-                        .value()
-                        .read()
-                        .create_face_meshes(&texture_map, lod, chunk_pos);
-                for (i, face_mesh) in face_meshes.into_iter().enumerate() {
-                    let face = i.into();
+                let climate = climates.get(&chunk_pos.into());
+                let tint = GrassTint {
+                    colors: &grass_colors,
+                    climate: climate.as_ref().map(|entry| entry.value()),
+                };
+                let (face_meshes, shape_mesh) = {
+                    let chunk = chunk.value().read();
+                    (
+                        chunk.create_face_meshes(&texture_map, lod, chunk_pos, &tint),
+                        chunk.create_shape_mesh(&texture_map, lod, &tint),
+                    )
+                };
+                let meshes = face_meshes
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, face_mesh)| (face_mesh, Some(Face::from(i))))
+                    .chain([(shape_mesh, None)]);
+                for (mesh, face) in meshes {
                     if mesh_sender
-                        .send((face_mesh, chunk_pos, face, LOD(lod)))
+                        .send((mesh, chunk_pos, face, LOD(lod)))
                         .is_err()
                     {
                         warn!("Mesh channel is closed, stopping mesh thread");
@@ -90,8 +104,9 @@ pub fn setup_mesh_thread(
         .detach();
 }
 
+/// The face is None for the mesh of non-cube shapes, which can be seen from every side
 #[derive(Resource)]
-pub struct MeshReciever(pub Receiver<(Option<Mesh>, ChunkPos, Face, LOD)>);
+pub struct MeshReciever(pub Receiver<(Option<Mesh>, ChunkPos, Option<Face>, LOD)>);
 
 #[derive(Resource)]
 pub struct MeshOrderSender(pub Sender<ChunkPos>);
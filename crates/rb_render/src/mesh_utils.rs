@@ -1,4 +1,4 @@
-use rb_block::Face;
+use rb_block::{Face, ShapeBox};
 // Note: This whole file will become unnecessary when we have instancing,
 // because it is used to convert a quad to 4 vertices which we won't need to do
 
@@ -10,6 +10,73 @@ fn vertex_info(xyz: u32, u: u32, v: u32) -> u32 {
     (v << 24) | (u << 18) | xyz
 }
 
+/// Offset of a vertex in its block and texture coordinates added to the ones of the vertex, in sixteenths of a block
+/// `0bvvvvv_uuuuu_zzzzz_yyyyy_xxxxx`
+fn shape_data(offset: [u32; 3], u: u32, v: u32) -> u32 {
+    (v << 20) | (u << 15) | (offset[2] << 10) | (offset[1] << 5) | offset[0]
+}
+
+/// Corners of a face of a block, in the same order as the vertices of `vertices_packed`,
+/// and the axis the texture coordinates u and v follow, reversed or not
+fn face_corners(face: Face) -> ([[u8; 3]; 4], [(usize, bool); 2]) {
+    match face {
+        Face::Left => ([[0, 0, 0], [0, 0, 1], [0, 1, 0], [0, 1, 1]], [(2, true), (1, true)]),
+        Face::Down => ([[0, 0, 1], [0, 0, 0], [1, 0, 1], [1, 0, 0]], [(0, true), (2, false)]),
+        Face::Back => ([[0, 0, 0], [0, 1, 0], [1, 0, 0], [1, 1, 0]], [(0, true), (1, true)]),
+        Face::Right => ([[1, 1, 0], [1, 1, 1], [1, 0, 0], [1, 0, 1]], [(2, false), (1, true)]),
+        Face::Up => ([[1, 1, 1], [1, 1, 0], [0, 1, 1], [0, 1, 0]], [(0, false), (2, false)]),
+        Face::Front => ([[0, 1, 1], [0, 0, 1], [1, 1, 1], [1, 0, 1]], [(0, false), (1, true)]),
+    }
+}
+
+/// Packed vertices of a face of a box of the block at x, y, z, along with their shape data.
+/// The texture is cut like the box so the faces of a shape line up with the faces of cubes.
+pub fn box_face_vertices(x: u32, y: u32, z: u32, shape_box: ShapeBox, face: Face) -> [(u32, u32); 4] {
+    let (corners, uv_axis) = face_corners(face);
+    corners.map(|corner| {
+        let offset: [u32; 3] = core::array::from_fn(|axis| {
+            (if corner[axis] == 0 { shape_box.min[axis] } else { shape_box.max[axis] }) as u32
+        });
+        let [u, v] = uv_axis.map(|(axis, reversed)| if reversed { 16 - offset[axis] } else { offset[axis] });
+        (packed_xyz(x, y, z), shape_data(offset, u, v))
+    })
+}
+
+/// Turns the texture of a quad by a quarter, the corners stay in place.
+/// The texture repeats along both sides so it works for merged quads as well.
+pub fn quarter_turn_uv(vertices: [u32; 4]) -> [u32; 4] {
+    let uv = |vertex: u32| ((vertex >> 18) & 0b111111, (vertex >> 24) & 0b111111);
+    let max_u = vertices.iter().map(|&vertex| uv(vertex).0).max().unwrap_or(0);
+    vertices.map(|vertex| {
+        let (u, v) = uv(vertex);
+        (vertex & !(0xfff << 18)) | vertex_info(0, v, max_u - u)
+    })
+}
+
+/// Corner of the block where each diagonal quad starts, and the horizontal direction it spans.
+/// Each diagonal is covered twice with opposite windings so plants can be seen from both sides.
+const CROSS_QUADS: [((u32, u32), (i32, i32)); 4] = [
+    ((0, 0), (1, 1)),
+    ((1, 1), (-1, -1)),
+    ((0, 1), (1, -1)),
+    ((1, 0), (-1, 1)),
+];
+
+/// Packed vertices of the 2 crossed quads (both sides) used to draw a plant at x, y, z
+pub fn cross_vertices_packed(x: u32, y: u32, z: u32) -> [[u32; 4]; 4] {
+    CROSS_QUADS.map(|((cx, cz), (dx, dz))| {
+        let start = packed_xyz(x + cx, y, z + cz);
+        let end = packed_xyz(((x + cx) as i32 + dx) as u32, y, ((z + cz) as i32 + dz) as u32);
+        // same layout as a Back face, with the diagonal standing in for the x axis
+        [
+            vertex_info(start, 1, 1),
+            vertex_info(start + packed_xyz(0, 1, 0), 1, 0),
+            vertex_info(end, 0, 1),
+            vertex_info(end + packed_xyz(0, 1, 0), 0, 0),
+        ]
+    })
+}
+
 pub trait FaceVertices {
     fn vertices_packed(&self, xyz: u32, w: u32, h: u32, lod: u32) -> [u32; 4];
 }
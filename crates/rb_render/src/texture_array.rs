@@ -1,5 +1,9 @@
-use super::{BlockTexState, BlockTextureFolder, mesh_logic::ATTRIBUTE_VOXEL_DATA, parse_block_tex_name};
-use rb_block::{Block, Face, FaceSpecifier};
+use super::{
+    BlockTexState, BlockTextureFolder,
+    mesh_logic::{ATTRIBUTE_SHAPE_DATA, ATTRIBUTE_VOXEL_DATA},
+    parse_block_tex_name,
+};
+use rb_block::{Block, BlockState, Face, FaceSpecifier, Lit};
 use bevy::{
     asset::{LoadedFolder, RenderAssetUsages},
     mesh::MeshVertexBufferLayoutRef,
@@ -27,21 +31,25 @@ impl Plugin for TextureArrayPlugin {
 }
 
 #[derive(Resource)]
-pub struct TextureMap(pub HashMap<(Block, FaceSpecifier), usize>);
+pub struct TextureMap(pub HashMap<(Block, Lit, FaceSpecifier), usize>);
 
 pub trait TextureMapTrait {
-    fn get_texture_index(&self, block: Block, face: Face) -> usize;
+    fn get_texture_index(&self, state: BlockState, face: Face) -> usize;
 }
 
-impl TextureMapTrait for &HashMap<(Block, FaceSpecifier), usize> {
+impl TextureMapTrait for &HashMap<(Block, Lit, FaceSpecifier), usize> {
     // TODO: need to allow the user to create a json with "texture files links" such as:
     // grass_block_bottom.png -> dirt.png
     // furnace_bottom.png -> stone.png
     // etc ...
-    fn get_texture_index(&self, block: Block, face: Face) -> usize {
-        for specifier in face.specifiers() {
-            if let Some(i) = self.get(&(block, *specifier)) {
-                return *i;
+    fn get_texture_index(&self, state: BlockState, face: Face) -> usize {
+        // lit blocks without a lit texture fall back on their unlit one
+        let lit = state.lit().unwrap_or(Lit::Off);
+        for lit in [lit, Lit::Off] {
+            for specifier in face.specifiers() {
+                if let Some(i) = self.get(&(state.block, lit, *specifier)) {
+                    return *i;
+                }
             }
         }
         0
@@ -98,11 +106,11 @@ fn build_tex_array(
             continue;
         };
         let filename = handle.path().unwrap().path().file_stem().unwrap();
-        let Some((block, face_specifier)) = parse_block_tex_name(filename) else {
+        let Some((block, lit, face_specifier)) = parse_block_tex_name(filename) else {
             continue;
         };
         let frames = texture.height() / texture.width();
-        texture_map.0.insert((block, face_specifier), index);
+        texture_map.0.insert((block, lit, face_specifier), index);
         texture_list.push(texture);
         if block == Block::SeaBlock {
             water_layer = Some(index);
@@ -201,9 +209,13 @@ impl MaterialExtension for ArrayTextureMaterial {
         layout: &MeshVertexBufferLayoutRef,
         _key: MaterialExtensionKey<ArrayTextureMaterial>,
     ) -> Result<(), bevy::render::render_resource::SpecializedMeshPipelineError> {
-        let vertex_layout = layout
-            .0
-            .get_layout(&[ATTRIBUTE_VOXEL_DATA.at_shader_location(0)])?;
+        let mut attributes = vec![ATTRIBUTE_VOXEL_DATA.at_shader_location(0)];
+        // the mesh of non-cube shapes places its vertices within their blocks
+        if layout.0.contains(ATTRIBUTE_SHAPE_DATA) {
+            attributes.push(ATTRIBUTE_SHAPE_DATA.at_shader_location(1));
+            descriptor.vertex.shader_defs.push("SHAPE_DATA".into());
+        }
+        let vertex_layout = layout.0.get_layout(&attributes)?;
         descriptor.vertex.buffers = vec![vertex_layout];
         Ok(())
     }
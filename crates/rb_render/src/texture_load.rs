@@ -1,6 +1,6 @@
 use std::ffi::OsStr;
 use bevy::{asset::LoadedFolder, prelude::*};
-use rb_block::{Block, Face, FaceSpecifier};
+use rb_block::{Block, Face, FaceSpecifier, Lit};
 use rb_asset_processing::from_filename;
 
 const DIGITS: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
@@ -61,7 +61,8 @@ pub enum ItemTexState {
 pub struct ItemTextureFolder(pub Handle<LoadedFolder>);
 
 
-pub fn parse_block_tex_name(filename: &OsStr) -> Option<(Block, FaceSpecifier)> {
+/// Block textures are named `<block>[_on][_<face>]`, `_on` being the texture of the lit block
+pub fn parse_block_tex_name(filename: &OsStr) -> Option<(Block, Lit, FaceSpecifier)> {
     let filename = filename.to_str()?.trim_end_matches(DIGITS);
     let (block, face) = match filename.rsplit_once("_") {
         Some((block, "side")) => (block, FaceSpecifier::Side),
@@ -71,7 +72,11 @@ pub fn parse_block_tex_name(filename: &OsStr) -> Option<(Block, FaceSpecifier)>
         Some((block, "back")) => (block, FaceSpecifier::Specific(Face::Back)),
         _ => (filename, FaceSpecifier::All),
     };
-    Some((from_filename(block)?, face))
+    let (block, lit) = match block.strip_suffix("_on") {
+        Some(block) => (block, Lit::On),
+        None => (block, Lit::Off),
+    };
+    Some((from_filename(block)?, lit, face))
 }
 
 fn load_item_textures(mut commands: Commands, asset_server: Res<AssetServer>) {
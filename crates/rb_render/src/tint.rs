@@ -0,0 +1,85 @@
+use rb_world::{CHUNK_S1, ChunkedPos2d, Climate, ColClimate};
+/// Used when the climate of a column is unknown
+const DEFAULT_GRASS: (f32, f32, f32) = (0.1, 0.9, 0.2);
+/// Leaves are a bit darker than the grass of the same climate
+const FOLIAGE_SHADE: f32 = 0.8;
+
+fn hex_color(str: &str) -> (f32, f32, f32) {
+    let channels: Vec<f32> = str
+        .trim()
+        .trim_start_matches('#')
+        .chars()
+        .map(|c| {
+            c.to_digit(16)
+                .unwrap_or_else(|| panic!("Failed to parse hex color '{}'", str)) as f32
+                / 15.
+        })
+        .collect();
+    (channels[0], channels[1], channels[2])
+}
+
+/// Colors placed at points of the (temperature, humidity) space, interpolated in between
+pub struct ColorTable(Vec<([f32; 2], (f32, f32, f32))>);
+
+impl ColorTable {
+    pub fn from_csv(path: &str) -> Self {
+        let mut points = Vec::new();
+        let mut reader = csv::Reader::from_path(path).unwrap();
+        for record in reader.records() {
+            let record = record.unwrap();
+            let point: [f32; 2] =
+                core::array::from_fn(|i| record[i + 1].trim().parse::<f32>().unwrap());
+            points.push((point, hex_color(&record[0])));
+        }
+        Self(points)
+    }
+
+    /// Inverse distance weighting of the colors of the table
+    pub fn color(&self, temperature: f32, humidity: f32) -> (f32, f32, f32) {
+        let mut total = 0.;
+        let mut res = (0., 0., 0.);
+        for ([t, h], (r, g, b)) in self.0.iter() {
+            let dist2 = (t - temperature).powi(2) + (h - humidity).powi(2);
+            if dist2 < f32::EPSILON {
+                return (*r, *g, *b);
+            }
+            let weight = 1. / dist2;
+            res.0 += r * weight;
+            res.1 += g * weight;
+            res.2 += b * weight;
+            total += weight;
+        }
+        if total == 0. {
+            return DEFAULT_GRASS;
+        }
+        (res.0 / total, res.1 / total, res.2 / total)
+    }
+}
+
+/// Grass and foliage colors of a chunk column
+pub struct GrassTint<'a> {
+    pub colors: &'a ColorTable,
+    pub climate: Option<&'a ColClimate>,
+}
+
+impl<'a> GrassTint<'a> {
+    pub fn grass(&self, x: usize, z: usize) -> (f32, f32, f32) {
+        let Some(climate) = self.climate else {
+            return DEFAULT_GRASS;
+        };
+        let Climate {
+            temperature,
+            humidity,
+            ..
+        } = climate.get(ChunkedPos2d {
+            x: x.min(CHUNK_S1 - 1),
+            z: z.min(CHUNK_S1 - 1),
+        });
+        self.colors.color(temperature, humidity)
+    }
+
+    pub fn foliage(&self, x: usize, z: usize) -> (f32, f32, f32) {
+        let (r, g, b) = self.grass(x, z);
+        (r * FOLIAGE_SHADE, g * FOLIAGE_SHADE, b * FOLIAGE_SHADE)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,88 @@
+use super::{
+    GameUiState,
+    game_menu::despawn_screen,
+    ui_tex_map::{UiSlotKind, UiTextureMap},
+};
+use crate::item_slots::UISlot;
+use bevy::prelude::*;
+use itertools::Itertools;
+use rb_agents::{Chest, HOTBAR_SLOTS, OpenChest};
+use rb_items::ItemHolder;
+
+pub struct ChestMenuPlugin;
+
+impl Plugin for ChestMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(OpenChest(None))
+            .add_systems(OnEnter(GameUiState::ChestMenu), open_chest_menu)
+            .add_systems(OnExit(GameUiState::ChestMenu), despawn_screen::<ChestMenu>);
+    }
+}
+
+#[derive(Component)]
+struct ChestMenu;
+
+/// Shows the slots of the chest in rows as wide as the hotbar, right above it
+fn open_chest_menu(
+    mut commands: Commands,
+    tex_map: Res<UiTextureMap>,
+    open_chest: Res<OpenChest>,
+    chest_query: Query<(&Chest, &ItemHolder)>,
+) {
+    let Some(chest_entt) = open_chest.0 else {
+        return;
+    };
+    let Ok((chest, ItemHolder::Inventory(slots))) = chest_query.get(chest_entt) else {
+        return;
+    };
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            bottom: Val::Percent(12.),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Vw(0.5)),
+                        ..Default::default()
+                    },
+                    BackgroundColor(Color::LinearRgba(LinearRgba::new(0., 0., 0., 0.9))),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(&chest.name),
+                        TextFont {
+                            font_size: 40.,
+                            ..Default::default()
+                        },
+                    ));
+                    for row in &slots.iter().enumerate().chunks(HOTBAR_SLOTS) {
+                        parent
+                            .spawn(Node {
+                                flex_direction: FlexDirection::Row,
+                                ..Default::default()
+                            })
+                            .with_children(|node| {
+                                for (slot_id, stack) in row {
+                                    node.spawn(Node {
+                                        margin: UiRect::all(Val::Vw(0.2)),
+                                        ..Default::default()
+                                    })
+                                    .insert(Interaction::default())
+                                    .insert(UISlot(chest_entt, slot_id))
+                                    .with_children(|node| {
+                                        tex_map.make_item_slot(node, stack, UiSlotKind::Default)
+                                    });
+                                }
+                            });
+                    }
+                });
+        })
+        .insert(ChestMenu);
+}
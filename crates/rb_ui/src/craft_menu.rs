@@ -266,6 +266,8 @@ fn craft_action(
     for (slot, qty) in selection.iter() {
         let _ = hotbar[*slot].take(*qty);
     }
-    hotbar.try_add(Stack::Some(recipe.out.0, recipe.out.1));
+    for stack in Stack::new_many(recipe.out.0, recipe.out.1) {
+        hotbar.try_add(stack);
+    }
     commands.trigger(ItemGet { entity: player });
 }
@@ -4,7 +4,7 @@ use bevy::prelude::*;
 use rb_agents::TargetBlock;
 use rb_block::Block;
 use rb_camera::PlayerControlled;
-use rb_world::VoxelWorld;
+use rb_world::{BlockPos2d, Realm, VoxelWorld};
 use std::time::Duration;
 
 pub struct DebugDisplayPlugin;
@@ -16,6 +16,7 @@ impl Plugin for DebugDisplayPlugin {
             .add_systems(Update, update_fps_display)
             .add_systems(Update, update_entt_display)
             .add_systems(Update, update_pos_display)
+            .add_systems(Update, update_climate_display)
             .add_systems(Update, update_block_display);
     }
 }
@@ -26,6 +27,9 @@ struct DebugTextFPS;
 #[derive(Component)]
 struct DebugTextPos;
 
+#[derive(Component)]
+struct DebugTextClimate;
+
 #[derive(Component)]
 struct DebugTextBlock;
 
@@ -51,6 +55,7 @@ fn setup_debug_display(mut commands: Commands, asset_server: Res<AssetServer>) {
         .with_children(|parent| {
             parent.spawn((Text::new("FPS: "), DebugTextFPS));
             parent.spawn((Text::new("p: "), DebugTextPos));
+            parent.spawn((Text::new("biome: "), DebugTextClimate));
             parent.spawn((Text::new("block: "), DebugTextBlock));
             parent.spawn((Text::new("E: "), DebugTextEntities));
         });
@@ -101,6 +106,31 @@ fn update_pos_display(
     }
 }
 
+fn update_climate_display(
+    mut climate_text_query: Query<&mut Text, With<DebugTextClimate>>,
+    player_query: Query<(&Transform, &Realm), With<PlayerControlled>>,
+    world: Res<VoxelWorld>,
+) {
+    let (transform, realm) = player_query.single().unwrap();
+    let pos = BlockPos2d::from((transform.translation, *realm));
+    let Ok(mut climate_text) = climate_text_query.single_mut() else {
+        return;
+    };
+    climate_text.0 = match world.climate_at(pos) {
+        Some(climate) => format!(
+            "biome: {:?} (t: {:.2}, h: {:.2}, ph: {:.2}, c: {:.2}, m: {:.2}, trees: {:.2})\n",
+            climate.biome,
+            climate.temperature,
+            climate.humidity,
+            climate.ph,
+            climate.continentalness,
+            climate.mountainness,
+            climate.trees
+        ),
+        None => "biome: -\n".to_string(),
+    };
+}
+
 fn update_block_display(
     player_query: Query<&TargetBlock, With<PlayerControlled>>,
     mut block_text_query: Query<&mut Text, With<DebugTextBlock>>,
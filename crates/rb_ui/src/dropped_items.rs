@@ -0,0 +1,69 @@
+use super::ui_tex_map::UiTextureMap;
+use bevy::prelude::*;
+use rb_agents::{DROPPED_ITEM_SIZE, DroppedItem};
+use rb_camera::FpsCam;
+
+/// Side of the quad showing a dropped item, a bit bigger than its collision box so it's easy to spot
+const SPRITE_SIZE: f32 = 0.4;
+
+pub struct DroppedItemsPlugin;
+
+impl Plugin for DroppedItemsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_sprite_mesh)
+            .add_systems(Update, (add_item_sprites, face_camera).chain());
+    }
+}
+
+#[derive(Resource)]
+struct SpriteMesh(Handle<Mesh>);
+
+/// The quad showing the texture of a dropped item, always facing the camera
+#[derive(Component)]
+struct ItemSprite;
+
+fn setup_sprite_mesh(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    commands.insert_resource(SpriteMesh(
+        meshes.add(Rectangle::new(SPRITE_SIZE, SPRITE_SIZE)),
+    ));
+}
+
+fn add_item_sprites(
+    mut commands: Commands,
+    dropped_items: Query<(Entity, &DroppedItem), Added<DroppedItem>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    sprite_mesh: Res<SpriteMesh>,
+    tex_map: Res<UiTextureMap>,
+) {
+    for (entity, dropped) in dropped_items.iter() {
+        let material_handle = materials.add(StandardMaterial {
+            base_color_texture: Some(tex_map.get_texture(&dropped.stack)),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            cull_mode: None,
+            double_sided: true,
+            ..default()
+        });
+        commands.entity(entity).with_children(|item| {
+            item.spawn((
+                Mesh3d(sprite_mesh.0.clone()),
+                MeshMaterial3d(material_handle),
+                Transform::from_translation(Vec3::splat(DROPPED_ITEM_SIZE / 2.)),
+                ItemSprite,
+            ));
+        });
+    }
+}
+
+fn face_camera(
+    cam_query: Query<&GlobalTransform, With<FpsCam>>,
+    mut sprites: Query<&mut Transform, With<ItemSprite>>,
+) {
+    let Ok(cam_transform) = cam_query.single() else {
+        return;
+    };
+    let (_, rotation, _) = cam_transform.to_scale_rotation_translation();
+    for mut transform in sprites.iter_mut() {
+        transform.rotation = rotation;
+    }
+}
@@ -1,11 +1,12 @@
 use super::{
     Inventory,
-    ui_tex_map::{SLOT_SIZE_PERCENT, UiSlotKind, UiTextureMap},
+    ui_tex_map::{DurabilityBar, SLOT_SIZE_PERCENT, UiSlotKind, UiTextureMap, durability_bar},
 };
 use bevy::prelude::*;
 use rb_agents::Dragging;
 use rb_items::{ItemHolder, Stack};
 use rb_render::ItemTexState;
+use std::mem;
 
 /// (entity holding the ItemHolder, slot index)
 #[derive(Component, Clone, Copy)]
@@ -32,13 +33,34 @@ struct DraggingNode;
 
 fn item_slot_click(
     mut interaction_query: Query<(&Interaction, &UISlot), Changed<Interaction>>,
+    slot_query: Query<&UISlot>,
     mut dragging: ResMut<Dragging>,
     mut item_holders: Query<&mut ItemHolder>,
+    keys: Res<ButtonInput<KeyCode>>,
 ) {
     for (interaction, UISlot(item_holder_entt, slot_id)) in interaction_query.iter_mut() {
         if *interaction != Interaction::Pressed {
             continue;
         }
+        if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            // the stack goes to the other holder on screen, the chest or furnace if it's from the player and vice versa
+            let Some(UISlot(other_entt, _)) = slot_query
+                .iter()
+                .find(|UISlot(entt, _)| entt != item_holder_entt)
+            else {
+                continue;
+            };
+            let Ok([mut clicked_item_holder, mut other_item_holder]) =
+                item_holders.get_many_mut([*item_holder_entt, *other_entt])
+            else {
+                continue;
+            };
+            let stack = mem::take(clicked_item_holder.get_mut(*slot_id));
+            if let Some(rest) = other_item_holder.try_add(stack) {
+                *clicked_item_holder.get_mut(*slot_id) = rest;
+            }
+            continue;
+        }
         let Ok(mut clicked_item_holder) = item_holders.get_mut(*item_holder_entt) else {
             continue;
         };
@@ -59,6 +81,7 @@ fn refresh_slot_items(
     node_query: Query<(&UISlot, &Children)>,
     mut img_query: Query<&mut ImageNode>,
     mut text_query: Query<&mut Text>,
+    mut bar_query: Query<(&mut Node, &mut BackgroundColor), With<DurabilityBar>>,
     tex_map: Res<UiTextureMap>,
     item_query: Query<&ItemHolder, Changed<ItemHolder>>,
 ) {
@@ -79,6 +102,9 @@ fn refresh_slot_items(
                     quantity.to_string()
                 };
             }
+            if let Ok((mut node, mut color)) = bar_query.get_mut(*child) {
+                (*node, *color) = durability_bar(stack);
+            }
         }
     }
 }
@@ -118,6 +144,7 @@ fn refresh_dragging_ui(
     dragging_node_query: Query<&Children, With<DraggingNode>>,
     mut img_query: Query<&mut ImageNode>,
     mut text_query: Query<&mut Text>,
+    mut bar_query: Query<(&mut Node, &mut BackgroundColor), With<DurabilityBar>>,
     tex_map: Res<UiTextureMap>,
 ) {
     if !dragging.is_changed() {
@@ -138,6 +165,9 @@ fn refresh_dragging_ui(
                 quantity.to_string()
             };
         }
+        if let Ok((mut node, mut color)) = bar_query.get_mut(*child) {
+            (*node, *color) = durability_bar(&dragging.0);
+        }
     }
 }
 
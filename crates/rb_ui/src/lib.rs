@@ -1,6 +1,8 @@
+mod chest_menu;
 mod craft_menu;
 mod crosshair;
 mod debug_display;
+mod dropped_items;
 mod effects;
 mod furnace_menu;
 mod game_menu;
@@ -12,9 +14,11 @@ use bevy::{
     prelude::*,
     window::{CursorGrabMode, CursorIcon, CursorOptions, SystemCursorIcon},
 };
+use chest_menu::ChestMenuPlugin;
 use craft_menu::CraftMenuPlugin;
 use crosshair::setup_crosshair;
 use debug_display::DebugDisplayPlugin;
+use dropped_items::DroppedItemsPlugin;
 use furnace_menu::FurnaceMenuPlugin;
 use game_menu::MenuPlugin;
 use hotbar::HotbarPlugin;
@@ -22,7 +26,7 @@ use in_hand::InHandPlugin;
 pub use item_slots::*;
 use leafwing_input_manager::prelude::*;
 pub use rb_agents::{
-    CursorGrabbed, Dragging, GameUiState, Inventory, OpenFurnace, ScrollGrabbed,
+    CursorGrabbed, Dragging, GameUiState, Inventory, OpenChest, OpenFurnace, ScrollGrabbed,
     SelectedHotbarSlot, UIAction,
 };
 use ui_tex_map::UiTexMapPlugin;
@@ -45,7 +49,9 @@ impl Plugin for UIPlugin {
             .add_plugins(MenuPlugin)
             .add_plugins(CraftMenuPlugin)
             .add_plugins(InHandPlugin)
+            .add_plugins(DroppedItemsPlugin)
             .add_plugins(FurnaceMenuPlugin)
+            .add_plugins(ChestMenuPlugin)
             .add_plugins(EffectsPlugin)
             .add_systems(Startup, setup_ui_actions)
             .add_systems(Startup, setup_crosshair)
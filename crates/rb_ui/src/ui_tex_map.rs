@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use bevy::{asset::LoadedFolder, color::palettes::css, image::TRANSPARENT_IMAGE_HANDLE, prelude::*};
 use itertools::Itertools;
 use rb_asset_processing::from_filename;
-use rb_block::{Block, Face, FaceSpecifier};
+use rb_block::{Block, Face, FaceSpecifier, Lit};
 use rb_items::{Item, Stack};
 use rb_render::{parse_block_tex_name, BlockTexState, BlockTextureFolder, ItemTexState, ItemTextureFolder};
 pub const SLOT_SIZE_PERCENT: f32 = 4.;
@@ -36,10 +36,31 @@ struct UiTextureMapState {
 #[derive(Resource)]
 pub struct UiTextureMap(HashMap<Item, Handle<Image>>);
 
+/// The bar under a tool that shows how much durability it has left
+#[derive(Component)]
+pub struct DurabilityBar;
+
+/// The node and color of the durability bar of a stack, the bar is hidden if the stack isn't a tool
+pub fn durability_bar(stack: &Stack) -> (Node, BackgroundColor) {
+    let left = stack.durability_left().unwrap_or(1.);
+    (
+        Node {
+            display: if stack.durability_left().is_some() { Display::Flex } else { Display::None },
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(8.),
+            left: Val::Percent(10.),
+            width: Val::Percent(80. * left),
+            height: Val::Percent(6.),
+            ..Default::default()
+        },
+        BackgroundColor(Color::linear_rgb(1. - left, left, 0.)),
+    )
+}
+
 impl UiTextureMap {
     pub fn get_texture(&self, stack: &Stack) -> Handle<Image> {
         match stack {
-			Stack::Some(item, _) => self.0.get(item).cloned(),
+			Stack::Some(item, _) | Stack::Tool(item, _) => self.0.get(item).cloned(),
 			Stack::None => None,
 		}.unwrap_or(TRANSPARENT_IMAGE_HANDLE)
     }
@@ -72,6 +93,7 @@ impl UiTextureMap {
                 Color::linear_rgba(0., 0., 0., 0.7)
             })
         ));
+        node.spawn((DurabilityBar, durability_bar(stack)));
         let qty = stack.quantity();
 		node.spawn((
             Text::new(if qty > 1 { qty.to_string() } else { String::new() }),
@@ -139,7 +161,8 @@ fn load_ui_block_textures(
     let mut tex_map: HashMap<(Block, FaceSpecifier), Handle<Image>> = HashMap::new();
     for block_handle in block_folder.handles.iter() {        
         let filename = block_handle.path().unwrap().path().file_stem().unwrap();
-        let Some((block, face_specifier)) = parse_block_tex_name(filename) else {
+        // items show the unlit block
+        let Some((block, Lit::Off, face_specifier)) = parse_block_tex_name(filename) else {
             continue;
         };
 		tex_map.insert((block, face_specifier), block_handle.clone().try_typed().unwrap());
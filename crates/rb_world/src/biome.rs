@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumString;
+
+#[derive(
+    Debug, Clone, Copy, EnumString, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Biome {
+    PolarOcean,
+    Canyon,
+    Ocean,
+    Plain,
+    Mountain,
+    Desert,
+    Tundra,
+    Savannah,
+    Jungle,
+}
+
+impl Biome {
+    pub fn is_ocean(&self) -> bool {
+        matches!(self, Biome::Ocean | Biome::PolarOcean)
+    }
+
+    /// Average color of the biome seen from afar, used by the horizon tiles
+    pub fn color(&self) -> [f32; 3] {
+        match self {
+            Biome::PolarOcean => [0.55, 0.65, 0.8],
+            Biome::Canyon => [0.75, 0.45, 0.3],
+            Biome::Ocean => [0.1, 0.25, 0.55],
+            Biome::Plain => [0.35, 0.6, 0.25],
+            Biome::Mountain => [0.5, 0.5, 0.5],
+            Biome::Desert => [0.85, 0.78, 0.55],
+            Biome::Tundra => [0.8, 0.85, 0.85],
+            Biome::Savannah => [0.65, 0.6, 0.3],
+            Biome::Jungle => [0.15, 0.45, 0.15],
+        }
+    }
+}
@@ -2,12 +2,12 @@ use crate::CHUNK_S1I;
 use crate::{CHUNK_S1, CHUNKP_S1, CHUNKP_S2, CHUNKP_S3, ChunkedPos, ChunkedPos2d, utils::Palette};
 use itertools::Itertools;
 use packed_uints::PackedUints;
-use rb_block::{Block, Face};
+use rb_block::{Block, BlockState, Face};
 
 #[derive(Debug)]
 pub struct Chunk {
     pub data: PackedUints,
-    pub palette: Palette<Block>,
+    pub palette: Palette<BlockState>,
 }
 
 pub fn linearize(x: usize, y: usize, z: usize) -> usize {
@@ -19,23 +19,23 @@ pub fn pad_linearize(x: usize, y: usize, z: usize) -> usize {
 }
 
 impl Chunk {
-    pub fn get(&self, chunked_pos: ChunkedPos) -> &Block {
+    pub fn get(&self, chunked_pos: ChunkedPos) -> &BlockState {
         &self.palette[self
             .data
             .get(pad_linearize(chunked_pos.x, chunked_pos.y, chunked_pos.z))]
     }
 
-    pub fn set(&mut self, chunked_pos: ChunkedPos, block: Block) {
+    pub fn set(&mut self, chunked_pos: ChunkedPos, block: BlockState) {
         let idx = pad_linearize(chunked_pos.x, chunked_pos.y, chunked_pos.z);
         self.data.set(idx, self.palette.index(block));
     }
 
-    pub fn set_unpadded(&mut self, chunked_pos: ChunkedPos, block: Block) {
+    pub fn set_unpadded(&mut self, chunked_pos: ChunkedPos, block: BlockState) {
         let idx = linearize(chunked_pos.x, chunked_pos.y, chunked_pos.z);
         self.data.set(idx, self.palette.index(block));
     }
 
-    pub fn set_yrange(&mut self, chunked_pos: ChunkedPos, height: usize, block: Block) {
+    pub fn set_yrange(&mut self, chunked_pos: ChunkedPos, height: usize, block: BlockState) {
         let value = self.palette.index(block);
         self.data.set_range_step(
             pad_linearize(chunked_pos.x, chunked_pos.y - height, chunked_pos.z),
@@ -45,7 +45,7 @@ impl Chunk {
         );
     }
 
-    pub fn top(&self, in_column_pos: ChunkedPos2d) -> (&Block, usize) {
+    pub fn top(&self, in_column_pos: ChunkedPos2d) -> (&BlockState, usize) {
         for y in (0..CHUNK_S1).rev() {
             let b_idx = self
                 .data
@@ -57,9 +57,9 @@ impl Chunk {
         (&self.palette[0], 0)
     }
 
-    pub fn set_if_empty(&mut self, chunked_pos: ChunkedPos, block: Block) -> bool {
+    pub fn set_if_empty(&mut self, chunked_pos: ChunkedPos, block: BlockState) -> bool {
         let idx = pad_linearize(chunked_pos.x, chunked_pos.y, chunked_pos.z);
-        if self.palette[self.data.get(idx)] != Block::Air {
+        if self.palette[self.data.get(idx)].block != Block::Air {
             return false;
         }
         self.data.set(idx, self.palette.index(block));
@@ -96,7 +96,7 @@ impl Chunk {
                 let value = if let Some(val) = translation[other_value] {
                     val
                 } else {
-                    self.palette.index(other.palette[other_value].clone())
+                    self.palette.index(other.palette[other_value])
                 };
                 self.data.set(self_i, value);
                 self_i += row_step;
@@ -111,10 +111,10 @@ impl Chunk {
 impl From<&[Block]> for Chunk {
     fn from(values: &[Block]) -> Self {
         let mut palette = Palette::new();
-        palette.index(Block::Air);
+        palette.index(Block::Air.into());
         let values = values
             .iter()
-            .map(|v| palette.index(v.clone()))
+            .map(|v| palette.index((*v).into()))
             .collect_vec();
         let data = PackedUints::from(values.as_slice());
         Chunk { data, palette }
@@ -124,7 +124,7 @@ impl From<&[Block]> for Chunk {
 impl Chunk {
     pub fn new() -> Self {
         let mut palette = Palette::new();
-        palette.index(Block::Air);
+        palette.index(Block::Air.into());
         Chunk {
             data: PackedUints::new(CHUNKP_S3),
             palette: palette,
@@ -134,8 +134,8 @@ impl Chunk {
 
 #[cfg(test)]
 mod tests {
-    use crate::{CHUNK_S1, CHUNK_S1I, CHUNKP_S1, CHUNKP_S2, linearize};
-    use rb_block::Face;
+    use crate::{CHUNK_S1, CHUNK_S1I, CHUNKP_S1, CHUNKP_S2, Chunk, ChunkedPos, linearize};
+    use rb_block::{Axis, Block, BlockState, Face};
 
     fn plane(face: Face) -> [usize; 3] {
         match face {
@@ -209,4 +209,21 @@ mod tests {
         assert_chunk_face_indices(Face::Up);
         assert_chunk_face_indices(Face::Front);
     }
+
+    #[test]
+    fn test_states_in_palette() {
+        let mut chunk = Chunk::new();
+        let pos = ChunkedPos { x: 1, y: 2, z: 3 };
+        let lying = BlockState::from(Block::OakLog).with_axis(Axis::X);
+        chunk.set(pos, lying);
+        chunk.set(ChunkedPos { x: 1, y: 3, z: 3 }, Block::OakLog.into());
+        assert_eq!(*chunk.get(pos), lying);
+        assert_eq!(chunk.get(pos).axis(), Some(Axis::X));
+        assert_eq!(
+            chunk.get(ChunkedPos { x: 1, y: 3, z: 3 }).axis(),
+            Some(Axis::Y)
+        );
+        // air, and the 2 states of the log
+        assert_eq!(chunk.palette.iter().count(), 3);
+    }
 }
@@ -0,0 +1,42 @@
+use crate::{Biome, CHUNK_S1, ChunkedPos2d};
+
+/// Climate of a single block column, every biome parameter is in [0; 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Climate {
+    pub continentalness: f32,
+    pub mountainness: f32,
+    pub temperature: f32,
+    pub humidity: f32,
+    pub ph: f32,
+    pub trees: f32,
+    pub biome: Biome,
+}
+
+/// Biome parameters and dominant biome of every block column of a chunk column,
+/// kept after generation for rendering tints and gameplay.
+/// Every Vec is indexed by `dx + dz * CHUNK_S1`.
+#[derive(Debug, Clone)]
+pub struct ColClimate {
+    pub continentalness: Vec<f32>,
+    pub mountainness: Vec<f32>,
+    pub temperature: Vec<f32>,
+    pub humidity: Vec<f32>,
+    pub ph: Vec<f32>,
+    pub trees: Vec<f32>,
+    pub biomes: Vec<Biome>,
+}
+
+impl ColClimate {
+    pub fn get(&self, pos: ChunkedPos2d) -> Climate {
+        let i = pos.x + pos.z * CHUNK_S1;
+        Climate {
+            continentalness: self.continentalness[i],
+            mountainness: self.mountainness[i],
+            temperature: self.temperature[i],
+            humidity: self.humidity[i],
+            ph: self.ph[i],
+            trees: self.trees[i],
+            biome: self.biomes[i],
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{CHUNK_S1, ChunkPos2d};
+use bevy::prelude::Resource;
+use crossbeam::channel::Receiver;
+
+/// Chunk columns past the render distance and up to this distance are drawn as coarse heightfield tiles
+pub const HORIZON_DISTANCE: i32 = 64;
+/// Cells per side of a horizon tile
+pub const HORIZON_RES: usize = 8;
+pub const HORIZON_CELL: f32 = CHUNK_S1 as f32 / HORIZON_RES as f32;
+
+/// Low detail stand-in for a chunk column too far to be generated.
+/// Cells are indexed by `x + z * HORIZON_RES`.
+pub struct HorizonTile {
+    pub col: ChunkPos2d,
+    /// Height of the surface (water included) at the center of every cell
+    pub heights: Vec<i32>,
+    pub colors: Vec<[f32; 3]>,
+}
+
+/// Block column at the center of a cell, along one axis
+pub fn horizon_cell_center(i: usize) -> usize {
+    ((i as f32 + 0.5) * HORIZON_CELL) as usize
+}
+
+#[derive(Resource)]
+pub struct HorizonTileReceiver(pub Receiver<HorizonTile>);
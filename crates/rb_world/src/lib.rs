@@ -1,12 +1,18 @@
+mod biome;
 mod block_entities;
 mod chunk;
+mod col_climate;
+mod horizon;
 mod load_area;
 mod utils;
 mod voxel_world;
 use bevy::prelude::*;
+pub use biome::Biome;
 pub use block_entities::BlockEntities;
 pub use block_entities::unload_block_entities;
 pub use chunk::*;
+pub use col_climate::{ColClimate, Climate};
+pub use horizon::*;
 pub use load_area::*;
 use rand_chacha::ChaCha8Rng;
 pub use rb_pos::*;
@@ -20,6 +26,10 @@ pub struct PlayerCol(pub ChunkPos2d);
 #[derive(Message)]
 pub struct ColUnloadEvent(pub ChunkPos2d);
 
+/// Sent once a column is fully generated
+#[derive(Message)]
+pub struct ColLoadEvent(pub ChunkPos2d);
+
 #[derive(Resource)]
 pub struct WorldRng {
     pub seed: u64,
@@ -1,6 +1,6 @@
 use crate::{
     BlockPos, BlockPos2d, CHUNK_S1, CHUNKP_S1, Chunk, ChunkPos, ChunkPos2d, ChunkedPos,
-    ChunkedPos2d, MAX_HEIGHT, Realm, Y_CHUNKS, chunked, pos2d::chunks_in_col,
+    ChunkedPos2d, Climate, ColClimate, MAX_HEIGHT, Realm, Y_CHUNKS, chunked, pos2d::chunks_in_col,
 };
 use bevy::{
     log::warn,
@@ -9,7 +9,7 @@ use bevy::{
 use crossbeam::channel::Sender;
 use crossbeam_skiplist::{SkipMap, SkipSet, map::Entry};
 use parking_lot::RwLock;
-use rb_block::{Block, Face};
+use rb_block::{Block, BlockState, Face, Shape};
 use std::sync::Arc;
 
 pub struct BlockRayCastHit {
@@ -26,12 +26,11 @@ impl PartialEq for BlockRayCastHit {
 #[derive(Resource, Clone)]
 pub struct VoxelWorld {
     pub chunks: Arc<SkipMap<ChunkPos, RwLock<Chunk>>>,
-    /// Mark columns that eventually should have data
-    /// (they may not having it yet because of async loading)
+    /// Columns that are fully generated, changes to the other columns are not propagated
+    /// because they will be synced with their neighbors once generation is done
     pub loaded_columns: Arc<SkipSet<ChunkPos2d>>,
-    /// Mark columns that eventually shouldn't have data
-    /// (they may have it because of structure generation writing to neighboring chunks)
-    pub unloaded_columns: Arc<SkipSet<ChunkPos2d>>,
+    /// Climate of the generated columns, used to tint grass and foliage and by gameplay
+    pub climates: Arc<SkipMap<ChunkPos2d, ColClimate>>,
     chunk_changes: Sender<ChunkPos>,
 }
 
@@ -40,12 +39,14 @@ impl VoxelWorld {
         VoxelWorld {
             chunks: Arc::new(SkipMap::new()),
             loaded_columns: Arc::new(SkipSet::new()),
-            unloaded_columns: Arc::new(SkipSet::new()),
+            climates: Arc::new(SkipMap::new()),
             chunk_changes,
         }
     }
 
-    pub fn set_block(&self, pos: BlockPos, block: Block) {
+    /// Blocks are set with the default value of their states unless a `BlockState` is given
+    pub fn set_block(&self, pos: BlockPos, block: impl Into<BlockState>) {
+        let block = block.into();
         let (chunk_pos, chunked_pos) = <(ChunkPos, ChunkedPos)>::from(pos);
         self.chunks
             .get_or_insert_with(chunk_pos, || RwLock::new(Chunk::new()))
@@ -55,7 +56,7 @@ impl VoxelWorld {
         self.mark_change(chunk_pos, chunked_pos, block);
     }
 
-    pub fn set_block_safe(&self, pos: BlockPos, block: Block) -> bool {
+    pub fn set_block_safe(&self, pos: BlockPos, block: impl Into<BlockState>) -> bool {
         if pos.y < 0 || pos.y >= MAX_HEIGHT as i32 {
             return false;
         }
@@ -92,7 +93,7 @@ impl VoxelWorld {
                         z: in_col_pos.z,
                     },
                     h,
-                    block,
+                    block.into(),
                 );
             height -= h;
             cy -= 1;
@@ -107,28 +108,53 @@ impl VoxelWorld {
             .get_or_insert_with(chunk_pos, || RwLock::new(Chunk::new()))
             .value()
             .write()
-            .set_if_empty(chunked_pos, block)
+            .set_if_empty(chunked_pos, block.into())
         {
-            self.mark_change(chunk_pos, chunked_pos, block);
+            self.mark_change(chunk_pos, chunked_pos, block.into());
         }
     }
 
     pub fn get_block(&self, pos: BlockPos) -> Block {
+        self.get_block_state(pos).block
+    }
+
+    pub fn get_block_state(&self, pos: BlockPos) -> BlockState {
         let (chunk_pos, chunked_pos) = <(ChunkPos, ChunkedPos)>::from(pos);
         match self.chunks.get(&chunk_pos) {
-            None => Block::Air,
-            Some(chunk) => chunk.value().read().get(chunked_pos).clone(),
+            None => Block::Air.into(),
+            Some(chunk) => *chunk.value().read().get(chunked_pos),
         }
     }
 
+    /// Replaces the block but keeps its state, for blocks that turn into one another like an ore being depleted
+    pub fn turn_into(&self, pos: BlockPos, block: Block) {
+        let state = self.get_block_state(pos);
+        self.set_block(pos, BlockState { block, ..state });
+    }
+
+    /// Changes the state of the block in place, like a furnace being lit or a crop growing
+    pub fn update_state(&self, pos: BlockPos, update: impl FnOnce(BlockState) -> BlockState) {
+        self.set_block(pos, update(self.get_block_state(pos)));
+    }
+
     pub fn get_block_safe(&self, pos: BlockPos) -> Block {
+        self.get_block_state_safe(pos).block
+    }
+
+    pub fn get_block_state_safe(&self, pos: BlockPos) -> BlockState {
         if pos.y < 0 || pos.y >= MAX_HEIGHT as i32 {
-            Block::Air
+            Block::Air.into()
         } else {
-            self.get_block(pos)
+            self.get_block_state(pos)
         }
     }
 
+    /// Returns None if the column hasn't been generated yet
+    pub fn climate_at(&self, pos: BlockPos2d) -> Option<Climate> {
+        let (col, pos): (ChunkPos2d, ChunkedPos2d) = pos.into();
+        self.climates.get(&col).map(|entry| entry.value().get(pos))
+    }
+
     pub fn top_block(&self, pos: BlockPos2d) -> (Block, i32) {
         let (col_pos, pos2d) = pos.into();
         for y in (0..Y_CHUNKS as i32).rev() {
@@ -140,14 +166,39 @@ impl VoxelWorld {
             };
             if let Some(chunk) = self.chunks.get(&chunk_pos) {
                 let (&block, block_y) = chunk.value().read().top(pos2d);
-                if block != Block::Air {
-                    return (block.clone(), y * CHUNK_S1 as i32 + block_y as i32);
+                if block.block != Block::Air {
+                    return (block.block, y * CHUNK_S1 as i32 + block_y as i32);
                 }
             }
         }
         (Block::Air, 0)
     }
 
+    /// Positions of the blocks of a column that match, chunks without any matching block in their palette are skipped
+    pub fn find_blocks(&self, col: ChunkPos2d, matches: impl Fn(Block) -> bool) -> Vec<BlockPos> {
+        let mut found = Vec::new();
+        for chunk_pos in chunks_in_col(&col) {
+            let Some(chunk) = self.chunks.get(&chunk_pos) else {
+                continue;
+            };
+            let chunk = chunk.value().read();
+            if !chunk.palette.iter().any(|state| matches(state.block)) {
+                continue;
+            }
+            for y in 0..CHUNK_S1 {
+                for z in 0..CHUNK_S1 {
+                    for x in 0..CHUNK_S1 {
+                        let chunked_pos = ChunkedPos { x, y, z };
+                        if matches(chunk.get(chunked_pos).block) {
+                            found.push(BlockPos::from((chunk_pos, chunked_pos)));
+                        }
+                    }
+                }
+            }
+        }
+        found
+    }
+
     pub fn is_col_loaded(&self, player_pos: Vec3, realm: Realm) -> bool {
         let (chunk_pos, _): (ChunkPos, _) = <BlockPos>::from((player_pos, realm)).into();
         for y in (0..Y_CHUNKS as i32).rev() {
@@ -180,15 +231,19 @@ impl VoxelWorld {
         let Some(other) = self.chunks.get(&other_pos) else {
             return;
         };
-        chunk
-            .value()
-            .write()
-            .copy_side_from(&other.value().read(), face);
-        other
-            .value()
-            .write()
-            .copy_side_from(&chunk.value().read(), face.opposite());
-        if send_change {
+        // Terrain workers can sync the two sides of a border at the same time,
+        // locking both chunks in position order prevents them from deadlocking
+        let (mut this, mut that) = if chunk_pos < other_pos {
+            let this = chunk.value().write();
+            (this, other.value().write())
+        } else {
+            let that = other.value().write();
+            (chunk.value().write(), that)
+        };
+        this.copy_side_from(&that, face);
+        that.copy_side_from(&this, face.opposite());
+        // neighbors that are still being generated will be sent once they're done
+        if send_change && self.loaded_columns.contains(&other_pos.into()) {
             self.chunk_changes
                 .send(other_pos)
                 .expect("Failed to send chunk change");
@@ -220,7 +275,8 @@ impl VoxelWorld {
     }
 
     pub fn unload_col(&self, col: ChunkPos2d) {
-        self.unloaded_columns.remove(&col);
+        self.loaded_columns.remove(&col);
+        self.climates.remove(&col);
         for y in 0..Y_CHUNKS as i32 {
             let chunk_pos = ChunkPos {
                 x: col.x,
@@ -243,12 +299,9 @@ impl VoxelWorld {
     }
 
     /// Mark a block change, reflecting in neighboring chunks if needed
-    fn mark_change(&self, chunk_pos: ChunkPos, chunked_pos: ChunkedPos, block: Block) {
-        // If the chunk is not supposed to be loaded (can happen in structure generation acting into neighboring chunks),
-        // mark it for unloading straight away so the memory gets cleaned up
-        // and skip sending the change
+    fn mark_change(&self, chunk_pos: ChunkPos, chunked_pos: ChunkedPos, block: BlockState) {
+        // The column is still being generated, it will be synced with its neighbors when done
         if !self.loaded_columns.contains(&chunk_pos.into()) {
-            self.unloaded_columns.insert(chunk_pos.into());
             return;
         }
         if let Err(_) = self.chunk_changes.send(chunk_pos) {
@@ -367,15 +420,36 @@ impl VoxelWorld {
                 pos.z += sz;
                 t_max_z += slope_z;
             }
-            if self.get_block_safe(pos).is_targetable() {
-                return Some(BlockRayCastHit {
-                    pos,
-                    normal: Vec3 {
-                        x: (last_pos.x - pos.x) as f32,
-                        y: (last_pos.y - pos.y) as f32,
-                        z: (last_pos.z - pos.z) as f32,
-                    },
-                });
+            let state = self.get_block_state_safe(pos);
+            if state.block.is_targetable() {
+                if state.block.shape() == Shape::Cube {
+                    return Some(BlockRayCastHit {
+                        pos,
+                        normal: Vec3 {
+                            x: (last_pos.x - pos.x) as f32,
+                            y: (last_pos.y - pos.y) as f32,
+                            z: (last_pos.z - pos.z) as f32,
+                        },
+                    });
+                }
+                // the ray can go past the boxes of the other shapes
+                let corner: Vec3 = pos.into();
+                let hit = state
+                    .boxes(|face| {
+                        let [dx, dy, dz] = face.n();
+                        self.get_block_safe(pos + (dx, dy, dz))
+                    })
+                    .iter()
+                    .filter_map(|shape_box| shape_box.ray_hit(start - corner, dir))
+                    .min_by(|(t1, _), (t2, _)| t1.total_cmp(t2));
+                if let Some((t, face)) = hit
+                    && t < dist
+                {
+                    return Some(BlockRayCastHit {
+                        pos,
+                        normal: Vec3::from(face.n().map(|n| n as f32)),
+                    });
+                }
             }
             if grazing && grazed_block.is_none() {
                 for &grazing_dir in &grazing_dirs {
@@ -8,6 +8,7 @@ use crossbeam::channel::unbounded;
 use rand_chacha::{ChaCha8Rng, rand_core::SeedableRng};
 use rb_agents::{PlayerPlugin, TerrainLoadPlugin};
 use rb_camera::Camera3dPlugin;
+use rb_generation::{SPAWN_POINTS_PATH, SpawnPoints, WORLD_META_PATH, WorldMeta};
 use rb_logging::RiverbedLogPlugin;
 use rb_physics::MovementPlugin;
 use rb_render::{MeshOrderReceiver, MeshOrderSender, RenderPlugin, TextureLoadPlugin};
@@ -15,9 +16,11 @@ use rb_sounds::SoundPlugin;
 use rb_ui::UIPlugin;
 use rb_world::{VoxelWorld, WorldRng};
 
-const SEED: u64 = 42;
-
 fn main() {
+    let world_meta = WorldMeta::load_or_create(WORLD_META_PATH, std::env::args().skip(1))
+        .unwrap_or_else(|err| panic!("{err:#}"));
+    let spawn_points = SpawnPoints::load_or_create(SPAWN_POINTS_PATH, &world_meta)
+        .unwrap_or_else(|err| panic!("{err:#}"));
     let mut app = App::new();
     let (mesh_order_sender, mesh_order_receiver) = unbounded();
     app.insert_resource(VoxelWorld::new(mesh_order_sender.clone()))
@@ -47,9 +50,11 @@ fn main() {
         )
         .add_plugins(RiverbedLogPlugin)
         .insert_resource(WorldRng {
-            seed: SEED,
-            rng: ChaCha8Rng::seed_from_u64(SEED),
+            seed: world_meta.seed,
+            rng: ChaCha8Rng::seed_from_u64(world_meta.seed),
         })
+        .insert_resource(world_meta)
+        .insert_resource(spawn_points)
         .add_plugins(PlayerPlugin)
         .add_plugins(Camera3dPlugin)
         .add_plugins(TextureLoadPlugin)